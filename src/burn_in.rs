@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+
+use crate::capture;
+use crate::heatmap::Heatmap;
+use crate::monitor_info::{get_all_monitors_info, get_display_device_info, monitor_device_name};
+use crate::mover;
+use crate::settings_path::settings_path;
+use crate::static_regions::Frame;
+
+/// How often the monitors are sampled, burn-in builds up over hours so a sample now and then is plenty
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the heatmaps are written to disk, they are written on exit too
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Width of the samples, the height follows the aspect ratio of the monitor
+const SAMPLE_WIDTH: i32 = 128;
+
+/// How often the thread wakes up to check whether the app exits
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+lazy_static! {
+    /// Monitor device id => heatmap, loaded from disk on first use
+    static ref HEATMAPS: Mutex<Option<HashMap<String, Heatmap>>> = Mutex::new(None);
+
+    static ref THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Set while the heatmaps are recorded. Synced from the settings by the controller.
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Set when the app exits, the recorder thread saves the heatmaps and stops
+static CLOSED: AtomicBool = AtomicBool::new(false);
+
+fn heatmaps_path() -> PathBuf {
+    return settings_path().with_file_name("heatmap.json");
+}
+
+/// Reads the heatmaps from disk, an empty set if there are none yet or they can't be read
+fn load() -> HashMap<String, Heatmap> {
+    let file = match File::open(heatmaps_path()) {
+        Ok(file) => file,
+        Err(_) => return HashMap::new(),
+    };
+    let mut heatmaps: HashMap<String, Heatmap> = serde_json::from_reader(file).unwrap_or_else(|err| {
+        log::warn!("Failed to read the heatmaps, starting over: {}", err);
+        HashMap::new()
+    });
+
+    // A heatmap without a cell for every pixel can't be drawn or added to, its monitor starts over
+    heatmaps.retain(|device_id, heatmap| {
+        if !heatmap.is_intact() {
+            log::warn!("The heatmap of {} is damaged, starting it over", device_id);
+        }
+        return heatmap.is_intact();
+    });
+    return heatmaps;
+}
+
+/// Writes the heatmaps to a temporary file first and then swaps it in, so a crash halfway doesn't leave a truncated file behind
+fn save(heatmaps: &HashMap<String, Heatmap>) {
+    let path = heatmaps_path();
+    let temp_path = path.with_extension("json.tmp");
+    let result = serde_json::to_string(heatmaps)
+        .map_err(|err| err.to_string())
+        .and_then(|json| {
+            fs::write(&temp_path, json)
+                .and_then(|_| fs::rename(&temp_path, &path))
+                .map_err(|err| err.to_string())
+        });
+    if let Err(err) = result {
+        log::error!("Failed to save the heatmaps: {}", err);
+    }
+}
+
+/// Runs the closure on the heatmaps, loading them first if needed
+fn with_heatmaps<T>(f: impl FnOnce(&mut HashMap<String, Heatmap>) -> T) -> T {
+    let mut heatmaps = HEATMAPS.lock().unwrap();
+    return f(heatmaps.get_or_insert_with(load));
+}
+
+/// Turns the recording on or off, the heatmaps recorded so far are kept either way
+pub fn configure(recording: bool) {
+    RECORDING.store(recording, Ordering::Relaxed);
+}
+
+/// Starts recording on a thread of its own
+pub fn start() {
+    let spawned = thread::Builder::new().name("burn_in_thread".to_string()).spawn(run);
+    match spawned {
+        Ok(handle) => *THREAD.lock().unwrap() = Some(handle),
+        Err(err) => log::error!("Failed to start the burn-in thread: {}", err),
+    }
+}
+
+/// Saves the heatmaps and stops recording, for when the app exits
+pub fn close() {
+    CLOSED.store(true, Ordering::Relaxed);
+    if let Some(handle) = THREAD.lock().unwrap().take() {
+        let _ = handle.join();
+    }
+}
+
+/// Returns a copy of the heatmaps, monitor device id => heatmap
+pub fn heatmaps() -> HashMap<String, Heatmap> {
+    return with_heatmaps(|heatmaps| heatmaps.clone());
+}
+
+/// Forgets the heatmap of the monitor and starts it over
+pub fn reset(device_id: &str) {
+    with_heatmaps(|heatmaps| {
+        heatmaps.remove(device_id);
+        save(heatmaps);
+    });
+}
+
+/// Samples every enabled monitor and adds the samples to their heatmaps
+fn sample(elapsed: Duration) {
+    for info in get_all_monitors_info() {
+        if !mover::is_monitor_enabled(&info) {
+            continue;
+        }
+
+        let raw_name = monitor_device_name(&info);
+        let device_id = get_display_device_info(&raw_name).map(|(_, device_id)| device_id).unwrap_or(raw_name);
+
+        let rect = info.rcMonitor;
+        let (monitor_width, monitor_height) = (rect.right - rect.left, rect.bottom - rect.top);
+        if monitor_width <= 0 || monitor_height <= 0 {
+            continue;
+        }
+        let width = SAMPLE_WIDTH;
+        let height = (SAMPLE_WIDTH * monitor_height / monitor_width).max(1);
+
+        let luma = match capture::capture_luma(&rect, width, height) {
+            Some(luma) => luma,
+            None => continue,
+        };
+        let frame = Frame { width: width as usize, height: height as usize, luma: &luma };
+
+        with_heatmaps(|heatmaps| {
+            heatmaps.entry(device_id)
+                .or_insert_with(|| Heatmap::new(width as usize, height as usize))
+                .update(&frame, elapsed);
+        });
+    }
+}
+
+fn run() {
+    let mut last_sample: Option<Instant> = None;
+    let mut last_save = Instant::now();
+    let mut unsaved = false;
+
+    loop {
+        if CLOSED.load(Ordering::Relaxed) {
+            if unsaved {
+                with_heatmaps(|heatmaps| save(heatmaps));
+            }
+            return;
+        }
+
+        if !RECORDING.load(Ordering::Relaxed) {
+            last_sample = None;
+        } else if last_sample.map_or(true, |last| last.elapsed() >= SAMPLE_INTERVAL) {
+            let elapsed = last_sample.map(|last| last.elapsed()).unwrap_or_default();
+            last_sample = Some(Instant::now());
+            sample(elapsed);
+            unsaved = true;
+        }
+
+        if unsaved && last_save.elapsed() >= SAVE_INTERVAL {
+            with_heatmaps(|heatmaps| save(heatmaps));
+            last_save = Instant::now();
+            unsaved = false;
+        }
+
+        thread::sleep(CHECK_INTERVAL);
+    }
+}
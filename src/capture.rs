@@ -0,0 +1,70 @@
+use std::{mem, ptr};
+
+use winapi::ctypes::c_void;
+use winapi::shared::windef::{HGDIOBJ, RECT};
+use winapi::um::wingdi::{
+    CreateCompatibleDC,
+    CreateDIBSection,
+    DeleteDC,
+    DeleteObject,
+    SelectObject,
+    SetBrushOrgEx,
+    SetStretchBltMode,
+    StretchBlt,
+    BITMAPINFO,
+    BITMAPINFOHEADER,
+    BI_RGB,
+    DIB_RGB_COLORS,
+    HALFTONE,
+    SRCCOPY,
+};
+use winapi::um::winuser::{GetDC, ReleaseDC};
+
+use crate::static_regions;
+
+/// Captures the rect of the screen scaled down to the given size, as one luminance byte per pixel
+pub fn capture_luma(rect: &RECT, width: i32, height: i32) -> Option<Vec<u8>> {
+    let mut info: BITMAPINFO = unsafe { mem::zeroed() };
+    info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as u32;
+    info.bmiHeader.biWidth = width;
+    info.bmiHeader.biHeight = -height; // Top-down
+    info.bmiHeader.biPlanes = 1;
+    info.bmiHeader.biBitCount = 32;
+    info.bmiHeader.biCompression = BI_RGB;
+
+    unsafe {
+        let screen = GetDC(ptr::null_mut());
+        let dc = CreateCompatibleDC(screen);
+        let mut bits: *mut c_void = ptr::null_mut();
+        let bitmap = CreateDIBSection(dc, &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0);
+
+        let mut luma = None;
+        if !dc.is_null() && !bitmap.is_null() && !bits.is_null() {
+            let previous = SelectObject(dc, bitmap as HGDIOBJ);
+            // Halftone averages the pixels instead of picking one, so small changes still show up
+            SetStretchBltMode(dc, HALFTONE);
+            SetBrushOrgEx(dc, 0, 0, ptr::null_mut());
+            let copied = StretchBlt(
+                dc, 0, 0, width, height,
+                screen, rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top,
+                SRCCOPY,
+            );
+            if copied != 0 {
+                let pixels = std::slice::from_raw_parts(bits as *const u8, (width * height * 4) as usize);
+                luma = Some(pixels.chunks_exact(4)
+                    .map(|bgra| static_regions::luminance(bgra[2], bgra[1], bgra[0]))
+                    .collect());
+            }
+            SelectObject(dc, previous);
+        }
+
+        if !bitmap.is_null() {
+            DeleteObject(bitmap as HGDIOBJ);
+        }
+        if !dc.is_null() {
+            DeleteDC(dc);
+        }
+        ReleaseDC(ptr::null_mut(), screen);
+        return luma;
+    }
+}
@@ -1,12 +1,28 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
-
+use std::time::{Duration, SystemTime};
+
+use crate::burn_in;
+use crate::desktop;
+use crate::dimmer;
+use crate::hotkeys::HotkeyAction;
+use crate::i18n::{tr, Text};
+use crate::ipc::{Control, ControllerEvent};
+use crate::logging;
+use crate::maximized;
 use crate::monitor_info::{get_all_monitors_info, get_display_device_info, monitor_device_name};
 use crate::mover;
-use crate::settings::SettingsManager;
+use crate::orbit;
+use crate::settings::{Settings, SettingsManager, LOWEST_DELAY, LOWEST_MAX_DISTANCE, MAX_DELAY};
+use crate::taskbar;
 use lazy_static::lazy_static;
+use serde_json::Value;
+use winapi::shared::minwindef::FILETIME;
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::sysinfoapi::{GetLocalTime, GetSystemTimeAsFileTime};
+use winapi::um::timezoneapi::{FileTimeToSystemTime, SystemTimeToFileTime, TzSpecificLocalTimeToSystemTime};
 
 /// The delays that can be selected from the tray menu, in milliseconds
 pub enum Delays {
@@ -68,6 +84,74 @@ impl Distances {
     }
 }
 
+/// The timed pauses that can be selected from the tray menu
+pub enum Pauses {
+    FifteenMinutes,
+    OneHour,
+    UntilTomorrow,
+}
+
+impl Pauses {
+    /// Returns how long the pause lasts from now on
+    pub fn as_duration(&self) -> Duration {
+        match self {
+            Pauses::FifteenMinutes => Duration::from_secs(15 * 60),
+            Pauses::OneHour => Duration::from_secs(60 * 60),
+            Pauses::UntilTomorrow => Pauses::until_midnight(),
+        }
+    }
+
+    /// Returns the time left until the next local midnight.
+    /// Midnight is converted to UTC with the time zone rules of that day, so a night the clocks change on is an hour shorter or longer.
+    fn until_midnight() -> Duration {
+        let mut now: SYSTEMTIME = unsafe { std::mem::zeroed() };
+        unsafe { GetLocalTime(&mut now) };
+
+        let elapsed_today = now.wHour as u64 * 3600 + now.wMinute as u64 * 60 + now.wSecond as u64;
+        let naive = Duration::from_secs(24 * 3600 - elapsed_today);
+        return Pauses::until_local_midnight_after(now).unwrap_or(naive);
+    }
+
+    /// Returns the time from now until the midnight that ends the local date, None if Windows can't convert it
+    fn until_local_midnight_after(today: SYSTEMTIME) -> Option<Duration> {
+        const TICKS_PER_SECOND: u64 = 10_000_000;
+
+        // Tomorrow's date is found by adding a day to today's midnight, as a local time without any time zone yet
+        let today_midnight = SYSTEMTIME { wHour: 0, wMinute: 0, wSecond: 0, wMilliseconds: 0, ..today };
+        let mut ticks: FILETIME = unsafe { std::mem::zeroed() };
+        if unsafe { SystemTimeToFileTime(&today_midnight, &mut ticks) } == 0 {
+            return None;
+        }
+        let tomorrow = filetime_ticks(&ticks) + 24 * 3600 * TICKS_PER_SECOND;
+        let ticks = FILETIME { dwLowDateTime: tomorrow as u32, dwHighDateTime: (tomorrow >> 32) as u32 };
+
+        let mut local_midnight: SYSTEMTIME = unsafe { std::mem::zeroed() };
+        let mut utc_midnight: SYSTEMTIME = unsafe { std::mem::zeroed() };
+        let mut midnight: FILETIME = unsafe { std::mem::zeroed() };
+        let converted = unsafe {
+            FileTimeToSystemTime(&ticks, &mut local_midnight) != 0
+                && TzSpecificLocalTimeToSystemTime(std::ptr::null(), &local_midnight, &mut utc_midnight) != 0
+                && SystemTimeToFileTime(&utc_midnight, &mut midnight) != 0
+        };
+        if !converted {
+            return None;
+        }
+
+        let mut now: FILETIME = unsafe { std::mem::zeroed() };
+        unsafe { GetSystemTimeAsFileTime(&mut now) };
+        let remaining = filetime_ticks(&midnight).checked_sub(filetime_ticks(&now))?;
+        return Some(Duration::from_nanos(remaining * 100));
+    }
+}
+
+/// Returns the 100 nanosecond ticks of a FILETIME as one number
+fn filetime_ticks(time: &FILETIME) -> u64 {
+    return ((time.dwHighDateTime as u64) << 32) | time.dwLowDateTime as u64;
+}
+
+/// Extra time the mover thread sleeps past the end of a pause, so it doesn't wake up a hair too early
+const PAUSE_END_SLACK: Duration = Duration::from_millis(100);
+
 lazy_static! {
     // This is a global variable that can be accessed from anywhere in the program
     // It wasn't possible to pass the max move to the mover function since it's used in a C style callback, so this is the next best thing
@@ -75,18 +159,43 @@ lazy_static! {
 
     // Same as above, but for the enabled monitors
     pub static ref ENABLED_MONITORS: Mutex<HashMap<String, bool>> = Mutex::new(HashMap::new());
+
+    // Same as above, how long a move glides, zero moves the windows instantly
+    pub static ref GLIDE_DURATION: Mutex<Duration> = Mutex::new(Duration::ZERO);
+
+    // Same as above, whether snapped windows are moved along with the windows they share edges with
+    pub static ref SNAP_GROUPS: Mutex<bool> = Mutex::new(false);
+
+    // Same as above, whether every window of a monitor is moved by the same offset
+    pub static ref COHERENT_SHIFT: Mutex<bool> = Mutex::new(false);
+}
+
+/// Why the mover thread was woken up before its interval ran out
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WakeReason {
+    /// The interval or the running state changed, start the countdown over
+    Reschedule,
+    /// Run a pass right away, then start the countdown over
+    ShiftNow,
+    /// Move the windows of the last pass back, then start the countdown over
+    Undo,
+    /// Move all the windows back to where the user left them, then start the countdown over
+    RestoreWindows,
 }
 
 pub(crate) struct Controller {
     settings_manager: SettingsManager,
-    condvar: Arc<(Mutex<bool>, Condvar)>
+    /// The actions the mover thread has yet to run, in the order they were asked for
+    condvar: Arc<(Mutex<VecDeque<WakeReason>>, Condvar)>,
+    listeners: Vec<Sender<ControllerEvent>>,
 }
 
 impl Default for Controller {
     fn default() -> Self {
         let controller = Controller {
             settings_manager: SettingsManager::default(),
-            condvar: Arc::new((Mutex::new(false), Condvar::new())),
+            condvar: Arc::new((Mutex::new(VecDeque::new()), Condvar::new())),
+            listeners: Vec::new(),
         };
         controller.update_max_move();
         return controller;
@@ -106,6 +215,17 @@ impl Controller {
         // Sync the controller state with the settings file
         *ENABLED_MONITORS.lock().unwrap() = controller.get_all_monitors();
         controller.update_max_move();
+        controller.update_glide_duration();
+        controller.update_snap_groups();
+        controller.update_coherent_shift();
+        controller.update_log_levels();
+        controller.update_taskbar();
+        controller.update_dimmer();
+        controller.update_burn_in();
+        controller.update_orbit();
+        controller.update_nudge_rules();
+        // A timed pause may have ended while the app wasn't running
+        controller.resume_if_pause_ended();
     }
 
     pub fn run(controller: Arc<Mutex<Self>>) {
@@ -115,48 +235,231 @@ impl Controller {
             loop {
                 let interval = {
                     let controller = controller.lock().unwrap();
-                    controller.get_interval()
+                    // Wake up when a timed pause ends, so it can be resumed on time
+                    match controller.get_pause_remaining() {
+                        Some(remaining) => Duration::min(controller.get_pass_interval(), remaining + PAUSE_END_SLACK),
+                        None => controller.get_pass_interval(),
+                    }
                 };
 
-                let mut running = lock.lock().unwrap();
-                *running = false;
-                let (new_running, timeout_result) = cvar.wait_timeout(running, interval).unwrap();
-                running = new_running;
-
-                if timeout_result.timed_out() {
-                    let controller = controller.lock().unwrap();
-                    if controller.is_running() {
-                        mover::move_all_windows();
+                let pending = lock.lock().unwrap();
+                let (mut pending, _) = cvar.wait_timeout_while(pending, interval, |pending| pending.is_empty()).unwrap();
+                let reasons: Vec<WakeReason> = pending.drain(..).collect();
+                drop(pending);
+
+                let mut controller = controller.lock().unwrap();
+                // The interval ran out
+                if reasons.is_empty() {
+                    // A pause that just ended starts a fresh countdown instead of shifting right away
+                    if !controller.resume_if_pause_ended() && controller.is_running() && controller.is_within_schedule() {
+                        controller.shift();
+                    }
+                }
+                for reason in reasons {
+                    match reason {
+                        WakeReason::ShiftNow => controller.shift(),
+                        WakeReason::Undo => mover::undo_last_pass(),
+                        WakeReason::RestoreWindows => {
+                            mover::restore_windows();
+                            desktop::restore();
+                        }
+                        WakeReason::Reschedule => {}
                     }
                 }
             }
         }).expect("Thread failed to start");
     }
 
+    /// Returns a copy of all the settings, to be edited as a draft
+    pub fn get_settings(&self) -> Settings {
+        return self.settings_manager.get_settings();
+    }
+
+    /// Applies what was edited in a copy of the settings all at once, the draft is expected to be validated already.
+    /// Only the values that differ from the copy as it was taken are applied, the others may have changed since.
+    pub fn apply_settings(&mut self, original: Settings, edited: Settings) {
+        self.settings_manager.update(|settings| settings.apply_changes(&original, &edited));
+
+        *ENABLED_MONITORS.lock().unwrap() = self.get_all_monitors();
+        self.update_max_move();
+        self.update_glide_duration();
+        self.update_snap_groups();
+        self.update_coherent_shift();
+        self.update_log_levels();
+        self.update_taskbar();
+        self.update_dimmer();
+        self.update_burn_in();
+        self.update_orbit();
+        self.update_nudge_rules();
+        if !self.settings_manager.is_desktop_shift() {
+            desktop::restore();
+        }
+        self.wake_mover();
+
+        let interval = self.get_interval();
+        let (max_x, max_y) = self.get_max_move();
+        let running = self.is_running();
+        self.notify(ControllerEvent::IntervalChanged(interval));
+        self.notify(ControllerEvent::MaxMoveChanged(max_x, max_y));
+        self.notify(ControllerEvent::RunningChanged(running));
+        for (monitor, enabled) in self.get_all_monitors() {
+            self.notify(ControllerEvent::MonitorStateChanged(monitor, enabled));
+        }
+    }
+
+    /// Returns a receiver that gets every state change made through the controller from now on
+    pub fn subscribe(&mut self) -> Receiver<ControllerEvent> {
+        let (sender, receiver) = channel();
+        self.listeners.push(sender);
+        return receiver;
+    }
+
+    /// Sends the event to all the subscribers, dropping the ones that have hung up
+    fn notify(&mut self, event: ControllerEvent) {
+        self.listeners.retain(|listener| listener.send(event.clone()).is_ok());
+    }
+
     pub fn get_interval(&self) -> Duration {
         return self.settings_manager.get_delay();
     }
 
+    /// Returns the time between two passes, the micro-shift delay while micro-shift is on
+    fn get_pass_interval(&self) -> Duration {
+        return match self.settings_manager.get_micro_shift() {
+            Some((delay, _)) => delay,
+            None => self.get_interval(),
+        };
+    }
+
     pub fn set_interval(&mut self, interval: Duration) {
         self.settings_manager.set_delay(interval);
-        let (lock, cvar) = &*self.condvar;
-        let mut running = lock.lock().unwrap();
-        *running = true;
-        cvar.notify_all();
+        self.wake_mover();
+        self.notify(ControllerEvent::IntervalChanged(interval));
     }
 
+    /// Switches to the next profile of the settings file, its delay and max distance apply right away.
+    /// Returns the name of the profile, None if there are no profiles.
+    pub fn next_profile(&mut self) -> Option<String> {
+        let profile = self.settings_manager.next_profile()?;
+        self.update_max_move();
+        self.wake_mover();
+        self.notify(ControllerEvent::IntervalChanged(self.get_interval()));
+        self.notify(ControllerEvent::MaxMoveChanged(profile.max_distance_x, profile.max_distance_y));
+        return Some(profile.name);
+    }
+
+    /// Returns true if the windows are being moved, that is enabled and not paused for a fixed duration
     pub fn is_running(&self) -> bool {
-        return self.settings_manager.is_running();
+        return self.settings_manager.is_running() && self.get_pause_remaining().is_none();
     }
 
+    /// Returns true if the schedule lets the passes run at this time of day, always when there is no schedule
+    fn is_within_schedule(&self) -> bool {
+        let schedule = match self.settings_manager.get_schedule() {
+            Some(schedule) => schedule,
+            None => return true,
+        };
+
+        let mut now: SYSTEMTIME = unsafe { std::mem::zeroed() };
+        unsafe { GetLocalTime(&mut now) };
+        return schedule.contains(now.wHour as u32 * 60 + now.wMinute as u32);
+    }
+
+    /// Toggles the running state, a timed pause is simply ended
     pub fn toggle_running(&mut self) {
-        self.settings_manager.toggle_running();
+        if self.settings_manager.get_paused_until().is_some() {
+            self.settings_manager.set_paused_until(None);
+            self.settings_manager.set_running(true);
+        } else {
+            self.settings_manager.toggle_running();
+        }
+        self.wake_mover();
+        let running = self.is_running();
+        self.notify(ControllerEvent::RunningChanged(running));
+    }
+
+    pub fn set_running(&mut self, running: bool) {
+        self.settings_manager.set_paused_until(None);
+        self.settings_manager.set_running(running);
+        self.wake_mover();
+        self.notify(ControllerEvent::RunningChanged(running));
+    }
+
+    /// Pauses moving the windows for the given duration, after which it resumes by itself
+    pub fn pause_for(&mut self, duration: Duration) {
+        self.settings_manager.set_paused_until(Some(SystemTime::now() + duration));
+        self.settings_manager.set_running(true);
+        self.wake_mover();
+        self.notify(ControllerEvent::RunningChanged(false));
+    }
+
+    /// Returns the time left of the timed pause, None if there is no timed pause or it has ended
+    pub fn get_pause_remaining(&self) -> Option<Duration> {
+        return self.settings_manager.get_paused_until()
+            .and_then(|until| until.duration_since(SystemTime::now()).ok())
+            .filter(|remaining| !remaining.is_zero());
+    }
+
+    /// Clears a timed pause that has ended, returns true if it did
+    pub fn resume_if_pause_ended(&mut self) -> bool {
+        if self.settings_manager.get_paused_until().is_none() || self.get_pause_remaining().is_some() {
+            return false;
+        }
+
+        self.settings_manager.set_paused_until(None);
+        let running = self.is_running();
+        self.notify(ControllerEvent::RunningChanged(running));
+        return true;
+    }
+
+    /// Wakes the mover thread up so that it picks up the new interval or running state
+    fn wake_mover(&self) {
+        self.wake(WakeReason::Reschedule);
+    }
+
+    /// Makes the mover thread run a pass right away, even when paused, and restarts the countdown
+    pub fn shift_now(&self) {
+        self.wake(WakeReason::ShiftNow);
+    }
+
+    /// Moves the windows of the last pass back to where they were, on the mover thread
+    pub fn undo(&self) {
+        self.wake(WakeReason::Undo);
+    }
+
+    /// Moves all the windows back to where the user left them, on the mover thread
+    pub fn restore_windows(&self) {
+        self.wake(WakeReason::RestoreWindows);
+    }
+
+    fn wake(&self, reason: WakeReason) {
         let (lock, cvar) = &*self.condvar;
-        let mut running = lock.lock().unwrap();
-        *running = true;
+        let mut pending = lock.lock().unwrap();
+        // Every action is run, a reschedule adds nothing to a pending one, the countdown starts over after it anyway
+        if reason != WakeReason::Reschedule || pending.is_empty() {
+            pending.push_back(reason);
+        }
         cvar.notify_all();
     }
 
+    /// Moves the windows once and lets the subscribers know, runs on the mover thread
+    fn shift(&mut self) {
+        match self.settings_manager.get_micro_shift() {
+            Some((_, distance)) => mover::micro_shift_all_windows(distance),
+            None => {
+                mover::move_all_windows();
+                if self.settings_manager.is_desktop_shift() {
+                    desktop::shift(self.get_max_move());
+                }
+            }
+        }
+        self.notify(ControllerEvent::Shifted);
+    }
+
+    pub fn get_hotkey(&self, action: HotkeyAction) -> Option<String> {
+        return self.settings_manager.get_hotkey(action);
+    }
+
     pub fn get_max_move(&self) -> (i32, i32) {
         return self.settings_manager.get_max_distance();
     }
@@ -164,6 +467,7 @@ impl Controller {
     pub fn set_max_move(&mut self, max_move_x: i32, max_move_y: i32) {
         *MAX_MOVE.lock().unwrap() = (max_move_x, max_move_y);
         self.settings_manager.set_max_distance(max_move_x, max_move_y);
+        self.notify(ControllerEvent::MaxMoveChanged(max_move_x, max_move_y));
     }
 
     /// Updates the max move from the settings file, to be used on startup
@@ -171,6 +475,47 @@ impl Controller {
         *MAX_MOVE.lock().unwrap() = self.settings_manager.get_max_distance();
     }
 
+    fn update_glide_duration(&self) {
+        *GLIDE_DURATION.lock().unwrap() = self.settings_manager.get_glide_duration();
+    }
+
+    fn update_snap_groups(&self) {
+        *SNAP_GROUPS.lock().unwrap() = self.settings_manager.is_move_snap_groups();
+    }
+
+    fn update_coherent_shift(&self) {
+        *COHERENT_SHIFT.lock().unwrap() = self.settings_manager.is_coherent_shift();
+    }
+
+    /// Applies the log levels from the settings to the logger
+    fn update_log_levels(&self) {
+        let (level, modules) = self.settings_manager.get_log_levels();
+        logging::configure(&level, &modules);
+    }
+
+    /// Applies the taskbar protection from the settings to the taskbar thread
+    fn update_taskbar(&self) {
+        let (mode, idle) = self.settings_manager.get_taskbar_protection();
+        taskbar::configure(mode, idle);
+    }
+
+    /// Applies the static region dimming from the settings to the dimmer thread
+    fn update_dimmer(&self) {
+        dimmer::configure(self.settings_manager.get_static_dimming());
+    }
+
+    fn update_burn_in(&self) {
+        burn_in::configure(self.settings_manager.is_record_heatmap());
+    }
+
+    fn update_orbit(&self) {
+        orbit::configure(self.settings_manager.get_screen_orbit());
+    }
+
+    fn update_nudge_rules(&self) {
+        maximized::configure(self.settings_manager.get_nudge_rules());
+    }
+
     /// Returns all the monitors in the format: device_id => (friendly_name, is_enabled, is_connected)
     pub fn get_monitors_merged(&self) -> HashMap<String, (String, bool, bool)> {
         let connected = self.get_connected_monitors();
@@ -186,7 +531,7 @@ impl Controller {
 
         for (device_id, is_enabled) in known {
             if !merged.contains_key(&device_id) {
-                merged.insert(device_id.clone(), ("(Offline)".to_string(), is_enabled, false));
+                merged.insert(device_id.clone(), (tr(Text::Offline).to_string(), is_enabled, false));
             }
         }
 
@@ -212,10 +557,26 @@ impl Controller {
         return self.settings_manager.get_all_monitors();
     }
 
+    /// Returns true if at least one screen is connected and all the connected ones are turned off
+    pub fn is_every_monitor_disabled(&self) -> bool {
+        let connected: Vec<bool> = self.get_monitors_merged().values()
+            .filter(|(_, _, is_connected)| *is_connected)
+            .map(|(_, is_enabled, _)| *is_enabled)
+            .collect();
+
+        return !connected.is_empty() && connected.iter().all(|is_enabled| !is_enabled);
+    }
+
+    /// Returns true if the settings file couldn't be read or written
+    pub fn has_settings_error(&self) -> bool {
+        return self.settings_manager.has_error();
+    }
+
     /// Sets the monitor state in the settings file
     pub fn set_monitor_state(&mut self, monitor: &str, enabled: bool) {
         self.settings_manager.set_monitor_state(monitor, enabled);
         *ENABLED_MONITORS.lock().unwrap() = self.get_all_monitors();
+        self.notify(ControllerEvent::MonitorStateChanged(monitor.to_string(), enabled));
     }
 
     /// Adds a monitor to the app
@@ -223,3 +584,79 @@ impl Controller {
         self.settings_manager.set_monitor_state(monitor, true);
     }
 }
+
+impl Control for Controller {
+    fn get_interval(&self) -> Duration {
+        return Controller::get_interval(self);
+    }
+
+    fn set_interval(&mut self, interval: Duration) -> Result<(), String> {
+        if interval < LOWEST_DELAY || interval > MAX_DELAY {
+            return Err(format!(
+                "The interval must be between {} and {} milliseconds", LOWEST_DELAY.as_millis(), MAX_DELAY.as_millis()
+            ));
+        }
+        Controller::set_interval(self, interval);
+        return Ok(());
+    }
+
+    fn get_max_move(&self) -> (i32, i32) {
+        return Controller::get_max_move(self);
+    }
+
+    fn set_max_move(&mut self, x: u64, y: u64) -> Result<(), String> {
+        if x < LOWEST_MAX_DISTANCE as u64 || y < LOWEST_MAX_DISTANCE as u64 || x > i32::MAX as u64 || y > i32::MAX as u64 {
+            return Err(format!("The max move must be at least {} pixel", LOWEST_MAX_DISTANCE));
+        }
+        Controller::set_max_move(self, x as i32, y as i32);
+        return Ok(());
+    }
+
+    fn get_monitors_merged(&self) -> HashMap<String, (String, bool, bool)> {
+        return Controller::get_monitors_merged(self);
+    }
+
+    fn set_monitor_state(&mut self, device_id: &str, enabled: bool) {
+        Controller::set_monitor_state(self, device_id, enabled);
+    }
+
+    fn is_running(&self) -> bool {
+        return Controller::is_running(self);
+    }
+
+    fn set_running(&mut self, running: bool) {
+        Controller::set_running(self, running);
+    }
+
+    fn pause_for(&mut self, duration: Duration) {
+        Controller::pause_for(self, duration);
+    }
+
+    fn get_pause_remaining(&self) -> Option<Duration> {
+        return Controller::get_pause_remaining(self);
+    }
+
+    fn toggle_running(&mut self) {
+        Controller::toggle_running(self);
+    }
+
+    fn shift_now(&self) {
+        Controller::shift_now(self);
+    }
+
+    fn undo(&self) {
+        Controller::undo(self);
+    }
+
+    fn restore_windows(&self) {
+        Controller::restore_windows(self);
+    }
+
+    fn explain(&self) -> Value {
+        return serde_json::to_value(mover::explain_pass()).unwrap_or(Value::Null);
+    }
+
+    fn subscribe(&mut self) -> Receiver<ControllerEvent> {
+        return Controller::subscribe(self);
+    }
+}
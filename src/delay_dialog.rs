@@ -1,6 +1,8 @@
 use std::{thread, cell::RefCell};
 use std::time::Duration;
 use nwg::{ControlHandle, NativeUi, NumberSelectData};
+use crate::dialog;
+use crate::i18n::{tr, Text};
 use crate::settings::{LOWEST_DELAY, MAX_DELAY};
 
 pub enum DelayDialogData {
@@ -12,6 +14,7 @@ pub enum DelayDialogData {
 pub struct DelayDialog {
     window: nwg::Window,
     icon: nwg::Icon,
+    big_icon: nwg::Icon,
     label: nwg::Label,
     number_select: nwg::NumberSelect,
     data: RefCell<Option<DelayDialogData>>,
@@ -36,6 +39,12 @@ impl DelayDialog {
             };
             app.number_select.set_data(number_select_data);
 
+            // Screen readers don't find the label of the nested text box by themselves
+            if let Some(edit) = dialog::number_select_edit(&app.number_select) {
+                dialog::set_accessible_name(edit, &app.label.text());
+                dialog::focus_on_open(&app.window, edit);
+            }
+
             nwg::dispatch_thread_events();
 
             // Notice the main thread that the dialog completed
@@ -54,7 +63,7 @@ impl DelayDialog {
                 *data = Some(DelayDialogData::Value(Duration::from_secs(parsed_value)));
             } else {
                 // TODO: Handle the error, if any
-                println!("Failed to parse value!");
+                log::warn!("Failed to parse the delay value");
                 *data = Some(DelayDialogData::Cancel);
             }
         } else if btn == &self.cancel_button {
@@ -75,29 +84,26 @@ mod number_select_app_ui {
     use std::rc::Rc;
     use std::cell::RefCell;
     use std::ops::Deref;
-    use crate::view::ICON;
+    use crate::dialog::{self, DialogKey};
 
     pub struct SpinDialogUI {
         inner: Rc<DelayDialog>,
         default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
     }
 
     impl NativeUi<SpinDialogUI> for DelayDialog {
         fn build_ui(mut data: DelayDialog) -> Result<SpinDialogUI, nwg::NwgError> {
-            // Resources
-            nwg::Icon::builder()
-                .source_bin(Option::from(ICON))
-                .build(&mut data.icon)?;
-
             // Controls
             nwg::Window::builder()
                 .size((320, 70))
                 .center(true)
-                .title("Delay Select Dialog")
-                .icon(Some(&data.icon))
+                .title(tr(Text::DelayDialogTitle))
                 .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE | nwg::WindowFlags::POPUP)
                 .build(&mut data.window)?;
 
+            dialog::set_window_icons(&data.window, &mut data.icon, &mut data.big_icon)?;
+
             let mut grid = nwg::GridLayout::default();
             nwg::GridLayout::builder()
                 .parent(&data.window)
@@ -105,25 +111,26 @@ mod number_select_app_ui {
                 .build(&mut grid)?;
 
             nwg::Label::builder()
-                .text("Value, in seconds:")
+                .text(tr(Text::DelayDialogLabel))
                 .parent(&data.window)
                 .build(&mut data.label)?;
 
             nwg::NumberSelect::builder()
                 .size((152, 27))
                 .decimals( 0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
                 .min_int(200)
                 .value_int(30000)
                 .parent(&data.window)
                 .build(&mut data.number_select)?;
 
             nwg::Button::builder()
-                .text("Ok")
+                .text(tr(Text::ButtonOk))
                 .parent(&data.window)
                 .build(&mut data.ok_button)?;
 
             nwg::Button::builder()
-                .text("Cancel")
+                .text(tr(Text::ButtonCancel))
                 .parent(&data.window)
                 .build(&mut data.cancel_button)?;
 
@@ -136,6 +143,7 @@ mod number_select_app_ui {
             let ui = SpinDialogUI {
                 inner: Rc::new(data),
                 default_handler: Default::default(),
+                raw_handler: Default::default(),
             };
 
             use nwg::Event as E;
@@ -164,6 +172,21 @@ mod number_select_app_ui {
                 nwg::full_bind_event_handler(&ui.window.handle, handle_events)
             );
 
+            // Enter and Escape
+            let key_ui = Rc::downgrade(&ui.inner);
+            let handle_keys = move |key| {
+                if let Some(ui) = key_ui.upgrade() {
+                    match key {
+                        DialogKey::Ok => DelayDialog::choose(&ui, &ui.ok_button.handle),
+                        DialogKey::Cancel => DelayDialog::choose(&ui, &ui.cancel_button.handle),
+                    }
+                }
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                dialog::bind_dialog_keys(&ui.window, handle_keys)?
+            );
+
             return Ok(ui);
         }
     }
@@ -175,6 +198,11 @@ mod number_select_app_ui {
             for handler in handlers.drain(0..) {
                 nwg::unbind_event_handler(&handler);
             }
+
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
         }
     }
 
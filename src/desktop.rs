@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::{mem, ptr};
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use rand::Rng;
+use winapi::shared::minwindef::{BOOL, LPARAM, TRUE};
+use winapi::shared::windef::{HWND, POINT, RECT};
+use winapi::um::winuser::{
+    EnumWindows,
+    FindWindowExW,
+    FindWindowW,
+    GetParent,
+    GetWindowRect,
+    IsWindow,
+    MapWindowPoints,
+    SendMessageTimeoutW,
+    SetWindowPos,
+    SMTO_NORMAL,
+    SWP_NOACTIVATE,
+    SWP_NOSIZE,
+    SWP_NOZORDER,
+};
+
+use crate::easing::Position;
+
+/// The desktop is only ever shifted by a few pixels, whatever the max distance of the windows is
+const MAX_DESKTOP_OFFSET: i32 = 10;
+
+/// Asks Progman to split the wallpaper into a WorkerW window of its own, undocumented but stable since Windows 8
+const SPAWN_WORKER_W: u32 = 0x052C;
+
+/// The desktop windows that have been shifted, hwnd => (where they belong, where they were last moved to).
+/// Positions are relative to the parent window, the icon list is a child window.
+#[derive(Default)]
+struct DesktopState {
+    shifted: HashMap<isize, (Position, Position)>,
+    /// Set once the desktop was restored for good, the app is exiting
+    closed: bool,
+}
+
+lazy_static! {
+    static ref STATE: Mutex<DesktopState> = Mutex::new(DesktopState::default());
+}
+
+fn wide(text: &str) -> Vec<u16> {
+    return OsStr::new(text).encode_wide().chain(std::iter::once(0)).collect();
+}
+
+fn find_child(parent: HWND, class: &str) -> HWND {
+    return unsafe { FindWindowExW(parent, ptr::null_mut(), wide(class).as_ptr(), ptr::null()) };
+}
+
+/// Finds the top level WorkerW window that hosts the desktop icons, they move there once the wallpaper got split off
+unsafe extern "system" fn find_icon_host_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let host = &mut *(lparam as *mut HWND);
+    if !find_child(hwnd, "SHELLDLL_DefView").is_null() {
+        *host = hwnd;
+        return 0; // Found, stop enumerating
+    }
+    return TRUE;
+}
+
+/// Returns the list view with the desktop icons and the window the wallpaper is drawn in, if they can be found.
+/// The layout of these windows differs between Windows versions, the wallpaper may be drawn by Progman itself and can't be moved then.
+fn find_desktop_windows() -> (Option<HWND>, Option<HWND>) {
+    let progman = unsafe { FindWindowW(wide("Progman").as_ptr(), ptr::null()) };
+    if progman.is_null() {
+        return (None, None);
+    }
+
+    let mut result = 0;
+    unsafe { SendMessageTimeoutW(progman, SPAWN_WORKER_W, 0xD, 0x1, SMTO_NORMAL, 1000, &mut result) };
+
+    let mut icon_host = progman;
+    let mut def_view = find_child(progman, "SHELLDLL_DefView");
+    if def_view.is_null() {
+        let mut host: HWND = ptr::null_mut();
+        unsafe { EnumWindows(Some(find_icon_host_proc), &mut host as *mut _ as LPARAM) };
+        if !host.is_null() {
+            icon_host = host;
+            def_view = find_child(host, "SHELLDLL_DefView");
+        }
+    }
+
+    let icons = if def_view.is_null() { ptr::null_mut() } else { find_child(def_view, "SysListView32") };
+
+    // Newer versions keep the wallpaper WorkerW inside Progman, older ones right after the icon host
+    let mut wallpaper = find_child(progman, "WorkerW");
+    if wallpaper.is_null() && icon_host != progman {
+        wallpaper = unsafe { FindWindowExW(ptr::null_mut(), icon_host, wide("WorkerW").as_ptr(), ptr::null()) };
+    }
+
+    let found = |hwnd: HWND| if hwnd.is_null() { None } else { Some(hwnd) };
+    return (found(icons), found(wallpaper));
+}
+
+/// Returns the position of the window relative to its parent, or the screen for a top level window
+fn position_in_parent(hwnd: HWND) -> Option<Position> {
+    let mut rect: RECT = unsafe { mem::zeroed() };
+    if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+        return None;
+    }
+
+    let mut point = POINT { x: rect.left, y: rect.top };
+    unsafe { MapWindowPoints(ptr::null_mut(), GetParent(hwnd), &mut point, 1) };
+    return Some((point.x, point.y));
+}
+
+fn set_position(hwnd: HWND, (x, y): Position) -> bool {
+    return unsafe { SetWindowPos(hwnd, ptr::null_mut(), x, y, 0, 0, SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE) } != 0;
+}
+
+/// Moves the desktop icons and the wallpaper to a random offset from where they belong.
+/// The offset stays within the max distance of the windows, and within a few pixels.
+pub fn shift((max_x, max_y): (i32, i32)) {
+    let mut state = STATE.lock().unwrap();
+    if state.closed {
+        return;
+    }
+
+    let (icons, wallpaper) = find_desktop_windows();
+    if wallpaper.is_none() {
+        log::debug!("The wallpaper is drawn by Progman, only the desktop icons are shifted");
+    }
+
+    let max_x = max_x.clamp(0, MAX_DESKTOP_OFFSET);
+    let max_y = max_y.clamp(0, MAX_DESKTOP_OFFSET);
+    let mut rng = rand::thread_rng();
+
+    for hwnd in icons.into_iter().chain(wallpaper) {
+        let current = match position_in_parent(hwnd) {
+            Some(position) => position,
+            None => continue,
+        };
+
+        // If the window isn't where it was put, Explorer has moved or recreated it, so this is where it belongs now
+        let home = match state.shifted.get(&(hwnd as isize)) {
+            Some((home, placed)) if *placed == current => *home,
+            _ => current,
+        };
+
+        let target = (home.0 + rng.gen_range(-max_x..=max_x), home.1 + rng.gen_range(-max_y..=max_y));
+        if set_position(hwnd, target) {
+            state.shifted.insert(hwnd as isize, (home, target));
+        } else {
+            log::warn!("Failed to shift the desktop window {:#x}", hwnd as isize);
+        }
+    }
+}
+
+/// Moves the desktop icons and the wallpaper back to where they belong
+pub fn restore() {
+    let mut state = STATE.lock().unwrap();
+    restore_locked(&mut state);
+}
+
+/// Restores the desktop for good, it's not shifted again after this. For when the app exits.
+pub fn close() {
+    let mut state = STATE.lock().unwrap();
+    restore_locked(&mut state);
+    state.closed = true;
+}
+
+fn restore_locked(state: &mut DesktopState) {
+    for (key, (home, placed)) in state.shifted.drain() {
+        let hwnd = key as HWND;
+        // Left alone if Explorer has moved or recreated it since
+        if unsafe { IsWindow(hwnd) } != 0 && position_in_parent(hwnd) == Some(placed) {
+            set_position(hwnd, home);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use crate::dialog;
+use crate::history::Rect;
+use crate::i18n::{tr, Text};
+use crate::mover::{self, Explanation};
+use std::collections::HashMap;
+use std::{thread, cell::RefCell};
+use nwg::NativeUi;
+
+#[derive(Default)]
+pub struct DiagnosticsWindow {
+    window: nwg::Window,
+    icon: nwg::Icon,
+    big_icon: nwg::Icon,
+    description_label: nwg::Label,
+    windows_list: nwg::ListView,
+    refresh_button: nwg::Button,
+    close_button: nwg::Button,
+    /// Monitor device id => friendly name, the mover only knows the ids
+    monitor_names: RefCell<HashMap<String, String>>,
+}
+
+/// Returns what a pass would do with the window, as shown to the user
+pub fn decision_label(explanation: &Explanation) -> &'static str {
+    return match explanation.skipped {
+        Some(reason) => reason.label(),
+        None => tr(Text::DecisionMove),
+    };
+}
+
+/// Formats a window rect as its position and size, e.g. "100, 200 (800 × 600)"
+pub fn format_rect(rect: Rect) -> String {
+    return format!("{}, {} ({} × {})", rect.0, rect.1, rect.2 - rect.0, rect.3 - rect.1);
+}
+
+impl DiagnosticsWindow {
+
+    /// Create the window on a new thread, the dry run is done there too.
+    /// To alert the main GUI that the window closed, this function takes a notice sender object.
+    pub(crate) fn popup(sender: nwg::NoticeSender, monitor_names: HashMap<String, String>) -> thread::JoinHandle<()> {
+        return thread::spawn(move || {
+            // Create the UI just like in the main function
+            let app = DiagnosticsWindow::build_ui(Default::default()).expect("Failed to build UI");
+            *app.monitor_names.borrow_mut() = monitor_names;
+
+            app.refresh();
+            if let Some(hwnd) = app.windows_list.handle.hwnd() {
+                dialog::focus_on_open(&app.window, hwnd);
+            }
+
+            nwg::dispatch_thread_events();
+
+            // Notice the main thread that the window closed
+            sender.notice();
+        })
+    }
+
+    /// Runs a new dry run and fills the list with it, the windows that would be moved first
+    fn refresh(&self) {
+        let mut explanations = mover::explain_pass();
+        explanations.sort_by_key(|explanation| explanation.skipped);
+
+        let monitor_names = self.monitor_names.borrow();
+        self.windows_list.clear();
+        for explanation in explanations.iter() {
+            let monitor = monitor_names.get(&explanation.monitor).cloned().unwrap_or_else(|| explanation.monitor.clone());
+            let target = explanation.target.map(format_rect).unwrap_or_default();
+
+            self.windows_list.insert_items_row(None, &[
+                decision_label(explanation).to_string(),
+                explanation.title.clone(),
+                explanation.process.clone(),
+                explanation.class.clone(),
+                monitor,
+                format_rect(explanation.rect),
+                target,
+            ]);
+        }
+    }
+
+    fn close(&self) {
+        self.window.close();
+    }
+
+    fn exit(&self) {
+        nwg::stop_thread_dispatch();
+    }
+}
+
+mod diagnostics_window_ui {
+    use native_windows_gui as nwg;
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use std::ops::Deref;
+    use crate::dialog::{self, DialogKey};
+
+    pub struct DiagnosticsWindowUI {
+        inner: Rc<DiagnosticsWindow>,
+        default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
+    }
+
+    impl NativeUi<DiagnosticsWindowUI> for DiagnosticsWindow {
+        fn build_ui(mut data: DiagnosticsWindow) -> Result<DiagnosticsWindowUI, nwg::NwgError> {
+            // Controls
+            nwg::Window::builder()
+                .size((900, 480))
+                .center(true)
+                .title(tr(Text::DiagnosticsTitle))
+                .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE | nwg::WindowFlags::POPUP)
+                .build(&mut data.window)?;
+
+            dialog::set_window_icons(&data.window, &mut data.icon, &mut data.big_icon)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::DiagnosticsDescription))
+                .parent(&data.window)
+                .build(&mut data.description_label)?;
+
+            nwg::ListView::builder()
+                .list_style(nwg::ListViewStyle::Detailed)
+                .flags(nwg::ListViewFlags::VISIBLE | nwg::ListViewFlags::TAB_STOP | nwg::ListViewFlags::SINGLE_SELECTION)
+                .ex_flags(nwg::ListViewExFlags::GRID | nwg::ListViewExFlags::FULL_ROW_SELECT)
+                .parent(&data.window)
+                .build(&mut data.windows_list)?;
+
+            data.windows_list.set_headers_enabled(true);
+            let columns = [
+                (tr(Text::ColumnDecision), 150),
+                (tr(Text::ColumnTitle), 180),
+                (tr(Text::ColumnProcess), 110),
+                (tr(Text::ColumnClass), 130),
+                (tr(Text::ColumnMonitor), 110),
+                (tr(Text::ColumnPosition), 100),
+                (tr(Text::ColumnTarget), 100),
+            ];
+            for (text, width) in columns.iter() {
+                data.windows_list.insert_column(nwg::InsertListViewColumn {
+                    index: None,
+                    fmt: None,
+                    width: Some(*width),
+                    text: Some(text.to_string()),
+                });
+            }
+
+            nwg::Button::builder()
+                .text(tr(Text::ButtonRefresh))
+                .parent(&data.window)
+                .build(&mut data.refresh_button)?;
+
+            nwg::Button::builder()
+                .text(tr(Text::ButtonClose))
+                .parent(&data.window)
+                .build(&mut data.close_button)?;
+
+            let grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.window)
+                .spacing(2)
+                .max_row(Some(10))
+                .max_column(Some(6))
+                .child_item(nwg::GridLayoutItem::new(&data.description_label, 0, 0, 6, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.windows_list, 0, 1, 6, 8))
+                .child(4, 9, &data.refresh_button)
+                .child(5, 9, &data.close_button)
+                .build(&grid)?;
+
+            // Wrap-up
+            let ui = DiagnosticsWindowUI {
+                inner: Rc::new(data),
+                default_handler: Default::default(),
+                raw_handler: Default::default(),
+            };
+
+            use nwg::Event as E;
+
+            // Events
+            let evt_ui = Rc::downgrade(&ui.inner);
+            let handle_events = move |evt, _evt_data, handle| {
+                if let Some(ui) = evt_ui.upgrade() {
+                    match evt {
+                        E::OnButtonClick => {
+                            if &handle == &ui.refresh_button {
+                                DiagnosticsWindow::refresh(&ui);
+                            } else if &handle == &ui.close_button {
+                                DiagnosticsWindow::close(&ui);
+                            }
+                        }
+                        E::OnWindowClose => {
+                            if &handle == &ui.window {
+                                DiagnosticsWindow::exit(&ui);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            };
+
+            ui.default_handler.borrow_mut().push(
+                nwg::full_bind_event_handler(&ui.window.handle, handle_events)
+            );
+
+            // Enter runs the dry run again, Escape closes the window
+            let key_ui = Rc::downgrade(&ui.inner);
+            let handle_keys = move |key: DialogKey| {
+                if let Some(ui) = key_ui.upgrade() {
+                    match key {
+                        DialogKey::Ok => DiagnosticsWindow::refresh(&ui),
+                        DialogKey::Cancel => DiagnosticsWindow::close(&ui),
+                    }
+                }
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                dialog::bind_dialog_keys(&ui.window, handle_keys)?
+            );
+
+            return Ok(ui);
+        }
+    }
+
+    impl Drop for DiagnosticsWindowUI {
+        /// To make sure that everything is freed without issues, the default handler must be unbound.
+        fn drop(&mut self) {
+            let mut handlers = self.default_handler.borrow_mut();
+            for handler in handlers.drain(0..) {
+                nwg::unbind_event_handler(&handler);
+            }
+
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
+        }
+    }
+
+    impl Deref for DiagnosticsWindowUI {
+        type Target = DiagnosticsWindow;
+
+        fn deref(&self) -> &DiagnosticsWindow {
+            &self.inner
+        }
+    }
+}
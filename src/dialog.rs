@@ -0,0 +1,131 @@
+use crate::view::ICON;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use winapi::shared::minwindef::{HIWORD, LOWORD, LPARAM, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::winuser::{
+    GetSystemMetrics, GetWindow, GetWindowLongW, SendMessageW, SetFocus, SetForegroundWindow, SetWindowLongW,
+    BN_CLICKED, GWL_STYLE, GW_CHILD, ICON_BIG, ICON_SMALL, IDCANCEL, IDOK, SM_CXICON, SM_CXSMICON, SM_CYICON,
+    SM_CYSMICON, WM_COMMAND, WM_SETICON, WS_TABSTOP,
+};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::HWND as WinHwnd;
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+use windows::Win32::UI::Accessibility::{IAccPropServices, CLSID_AccPropServices, PROPID_ACC_NAME};
+
+/// The id of the raw handler that turns Enter and Escape into OK and Cancel, ids up to 0xFFFF are reserved by NWG
+const DIALOG_KEYS_HANDLER_ID: usize = 0x10001;
+
+/// OBJID_CLIENT, the client area of a window as seen by screen readers
+const OBJID_CLIENT: u32 = 0xFFFFFFFC;
+
+/// The buttons a dialog can be closed with from the keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DialogKey {
+    /// Enter
+    Ok,
+    /// Escape
+    Cancel,
+}
+
+/// Gives the dialog the app icon in both sizes.
+/// NWG only sets the small one, so the taskbar and Alt+Tab used to show a blank icon.
+pub fn set_window_icons(window: &nwg::Window, small: &mut nwg::Icon, big: &mut nwg::Icon) -> Result<(), nwg::NwgError> {
+    let (small_size, big_size) = unsafe {
+        (
+            (GetSystemMetrics(SM_CXSMICON) as u32, GetSystemMetrics(SM_CYSMICON) as u32),
+            (GetSystemMetrics(SM_CXICON) as u32, GetSystemMetrics(SM_CYICON) as u32),
+        )
+    };
+
+    nwg::Icon::builder()
+        .source_bin(Some(ICON))
+        .size(Some(small_size))
+        .build(small)?;
+
+    nwg::Icon::builder()
+        .source_bin(Some(ICON))
+        .size(Some(big_size))
+        .build(big)?;
+
+    if let Some(hwnd) = window.handle.hwnd() {
+        unsafe {
+            SendMessageW(hwnd, WM_SETICON, ICON_SMALL as WPARAM, small.handle as LPARAM);
+            SendMessageW(hwnd, WM_SETICON, ICON_BIG as WPARAM, big.handle as LPARAM);
+        }
+    }
+
+    return Ok(());
+}
+
+/// Calls back with OK on Enter and with Cancel on Escape.
+/// NWG runs the dialogs through IsDialogMessage, which reports those keys as the IDOK and IDCANCEL commands.
+pub fn bind_dialog_keys<F>(window: &nwg::Window, callback: F) -> Result<nwg::RawEventHandler, nwg::NwgError>
+    where F: Fn(DialogKey) + 'static
+{
+    let handle_keys = move |_hwnd, msg: UINT, w: WPARAM, l: LPARAM| {
+        // Clicks on the real buttons carry their handle, the keys don't carry any
+        if msg != WM_COMMAND || l != 0 || HIWORD(w as u32) != BN_CLICKED as u16 {
+            return None;
+        }
+
+        match LOWORD(w as u32) as i32 {
+            IDOK => callback(DialogKey::Ok),
+            IDCANCEL => callback(DialogKey::Cancel),
+            _ => return None,
+        }
+        return Some(0);
+    };
+
+    return nwg::bind_raw_event_handler(&window.handle, DIALOG_KEYS_HANDLER_ID, handle_keys);
+}
+
+/// Brings the dialog to the front and puts the keyboard focus on the control.
+/// Dialogs are opened from the tray, which doesn't hand over the foreground by itself.
+pub fn focus_on_open(window: &nwg::Window, control: HWND) {
+    if let Some(hwnd) = window.handle.hwnd() {
+        unsafe { SetForegroundWindow(hwnd) };
+    }
+    unsafe { SetFocus(control) };
+}
+
+/// Returns the text box of a number select, that's where the typing and the focus go
+pub fn number_select_edit(number_select: &nwg::NumberSelect) -> Option<HWND> {
+    let container = number_select.handle.hwnd()?;
+    // The text box is created before the two buttons, so it's the first child
+    let edit = unsafe { GetWindow(container, GW_CHILD) };
+    return if edit.is_null() { None } else { Some(edit) };
+}
+
+/// Lets Tab stop on a control that NWG doesn't allow to be built with WS_TABSTOP
+pub fn add_tab_stop(hwnd: HWND) {
+    unsafe {
+        let style = GetWindowLongW(hwnd, GWL_STYLE) as u32;
+        SetWindowLongW(hwnd, GWL_STYLE, (style | WS_TABSTOP) as i32);
+    }
+}
+
+/// Gives the control a name for screen readers.
+/// They take the name of a text box from the label right before it, which doesn't work for
+/// controls nested in a NWG container like the number select, so it's set explicitly.
+pub fn set_accessible_name(hwnd: HWND, name: &str) {
+    let wide_name: Vec<u16> = OsStr::new(name).encode_wide().chain(std::iter::once(0)).collect();
+
+    let result = unsafe {
+        // Every dialog runs on its own thread, COM may not be initialized there yet
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+        CoCreateInstance::<_, IAccPropServices>(&CLSID_AccPropServices, None, CLSCTX_INPROC_SERVER)
+            .and_then(|services| services.SetHwndPropStr(
+                WinHwnd(hwnd as _),
+                OBJID_CLIENT,
+                0, // CHILDID_SELF
+                PROPID_ACC_NAME,
+                PCWSTR(wide_name.as_ptr()),
+            ))
+    };
+
+    if let Err(err) = result {
+        log::warn!("Failed to set the accessible name \"{}\": {}", name, err);
+    }
+}
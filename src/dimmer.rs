@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use winapi::shared::windef::RECT;
+
+use crate::capture;
+use crate::monitor_info::{get_all_monitors_info, monitor_device_name};
+use crate::mover;
+use crate::overlay::{self, TileOverlay};
+use crate::static_regions::{DimConfig, Frame, StaticRegions};
+
+/// Lowest time a region has to stay unchanged before it's dimmed, in minutes
+pub const LOWEST_STATIC_MINUTES: u32 = 1;
+
+/// Highest time a region has to stay unchanged before it's dimmed, in minutes (4 hours)
+pub const MAX_STATIC_MINUTES: u32 = 4 * 60;
+
+/// Lowest and highest strength of the dimming, in percent. Fully black regions would look like a broken screen.
+pub const LOWEST_STRENGTH: u32 = 5;
+pub const MAX_STRENGTH: u32 = 80;
+
+/// How long a static region takes to fade to its full dimming, slow enough not to catch the eye
+pub const FADE_IN: Duration = Duration::from_secs(5 * 60);
+
+/// Size of a tile on the screen, in pixels
+const TILE_SIZE: i32 = 64;
+
+/// Each tile is sampled as a square of this many pixels per side
+const SAMPLES_PER_TILE: i32 = 4;
+
+/// How often the screen is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the thread wakes up to handle the overlay messages and check whether the app exits
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A monitor being watched
+struct Watched {
+    rect: RECT,
+    regions: StaticRegions,
+    /// Only created while there is something to dim
+    overlay: Option<TileOverlay>,
+    /// The alphas the overlay shows right now
+    alphas: Vec<u8>,
+}
+
+lazy_static! {
+    /// How static regions are dimmed, None when they aren't. Synced from the settings by the controller.
+    static ref CONFIG: Mutex<Option<DimConfig>> = Mutex::new(None);
+
+    static ref THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Set when the app exits, the dimmer thread removes the overlays and stops
+static CLOSED: AtomicBool = AtomicBool::new(false);
+
+/// Sets how static regions are dimmed, None turns the dimming off and removes the overlays
+pub fn configure(config: Option<DimConfig>) {
+    *CONFIG.lock().unwrap() = config;
+}
+
+/// Starts watching the screen on a thread of its own, the overlays live on that thread too
+pub fn start() {
+    let spawned = thread::Builder::new().name("dimmer_thread".to_string()).spawn(run);
+    match spawned {
+        Ok(handle) => *THREAD.lock().unwrap() = Some(handle),
+        Err(err) => log::error!("Failed to start the dimmer thread: {}", err),
+    }
+}
+
+/// Removes the overlays and stops watching, for when the app exits
+pub fn close() {
+    CLOSED.store(true, Ordering::Relaxed);
+    if let Some(handle) = THREAD.lock().unwrap().take() {
+        let _ = handle.join();
+    }
+}
+
+fn same_rect(a: &RECT, b: &RECT) -> bool {
+    return a.left == b.left && a.top == b.top && a.right == b.right && a.bottom == b.bottom;
+}
+
+/// Samples the monitor, and dims the regions that haven't changed for long enough
+fn sample(watched: &mut Watched, elapsed: Duration, config: &DimConfig) {
+    let rect = watched.rect;
+    let width = watched.regions.columns() as i32 * SAMPLES_PER_TILE;
+    let height = watched.regions.rows() as i32 * SAMPLES_PER_TILE;
+
+    let mut luma = match capture::capture_luma(&rect, width, height) {
+        Some(luma) => luma,
+        None => {
+            log::debug!("Failed to sample the screen, the regions start over");
+            watched.regions.reset();
+            return;
+        }
+    };
+
+    // The overlay shows up in the sample on older Windows versions, the dimming is taken back out so it doesn't count as a change
+    if let Some(overlay) = watched.overlay.as_ref() {
+        if !overlay.is_excluded_from_capture() {
+            watched.regions.undim(&mut luma, width as usize, height as usize, &watched.alphas);
+        }
+    }
+
+    let frame = Frame { width: width as usize, height: height as usize, luma: &luma };
+    watched.regions.update(&frame, elapsed, config);
+    let alphas = watched.regions.alphas(config);
+
+    if alphas.iter().all(|&alpha| alpha == 0) {
+        watched.overlay = None;
+    } else if watched.overlay.is_none() {
+        watched.overlay = TileOverlay::new(rect, watched.regions.columns(), watched.regions.rows());
+        watched.alphas.clear();
+    }
+
+    if let Some(overlay) = watched.overlay.as_ref() {
+        if alphas != watched.alphas {
+            overlay.set_alphas(&alphas);
+        }
+        overlay.raise();
+    }
+    watched.alphas = alphas;
+}
+
+/// Samples every enabled monitor now and then and dims the static regions on it
+fn run() {
+    let mut watched: HashMap<String, Watched> = HashMap::new();
+    let mut last_sample: Option<Instant> = None;
+
+    loop {
+        overlay::pump_messages();
+
+        if CLOSED.load(Ordering::Relaxed) {
+            return; // Dropping the overlays removes them
+        }
+
+        let config = *CONFIG.lock().unwrap();
+        let config = match config {
+            Some(config) => config,
+            None => {
+                watched.clear();
+                last_sample = None;
+                thread::sleep(CHECK_INTERVAL);
+                continue;
+            }
+        };
+
+        let elapsed = last_sample.map(|last| last.elapsed());
+        if elapsed.map_or(true, |elapsed| elapsed >= SAMPLE_INTERVAL) {
+            last_sample = Some(Instant::now());
+
+            let mut seen = Vec::new();
+            for info in get_all_monitors_info() {
+                if !mover::is_monitor_enabled(&info) {
+                    continue;
+                }
+
+                let name = monitor_device_name(&info);
+                let rect = info.rcMonitor;
+                // A monitor that changed its resolution or position starts over
+                if watched.get(&name).map_or(false, |watched| !same_rect(&watched.rect, &rect)) {
+                    watched.remove(&name);
+                }
+
+                let entry = watched.entry(name.clone()).or_insert_with(|| Watched {
+                    rect,
+                    regions: StaticRegions::new(
+                        ((rect.right - rect.left) / TILE_SIZE) as usize,
+                        ((rect.bottom - rect.top) / TILE_SIZE) as usize,
+                    ),
+                    overlay: None,
+                    alphas: Vec::new(),
+                });
+                sample(entry, elapsed.unwrap_or_default(), &config);
+                seen.push(name);
+            }
+
+            // Monitors that were disabled or disconnected lose their overlay
+            watched.retain(|name, _| seen.contains(name));
+        }
+
+        thread::sleep(CHECK_INTERVAL);
+    }
+}
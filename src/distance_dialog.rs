@@ -1,3 +1,5 @@
+use crate::dialog;
+use crate::i18n::{tr, Text};
 use crate::mover;
 use std::{thread, cell::RefCell};
 use nwg::{ControlHandle, NativeUi, NumberSelectData};
@@ -12,6 +14,7 @@ pub enum DistanceDialogData {
 pub struct DistanceDialog {
     window: nwg::Window,
     icon: nwg::Icon,
+    big_icon: nwg::Icon,
     label_x: nwg::Label,
     label_y: nwg::Label,
     number_select_x: nwg::NumberSelect,
@@ -48,6 +51,15 @@ impl DistanceDialog {
             };
             app.number_select_y.set_data(number_select_data_y);
 
+            // Screen readers don't find the labels of the nested text boxes by themselves
+            if let Some(edit) = dialog::number_select_edit(&app.number_select_y) {
+                dialog::set_accessible_name(edit, &app.label_y.text());
+            }
+            if let Some(edit) = dialog::number_select_edit(&app.number_select_x) {
+                dialog::set_accessible_name(edit, &app.label_x.text());
+                dialog::focus_on_open(&app.window, edit);
+            }
+
             nwg::dispatch_thread_events();
 
             // Notice the main thread that the dialog completed
@@ -71,7 +83,7 @@ impl DistanceDialog {
                 *data = Some(DistanceDialogData::Value(value_x.unwrap(), value_y.unwrap()));
             } else {
                 // TODO: Handle the error, if any
-                println!("Failed to parse value!");
+                log::warn!("Failed to parse the distance value");
                 *data = Some(DistanceDialogData::Cancel);
             }
         } else if btn == &self.cancel_button {
@@ -92,29 +104,26 @@ mod number_select_app_ui {
     use std::rc::Rc;
     use std::cell::RefCell;
     use std::ops::Deref;
-    use crate::view::ICON;
+    use crate::dialog::{self, DialogKey};
 
     pub struct DistanceDialogUI {
         inner: Rc<DistanceDialog>,
         default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
     }
 
     impl NativeUi<DistanceDialogUI> for DistanceDialog {
         fn build_ui(mut data: DistanceDialog) -> Result<DistanceDialogUI, nwg::NwgError> {
-            // Resources
-            nwg::Icon::builder()
-                .source_bin(Option::from(ICON))
-                .build(&mut data.icon)?;
-
             // Controls
             nwg::Window::builder()
                 .size((320, 100))
                 .center(true)
-                .title("Distance Dialog")
-                .icon(Some(&data.icon))
+                .title(tr(Text::DistanceDialogTitle))
                 .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE | nwg::WindowFlags::POPUP)
                 .build(&mut data.window)?;
 
+            dialog::set_window_icons(&data.window, &mut data.icon, &mut data.big_icon)?;
+
             let mut grid = nwg::GridLayout::default();
             nwg::GridLayout::builder()
                 .parent(&data.window)
@@ -122,34 +131,36 @@ mod number_select_app_ui {
                 .build(&mut grid)?;
 
             nwg::Label::builder()
-                .text("Max distance x (pixels):")
+                .text(tr(Text::DistanceXLabel))
                 .parent(&data.window)
                 .build(&mut data.label_x)?;
 
             nwg::Label::builder()
-                .text("Max distance y (pixels):")
+                .text(tr(Text::DistanceYLabel))
                 .parent(&data.window)
                 .build(&mut data.label_y)?;
 
             nwg::NumberSelect::builder()
                 .size((152, 27))
                 .decimals( 0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
                 .parent(&data.window)
                 .build(&mut data.number_select_x)?;
 
             nwg::NumberSelect::builder()
                 .size((152, 27))
                 .decimals( 0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
                 .parent(&data.window)
                 .build(&mut data.number_select_y)?;
 
             nwg::Button::builder()
-                .text("Ok")
+                .text(tr(Text::ButtonOk))
                 .parent(&data.window)
                 .build(&mut data.ok_button)?;
 
             nwg::Button::builder()
-                .text("Cancel")
+                .text(tr(Text::ButtonCancel))
                 .parent(&data.window)
                 .build(&mut data.cancel_button)?;
 
@@ -164,6 +175,7 @@ mod number_select_app_ui {
             let ui = DistanceDialogUI {
                 inner: Rc::new(data),
                 default_handler: Default::default(),
+                raw_handler: Default::default(),
             };
 
             use nwg::Event as E;
@@ -192,6 +204,21 @@ mod number_select_app_ui {
                 nwg::full_bind_event_handler(&ui.window.handle, handle_events)
             );
 
+            // Enter and Escape
+            let key_ui = Rc::downgrade(&ui.inner);
+            let handle_keys = move |key| {
+                if let Some(ui) = key_ui.upgrade() {
+                    match key {
+                        DialogKey::Ok => DistanceDialog::choose(&ui, &ui.ok_button.handle),
+                        DialogKey::Cancel => DistanceDialog::choose(&ui, &ui.cancel_button.handle),
+                    }
+                }
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                dialog::bind_dialog_keys(&ui.window, handle_keys)?
+            );
+
             return Ok(ui);
         }
     }
@@ -203,6 +230,11 @@ mod number_select_app_ui {
             for handler in handlers.drain(0..) {
                 nwg::unbind_event_handler(&handler);
             }
+
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
         }
     }
 
@@ -0,0 +1,115 @@
+// Where a gliding window is at each moment: how far along the glide is, the easing curve, and the micro-shift circle
+
+use std::f64::consts::TAU;
+use std::time::Duration;
+
+/// A window position, the top left corner of its window rect in screen coordinates
+pub type Position = (i32, i32);
+
+/// A window rectangle in screen coordinates: (left, top, right, bottom)
+pub type Rect = (i32, i32, i32, i32);
+
+/// Returns how far along the glide is, from 0.0 at the start to 1.0 once the duration has passed
+pub fn progress(elapsed: Duration, duration: Duration) -> f64 {
+    if duration.is_zero() {
+        return 1.0;
+    }
+    return (elapsed.as_secs_f64() / duration.as_secs_f64()).clamp(0.0, 1.0);
+}
+
+/// Cubic ease-in-out: starts slowly, speeds up in the middle and slows down again before stopping.
+/// Maps 0.0 to 0.0 and 1.0 to 1.0, anything outside is clamped.
+pub fn ease_in_out(t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    return if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    };
+}
+
+/// Returns the position the given fraction of the way from one position to the other, rounded to whole pixels
+pub fn interpolate(from: Position, to: Position, fraction: f64) -> Position {
+    let x = from.0 as f64 + (to.0 - from.0) as f64 * fraction;
+    let y = from.1 as f64 + (to.1 - from.1) as f64 * fraction;
+    return (x.round() as i32, y.round() as i32);
+}
+
+/// Returns where a gliding window should be after the elapsed time
+pub fn glide_position(from: Position, to: Position, elapsed: Duration, duration: Duration) -> Position {
+    return interpolate(from, to, ease_in_out(progress(elapsed, duration)));
+}
+
+/// Returns the offset of the micro-shift trajectory after the given number of steps.
+/// The trajectory is a circle of the radius, walked at most a pixel per step on each axis so the drift is hard to notice.
+pub fn orbit_offset(step: u64, radius: i32) -> Position {
+    if radius <= 0 {
+        return (0, 0);
+    }
+
+    let radius = radius as f64;
+    let steps_per_turn = (TAU * radius).ceil().max(4.0) as u64;
+    let angle = TAU * (step % steps_per_turn) as f64 / steps_per_turn as f64;
+    return ((radius * angle.cos()).round() as i32, (radius * angle.sin()).round() as i32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_clamped() {
+        let duration = Duration::from_millis(400);
+        assert_eq!(progress(Duration::ZERO, duration), 0.0);
+        assert_eq!(progress(Duration::from_millis(100), duration), 0.25);
+        assert_eq!(progress(duration, duration), 1.0);
+        assert_eq!(progress(Duration::from_secs(5), duration), 1.0);
+        assert_eq!(progress(Duration::ZERO, Duration::ZERO), 1.0);
+    }
+
+    #[test]
+    fn ease_in_out_keeps_the_endpoints() {
+        assert_eq!(ease_in_out(0.0), 0.0);
+        assert_eq!(ease_in_out(0.5), 0.5);
+        assert_eq!(ease_in_out(1.0), 1.0);
+        assert_eq!(ease_in_out(-1.0), 0.0);
+        assert_eq!(ease_in_out(2.0), 1.0);
+    }
+
+    #[test]
+    fn ease_in_out_never_goes_back() {
+        let mut previous = ease_in_out(0.0);
+        for step in 1..=1000 {
+            let eased = ease_in_out(step as f64 / 1000.0);
+            assert!(eased >= previous, "{} went back from {}", eased, previous);
+            previous = eased;
+        }
+    }
+
+    #[test]
+    fn interpolate_goes_from_one_position_to_the_other() {
+        assert_eq!(interpolate((10, -20), (30, 40), 0.0), (10, -20));
+        assert_eq!(interpolate((10, -20), (30, 40), 0.5), (20, 10));
+        assert_eq!(interpolate((10, -20), (30, 40), 1.0), (30, 40));
+        assert_eq!(interpolate((0, 0), (-5, 5), 0.5), (-3, 3));
+    }
+
+    #[test]
+    fn glide_starts_and_ends_on_the_positions() {
+        let duration = Duration::from_millis(300);
+        assert_eq!(glide_position((100, 200), (140, 160), Duration::ZERO, duration), (100, 200));
+        assert_eq!(glide_position((100, 200), (140, 160), Duration::from_millis(150), duration), (120, 180));
+        assert_eq!(glide_position((100, 200), (140, 160), duration, duration), (140, 160));
+        assert_eq!(glide_position((100, 200), (140, 160), Duration::from_secs(1), duration), (140, 160));
+    }
+
+    #[test]
+    fn orbit_stays_on_its_circle() {
+        assert_eq!(orbit_offset(7, 0), (0, 0));
+        assert_eq!(orbit_offset(0, 3), (3, 0));
+        for step in 0..100 {
+            let (x, y) = orbit_offset(step, 3);
+            assert!(x.abs() <= 3 && y.abs() <= 3);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// The invisible resize borders around a window, so its visible frame is kept within the work area rather than its window rect
+
+use crate::easing::Rect;
+
+/// The invisible resize borders of a window: how far its window rect reaches past its visible frame on each side.
+/// Windows 10 and 11 draw them around most windows, about 7 pixels on the left, right and bottom.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Borders {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Borders {
+    /// Returns the borders between the window rect and its visible frame, as reported by DWM.
+    /// A side where the frame reaches past the window rect has no border, that's a frame DWM doesn't know the shape of.
+    pub fn between(window: Rect, frame: Rect) -> Borders {
+        return Borders {
+            left: (frame.0 - window.0).max(0),
+            top: (frame.1 - window.1).max(0),
+            right: (window.2 - frame.2).max(0),
+            bottom: (window.3 - frame.3).max(0),
+        };
+    }
+
+    /// Returns the visible frame of a window with the window rect
+    pub fn to_frame(self, window: Rect) -> Rect {
+        return (window.0 + self.left, window.1 + self.top, window.2 - self.right, window.3 - self.bottom);
+    }
+
+    /// Returns the window rect that puts the visible frame at the rect, the way around of `to_frame`
+    pub fn to_window(self, frame: Rect) -> Rect {
+        return (frame.0 - self.left, frame.1 - self.top, frame.2 + self.right, frame.3 + self.bottom);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Rect = (93, 100, 1107, 807);
+    const FRAME: Rect = (100, 100, 1100, 800);
+
+    #[test]
+    fn between_measures_the_invisible_borders() {
+        assert_eq!(Borders::between(WINDOW, FRAME), Borders { left: 7, top: 0, right: 7, bottom: 7 });
+        assert_eq!(Borders::between(FRAME, FRAME), Borders::default());
+    }
+
+    #[test]
+    fn frame_reaching_past_the_window_rect_has_no_border() {
+        assert_eq!(Borders::between(FRAME, WINDOW), Borders::default());
+        assert_eq!(Borders::between((100, 100, 1100, 800), (90, 105, 1100, 810)), Borders { left: 0, top: 5, right: 0, bottom: 0 });
+    }
+
+    #[test]
+    fn to_frame_and_to_window_round_trip() {
+        let borders = Borders::between(WINDOW, FRAME);
+        assert_eq!(borders.to_frame(WINDOW), FRAME);
+        assert_eq!(borders.to_window(FRAME), WINDOW);
+        assert_eq!(borders.to_window(borders.to_frame((-1900, -50, -1000, 600))), (-1900, -50, -1000, 600));
+    }
+
+    #[test]
+    fn to_window_puts_the_frame_at_the_rect() {
+        let borders = Borders::between(WINDOW, FRAME);
+        assert_eq!(borders.to_window((0, 0, 1000, 700)), (-7, 0, 1007, 707));
+        assert_eq!(Borders::default().to_window((25, 40, 525, 440)), (25, 40, 525, 440));
+    }
+}
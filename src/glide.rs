@@ -0,0 +1,164 @@
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::winuser::{
+    GetGUIThreadInfo,
+    GetWindowRect,
+    GetWindowThreadProcessId,
+    GUITHREADINFO,
+    GUI_INMOVESIZE,
+    HWND_TOP,
+    IsWindow,
+    SetWindowPos,
+    SWP_NOACTIVATE,
+    SWP_NOSIZE,
+    SWP_NOZORDER,
+};
+
+use crate::easing::{self, Position};
+
+/// Time between two frames of a glide, about 60 frames per second
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The longest glide allowed, in milliseconds
+pub const MAX_GLIDE_MILLISECONDS: u32 = 5000;
+
+/// A window gliding from one position to another
+pub struct Glide {
+    /// The window handle, as an integer so the glide can be sent to the glide thread
+    pub hwnd: isize,
+    pub from: Position,
+    pub to: Position,
+}
+
+/// The glide thread that is running, and the flag that tells it to stop
+struct Running {
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+lazy_static! {
+    static ref RUNNING: Mutex<Option<Running>> = Mutex::new(None);
+}
+
+/// Returns the current position of the window, None if it's gone
+fn window_position(hwnd: HWND) -> Option<Position> {
+    let mut rect: RECT = unsafe { mem::zeroed() };
+    if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+        return None;
+    }
+    return Some((rect.left, rect.top));
+}
+
+/// Returns true while the user is dragging or resizing the window
+fn is_in_move_size(hwnd: HWND) -> bool {
+    let thread_id = unsafe { GetWindowThreadProcessId(hwnd, std::ptr::null_mut()) };
+
+    let mut info: GUITHREADINFO = unsafe { mem::zeroed() };
+    info.cbSize = mem::size_of::<GUITHREADINFO>() as u32;
+    if unsafe { GetGUIThreadInfo(thread_id, &mut info) } == 0 {
+        return false;
+    }
+    return (info.flags & GUI_INMOVESIZE) != 0 && info.hwndMoveSize == hwnd;
+}
+
+fn set_position(hwnd: HWND, (x, y): Position) -> bool {
+    return unsafe { SetWindowPos(hwnd, HWND_TOP, x, y, 0, 0, SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE) } != 0;
+}
+
+/// Glides the windows to their targets on a thread of their own, all of them at once.
+/// A glide that is still running is finished first, its windows jump straight to their targets.
+/// Once every window has arrived or was let go, the callback gets where each of them was left, in the same order.
+pub fn start(glides: Vec<Glide>, duration: Duration, on_done: impl FnOnce(Vec<Position>) + Send + 'static) {
+    finish();
+
+    if glides.is_empty() {
+        on_done(Vec::new());
+        return;
+    }
+
+    // Nothing has moved if the thread doesn't start, the callback gets the windows where they were
+    let unmoved: Vec<Position> = glides.iter().map(|glide| glide.from).collect();
+    let on_done = Arc::new(Mutex::new(Some(on_done)));
+    let thread_on_done = on_done.clone();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let spawned = thread::Builder::new().name("glide_thread".to_string()).spawn(move || {
+        let placed = run(&glides, duration, &thread_stop);
+        if let Some(on_done) = thread_on_done.lock().unwrap().take() {
+            on_done(placed);
+        }
+    });
+
+    match spawned {
+        Ok(thread) => *RUNNING.lock().unwrap() = Some(Running { stop, thread }),
+        Err(err) => {
+            log::error!("Failed to start the glide thread: {}", err);
+            if let Some(on_done) = on_done.lock().unwrap().take() {
+                on_done(unmoved);
+            }
+        }
+    }
+}
+
+/// Stops the running glide, if any, and waits until its windows are at their targets and it has reported them.
+/// Must be called before anything else moves the windows or compares their positions, like undo does.
+pub fn finish() {
+    let running = RUNNING.lock().unwrap().take();
+    if let Some(running) = running {
+        running.stop.store(true, Ordering::Relaxed);
+        let _ = running.thread.join();
+    }
+}
+
+/// Moves the windows a frame at a time until they arrive or the glide is stopped.
+/// A window is left where it is as soon as the user grabs it or moves it somewhere else.
+/// Returns the position each window was last put at, its target if it arrived.
+fn run(glides: &[Glide], duration: Duration, stop: &AtomicBool) -> Vec<Position> {
+    let start = Instant::now();
+    let mut placed: Vec<Position> = glides.iter().map(|glide| glide.from).collect();
+    // The glides still running
+    let mut active: Vec<usize> = (0..glides.len()).collect();
+
+    while !active.is_empty() {
+        let stopped = stop.load(Ordering::Relaxed);
+        let elapsed = if stopped { duration } else { start.elapsed() };
+
+        active.retain(|&index| {
+            let glide = &glides[index];
+            let placed = &mut placed[index];
+            let hwnd = glide.hwnd as HWND;
+            if unsafe { IsWindow(hwnd) } == 0 {
+                return false;
+            }
+            if window_position(hwnd) != Some(*placed) || is_in_move_size(hwnd) {
+                log::debug!("Stopped gliding window {:#x}, the user moved it", glide.hwnd);
+                return false;
+            }
+
+            let position = easing::glide_position(glide.from, glide.to, elapsed, duration);
+            if position != *placed {
+                if !set_position(hwnd, position) {
+                    log::debug!("Window {:#x} refused to glide", glide.hwnd);
+                    return false;
+                }
+                *placed = position;
+            }
+
+            return position != glide.to;
+        });
+
+        if stopped {
+            break;
+        }
+        thread::sleep(FRAME_INTERVAL);
+    }
+
+    return placed;
+}
@@ -0,0 +1,182 @@
+// The burn-in heatmap of a monitor: how long each tile has shown bright content that doesn't change, and the colors it's drawn in
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rgb_image::RgbImage;
+use crate::static_regions::Frame;
+
+/// How much a pixel may change between two samples, out of 255, and still count as static
+const PIXEL_THRESHOLD: i32 = 8;
+
+/// The static time of a monitor, one cell per pixel of its scaled-down samples
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Heatmap {
+    pub width: usize,
+    pub height: usize,
+    /// How long the monitor has been sampled, in seconds
+    pub sampled_seconds: f64,
+    /// How long each cell showed static content, in seconds, weighted by how bright it was, row by row
+    pub cells: Vec<f32>,
+    /// The last sample, to compare the next one with. Not saved, a restart starts comparing over.
+    #[serde(skip)]
+    previous: Option<Vec<u8>>,
+}
+
+impl Heatmap {
+    pub fn new(width: usize, height: usize) -> Self {
+        return Heatmap {
+            width,
+            height,
+            sampled_seconds: 0.0,
+            cells: vec![0.0; width * height],
+            previous: None,
+        };
+    }
+
+    /// Adds a sample, the elapsed time is the time since the previous one.
+    /// Every pixel that stayed the same gets the elapsed time, scaled by its brightness: a static white pixel wears
+    /// the panel the most and a black one not at all. The first sample only becomes the reference.
+    /// A sample of another size starts the heatmap over, the aspect ratio of the monitor changed.
+    pub fn update(&mut self, frame: &Frame, elapsed: Duration) {
+        if frame.width != self.width || frame.height != self.height {
+            *self = Heatmap::new(frame.width, frame.height);
+        }
+
+        let size = self.width * self.height;
+        if frame.luma.len() < size {
+            self.previous = None;
+            return;
+        }
+
+        if let Some(previous) = self.previous.as_ref() {
+            let seconds = elapsed.as_secs_f32();
+            for ((cell, &luma), &before) in self.cells.iter_mut().zip(frame.luma.iter()).zip(previous.iter()) {
+                if (luma as i32 - before as i32).abs() <= PIXEL_THRESHOLD {
+                    *cell += seconds * luma as f32 / 255.0;
+                }
+            }
+            self.sampled_seconds += elapsed.as_secs_f64();
+        }
+
+        self.previous = Some(frame.luma[..size].to_vec());
+    }
+
+    /// Returns true if there's a cell for every pixel, a heatmap read from a truncated or edited file may not have
+    pub fn is_intact(&self) -> bool {
+        return self.width.checked_mul(self.height) == Some(self.cells.len());
+    }
+
+    /// Returns the highest static time of any cell, in seconds
+    pub fn peak(&self) -> f32 {
+        return self.cells.iter().copied().fold(0.0, f32::max);
+    }
+
+    /// Renders the heatmap, relative to its peak: black where nothing was static, then red, yellow and white for the worst
+    pub fn render(&self) -> RgbImage {
+        let mut image = RgbImage::new(self.width, self.height);
+        let peak = self.peak();
+        if peak <= 0.0 {
+            return image;
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                image.set(x, y, heat_color(self.cells[y * self.width + x] / peak));
+            }
+        }
+        return image;
+    }
+}
+
+/// Maps 0.0 to 1.0 to black, red, yellow and white
+pub fn heat_color(heat: f32) -> [u8; 3] {
+    let heat = heat.clamp(0.0, 1.0) * 3.0;
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    return [channel(heat), channel(heat - 1.0), channel(heat - 2.0)];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(luma: &[u8]) -> Frame<'_> {
+        return Frame { width: 2, height: 2, luma };
+    }
+
+    #[test]
+    fn first_sample_only_becomes_the_reference() {
+        let mut heatmap = Heatmap::new(2, 2);
+        heatmap.update(&frame(&[255; 4]), Duration::from_secs(10));
+        assert_eq!(heatmap.cells, vec![0.0; 4]);
+        assert_eq!(heatmap.sampled_seconds, 0.0);
+    }
+
+    #[test]
+    fn static_pixels_accumulate_by_luminance() {
+        let mut heatmap = Heatmap::new(2, 2);
+        let luma = [255, 51, 0, 255];
+        heatmap.update(&frame(&luma), Duration::ZERO);
+        heatmap.update(&frame(&luma), Duration::from_secs(10));
+        heatmap.update(&frame(&luma), Duration::from_secs(5));
+
+        assert_eq!(heatmap.cells, vec![15.0, 3.0, 0.0, 15.0]);
+        assert_eq!(heatmap.sampled_seconds, 15.0);
+        assert_eq!(heatmap.peak(), 15.0);
+    }
+
+    #[test]
+    fn only_changes_within_the_threshold_count_as_static() {
+        let mut heatmap = Heatmap::new(2, 2);
+        heatmap.update(&frame(&[100, 100, 100, 100]), Duration::ZERO);
+        let threshold = PIXEL_THRESHOLD as u8;
+        heatmap.update(&frame(&[100 + threshold, 100 - threshold, 101 + threshold, 99 - threshold]), Duration::from_secs(1));
+
+        assert!(heatmap.cells[0] > 0.0);
+        assert!(heatmap.cells[1] > 0.0);
+        assert_eq!(heatmap.cells[2], 0.0);
+        assert_eq!(heatmap.cells[3], 0.0);
+    }
+
+    #[test]
+    fn frame_of_another_size_starts_over() {
+        let mut heatmap = Heatmap::new(2, 2);
+        heatmap.update(&frame(&[255; 4]), Duration::ZERO);
+        heatmap.update(&frame(&[255; 4]), Duration::from_secs(10));
+        assert_eq!(heatmap.cells, vec![10.0; 4]);
+
+        let wide = Frame { width: 4, height: 1, luma: &[255; 4] };
+        heatmap.update(&wide, Duration::from_secs(10));
+        assert_eq!((heatmap.width, heatmap.height), (4, 1));
+        assert_eq!(heatmap.cells, vec![0.0; 4]);
+        assert_eq!(heatmap.sampled_seconds, 0.0);
+
+        // The new frame is the reference now
+        heatmap.update(&wide, Duration::from_secs(5));
+        assert_eq!(heatmap.cells, vec![5.0; 4]);
+    }
+
+    #[test]
+    fn heatmap_with_missing_cells_is_not_intact() {
+        let mut heatmap = Heatmap::new(3, 2);
+        assert!(heatmap.is_intact());
+
+        heatmap.cells.pop();
+        assert!(!heatmap.is_intact());
+
+        heatmap.width = usize::MAX;
+        assert!(!heatmap.is_intact());
+    }
+
+    #[test]
+    fn renders_relative_to_the_peak() {
+        let mut heatmap = Heatmap::new(2, 1);
+        assert_eq!(heatmap.render(), RgbImage::new(2, 1));
+
+        heatmap.cells = vec![0.0, 30.0];
+        let image = heatmap.render();
+        assert_eq!(image.get(0, 0), [0, 0, 0]);
+        assert_eq!(image.get(1, 0), [255, 255, 255]);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::burn_in;
+use crate::dialog;
+use crate::heatmap::Heatmap;
+use crate::i18n::{self, tr, tr_args, Text, Unit};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{fs, thread, cell::RefCell};
+use nwg::NativeUi;
+
+/// The heatmaps are tiny, they are scaled up by this factor to be seen
+const DISPLAY_SCALE: usize = 3;
+
+#[derive(Default)]
+pub struct HeatmapWindow {
+    window: nwg::Window,
+    icon: nwg::Icon,
+    big_icon: nwg::Icon,
+    description_label: nwg::Label,
+    monitor_label: nwg::Label,
+    monitor_combo: nwg::ComboBox<String>,
+    image_frame: nwg::ImageFrame,
+    /// Kept alive for as long as the image frame shows it
+    bitmap: RefCell<nwg::Bitmap>,
+    summary_label: nwg::Label,
+    export_dialog: nwg::FileDialog,
+    export_button: nwg::Button,
+    reset_button: nwg::Button,
+    close_button: nwg::Button,
+    /// Monitor device id => friendly name, the heatmaps are only stored by id
+    monitor_names: HashMap<String, String>,
+    /// The device ids of the monitors in the combo box, in the same order
+    device_ids: RefCell<Vec<String>>,
+    heatmaps: RefCell<HashMap<String, Heatmap>>,
+}
+
+impl HeatmapWindow {
+
+    /// Create the window on a new thread.
+    /// To alert the main GUI that the window closed, this function takes a notice sender object.
+    pub(crate) fn popup(sender: nwg::NoticeSender, monitor_names: HashMap<String, String>) -> thread::JoinHandle<()> {
+        return thread::spawn(move || {
+            let window = HeatmapWindow {
+                monitor_names,
+                ..Default::default()
+            };
+
+            // Create the UI just like in the main function
+            let app = HeatmapWindow::build_ui(window).expect("Failed to build UI");
+            app.refresh();
+            if let Some(hwnd) = app.monitor_combo.handle.hwnd() {
+                dialog::set_accessible_name(hwnd, &app.monitor_label.text());
+                dialog::focus_on_open(&app.window, hwnd);
+            }
+
+            nwg::dispatch_thread_events();
+
+            // Notice the main thread that the window closed
+            sender.notice();
+        })
+    }
+
+    /// Returns the device id of the selected monitor
+    fn selected_device_id(&self) -> Option<String> {
+        let index = self.monitor_combo.selection()?;
+        return self.device_ids.borrow().get(index).cloned();
+    }
+
+    /// Reloads the heatmaps and lists their monitors, keeping the selected one if it's still there
+    fn refresh(&self) {
+        let selected = self.selected_device_id();
+        *self.heatmaps.borrow_mut() = burn_in::heatmaps();
+
+        let mut device_ids: Vec<String> = self.heatmaps.borrow().keys().cloned().collect();
+        device_ids.sort();
+        let names = device_ids.iter()
+            .map(|device_id| self.monitor_names.get(device_id).cloned().unwrap_or_else(|| device_id.clone()))
+            .collect();
+        self.monitor_combo.set_collection(names);
+
+        let index = selected.and_then(|selected| device_ids.iter().position(|device_id| *device_id == selected));
+        self.monitor_combo.set_selection(index.or(if device_ids.is_empty() { None } else { Some(0) }));
+        *self.device_ids.borrow_mut() = device_ids;
+
+        self.show_selected();
+    }
+
+    /// Shows the heatmap of the selected monitor
+    fn show_selected(&self) {
+        let heatmaps = self.heatmaps.borrow();
+        let heatmap = self.selected_device_id().and_then(|device_id| heatmaps.get(&device_id));
+
+        let heatmap = match heatmap {
+            Some(heatmap) => heatmap,
+            None => {
+                self.image_frame.set_bitmap(None);
+                self.summary_label.set_text(tr(Text::HeatmapEmpty));
+                self.export_button.set_enabled(false);
+                self.reset_button.set_enabled(false);
+                return;
+            }
+        };
+
+        let bmp = heatmap.render().scaled(DISPLAY_SCALE).to_bmp();
+        match nwg::Bitmap::from_bin(&bmp) {
+            Ok(bitmap) => {
+                *self.bitmap.borrow_mut() = bitmap;
+                self.image_frame.set_bitmap(Some(&*self.bitmap.borrow()));
+            }
+            Err(err) => log::error!("Failed to show the heatmap: {}", err),
+        }
+
+        let hours = (heatmap.sampled_seconds / 3600.0).round() as u64;
+        self.summary_label.set_text(&tr_args(Text::HeatmapSummary, &[&i18n::count(Unit::Hour, hours)]));
+        self.export_button.set_enabled(true);
+        self.reset_button.set_enabled(true);
+    }
+
+    /// Saves the heatmap of the selected monitor as a PNG file where the user picks
+    fn export(&self) {
+        let image = {
+            let heatmaps = self.heatmaps.borrow();
+            match self.selected_device_id().and_then(|device_id| heatmaps.get(&device_id)) {
+                Some(heatmap) => heatmap.render(),
+                None => return,
+            }
+        };
+
+        if !self.export_dialog.run(Some(&self.window)) {
+            return;
+        }
+        let mut path = match self.export_dialog.get_selected_item() {
+            Ok(path) => PathBuf::from(path),
+            Err(_) => return,
+        };
+        if path.extension().is_none() {
+            path.set_extension("png");
+        }
+
+        if let Err(err) = fs::write(&path, image.to_png()) {
+            log::error!("Failed to export the heatmap to {:?}: {}", path, err);
+            nwg::modal_error_message(&self.window, tr(Text::HeatmapTitle), &tr_args(Text::HeatmapExportFailed, &[&err.to_string()]));
+        }
+    }
+
+    /// Starts the heatmap of the selected monitor over, after asking
+    fn reset(&self) {
+        let device_id = match self.selected_device_id() {
+            Some(device_id) => device_id,
+            None => return,
+        };
+
+        let params = nwg::MessageParams {
+            title: tr(Text::HeatmapTitle),
+            content: tr(Text::HeatmapResetConfirm),
+            buttons: nwg::MessageButtons::YesNo,
+            icons: nwg::MessageIcons::Question,
+        };
+        if nwg::modal_message(&self.window, &params) != nwg::MessageChoice::Yes {
+            return;
+        }
+
+        burn_in::reset(&device_id);
+        self.refresh();
+    }
+
+    fn close(&self) {
+        self.window.close();
+    }
+
+    fn exit(&self) {
+        nwg::stop_thread_dispatch();
+    }
+}
+
+mod heatmap_window_ui {
+    use native_windows_gui as nwg;
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use std::ops::Deref;
+    use crate::dialog::{self, DialogKey};
+
+    pub struct HeatmapWindowUI {
+        inner: Rc<HeatmapWindow>,
+        default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
+    }
+
+    impl NativeUi<HeatmapWindowUI> for HeatmapWindow {
+        fn build_ui(mut data: HeatmapWindow) -> Result<HeatmapWindowUI, nwg::NwgError> {
+            // Controls
+            nwg::Window::builder()
+                .size((480, 400))
+                .center(true)
+                .title(tr(Text::HeatmapTitle))
+                .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE | nwg::WindowFlags::POPUP)
+                .build(&mut data.window)?;
+
+            dialog::set_window_icons(&data.window, &mut data.icon, &mut data.big_icon)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::HeatmapDescription))
+                .parent(&data.window)
+                .build(&mut data.description_label)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::ColumnMonitor))
+                .parent(&data.window)
+                .build(&mut data.monitor_label)?;
+
+            nwg::ComboBox::builder()
+                .parent(&data.window)
+                .build(&mut data.monitor_combo)?;
+
+            nwg::ImageFrame::builder()
+                .background_color(Some([0, 0, 0]))
+                .parent(&data.window)
+                .build(&mut data.image_frame)?;
+
+            nwg::Label::builder()
+                .text("")
+                .parent(&data.window)
+                .build(&mut data.summary_label)?;
+
+            nwg::FileDialog::builder()
+                .title(tr(Text::HeatmapExport))
+                .action(nwg::FileDialogAction::Save)
+                .filters("PNG (*.png)")
+                .build(&mut data.export_dialog)?;
+
+            nwg::Button::builder()
+                .text(tr(Text::HeatmapExport))
+                .parent(&data.window)
+                .build(&mut data.export_button)?;
+
+            nwg::Button::builder()
+                .text(tr(Text::HeatmapReset))
+                .parent(&data.window)
+                .build(&mut data.reset_button)?;
+
+            nwg::Button::builder()
+                .text(tr(Text::ButtonClose))
+                .parent(&data.window)
+                .build(&mut data.close_button)?;
+
+            let grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.window)
+                .spacing(2)
+                .max_row(Some(12))
+                .max_column(Some(3))
+                .child_item(nwg::GridLayoutItem::new(&data.description_label, 0, 0, 3, 2))
+                .child(0, 2, &data.monitor_label)
+                .child_item(nwg::GridLayoutItem::new(&data.monitor_combo, 1, 2, 2, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.image_frame, 0, 3, 3, 7))
+                .child_item(nwg::GridLayoutItem::new(&data.summary_label, 0, 10, 3, 1))
+                .child(0, 11, &data.export_button)
+                .child(1, 11, &data.reset_button)
+                .child(2, 11, &data.close_button)
+                .build(&grid)?;
+
+            // Wrap-up
+            let ui = HeatmapWindowUI {
+                inner: Rc::new(data),
+                default_handler: Default::default(),
+                raw_handler: Default::default(),
+            };
+
+            use nwg::Event as E;
+
+            // Events
+            let evt_ui = Rc::downgrade(&ui.inner);
+            let handle_events = move |evt, _evt_data, handle| {
+                if let Some(ui) = evt_ui.upgrade() {
+                    match evt {
+                        E::OnComboxBoxSelection => {
+                            if &handle == &ui.monitor_combo {
+                                HeatmapWindow::show_selected(&ui);
+                            }
+                        }
+                        E::OnButtonClick => {
+                            if &handle == &ui.export_button {
+                                HeatmapWindow::export(&ui);
+                            } else if &handle == &ui.reset_button {
+                                HeatmapWindow::reset(&ui);
+                            } else if &handle == &ui.close_button {
+                                HeatmapWindow::close(&ui);
+                            }
+                        }
+                        E::OnWindowClose => {
+                            if &handle == &ui.window {
+                                HeatmapWindow::exit(&ui);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            };
+
+            ui.default_handler.borrow_mut().push(
+                nwg::full_bind_event_handler(&ui.window.handle, handle_events)
+            );
+
+            // Enter reloads the heatmaps, Escape closes the window
+            let key_ui = Rc::downgrade(&ui.inner);
+            let handle_keys = move |key: DialogKey| {
+                if let Some(ui) = key_ui.upgrade() {
+                    match key {
+                        DialogKey::Ok => HeatmapWindow::refresh(&ui),
+                        DialogKey::Cancel => HeatmapWindow::close(&ui),
+                    }
+                }
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                dialog::bind_dialog_keys(&ui.window, handle_keys)?
+            );
+
+            return Ok(ui);
+        }
+    }
+
+    impl Drop for HeatmapWindowUI {
+        /// To make sure that everything is freed without issues, the default handler must be unbound.
+        fn drop(&mut self) {
+            let mut handlers = self.default_handler.borrow_mut();
+            for handler in handlers.drain(0..) {
+                nwg::unbind_event_handler(&handler);
+            }
+
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
+        }
+    }
+
+    impl Deref for HeatmapWindowUI {
+        type Target = HeatmapWindow;
+
+        fn deref(&self) -> &HeatmapWindow {
+            &self.inner
+        }
+    }
+}
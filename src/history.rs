@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::sysinfoapi::GetLocalTime;
+use crate::i18n::{tr, Text};
+use crate::settings_path::settings_path;
+
+/// The log is rotated once it grows past this size, the previous log is kept as a single backup
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+pub use crate::easing::Rect;
+
+/// Why a visible window was left where it was
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    Maximized,
+    Snapped,
+    ExcludedClass,
+    MonitorDisabled,
+    TooLarge,
+    MoveFailed,
+}
+
+impl SkipReason {
+    /// The reason as shown to the user
+    pub fn label(&self) -> &'static str {
+        return match self {
+            SkipReason::Maximized => tr(Text::SkipMaximized),
+            SkipReason::Snapped => tr(Text::SkipSnapped),
+            SkipReason::ExcludedClass => tr(Text::SkipExcludedClass),
+            SkipReason::MonitorDisabled => tr(Text::SkipMonitorDisabled),
+            SkipReason::TooLarge => tr(Text::SkipTooLarge),
+            SkipReason::MoveFailed => tr(Text::SkipMoveFailed),
+        };
+    }
+}
+
+/// What happened to one window in one pass, one line of the log
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryEntry {
+    /// When the pass started, in milliseconds since the Unix epoch, shared by every window of the pass
+    pub pass: u64,
+    /// The local date of the pass, YYYY-MM-DD
+    pub date: String,
+    /// The executable file name of the process that owns the window
+    pub process: String,
+    pub class: String,
+    /// The device id of the monitor the window is on
+    pub monitor: String,
+    pub old_rect: Rect,
+    /// Where the window was moved to, None if it was skipped
+    pub new_rect: Option<Rect>,
+    pub skipped: Option<SkipReason>,
+}
+
+impl HistoryEntry {
+    /// Returns how far the window was moved, in pixels
+    pub fn displacement(&self) -> f64 {
+        return match self.new_rect {
+            Some(new_rect) => {
+                let dx = (new_rect.0 - self.old_rect.0) as f64;
+                let dy = (new_rect.1 - self.old_rect.1) as f64;
+                dx.hypot(dy)
+            }
+            None => 0.0,
+        };
+    }
+}
+
+/// The start time and local date of a pass, stamped on every entry of the pass
+pub fn pass_stamp() -> (u64, String) {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+
+    let mut now: SYSTEMTIME = unsafe { std::mem::zeroed() };
+    unsafe { GetLocalTime(&mut now) };
+    let date = format!("{:04}-{:02}-{:02}", now.wYear, now.wMonth, now.wDay);
+
+    return (millis, date);
+}
+
+lazy_static! {
+    /// Serializes writes to the log file, the mover and undo can run close to each other
+    static ref LOG_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// The log file, next to the settings file
+fn log_path() -> PathBuf {
+    return settings_path().with_file_name("history.jsonl");
+}
+
+/// The previous log file, kept after a rotation
+fn previous_log_path() -> PathBuf {
+    return settings_path().with_file_name("history.1.jsonl");
+}
+
+/// Appends the entries of a pass to the log, rotating it first if it got too big
+pub fn append(entries: &[HistoryEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+
+    let _lock = LOG_LOCK.lock().unwrap();
+    let path = log_path();
+
+    let too_big = fs::metadata(&path).map(|metadata| metadata.len() > MAX_LOG_BYTES).unwrap_or(false);
+    if too_big {
+        if let Err(err) = fs::rename(&path, previous_log_path()) {
+            log::error!("Failed to rotate the history log: {}", err);
+        }
+    }
+
+    let result = OpenOptions::new().create(true).append(true).open(&path).and_then(|mut file| {
+        let mut lines = String::new();
+        for entry in entries {
+            // Serializing these plain values can't fail
+            lines.push_str(&serde_json::to_string(entry).unwrap());
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())
+    });
+
+    if let Err(err) = result {
+        log::error!("Failed to write the history log: {}", err);
+    }
+}
+
+/// The totals shown in the Statistics window
+#[derive(Default)]
+pub struct Statistics {
+    /// Local date => (passes that moved at least one window, windows moved)
+    pub days: BTreeMap<String, (u32, u32)>,
+    /// Skip reason => windows skipped for it
+    pub skipped: BTreeMap<SkipReason, u32>,
+    /// Monitor device id => pixels moved in total
+    pub displacement: BTreeMap<String, f64>,
+}
+
+/// Adds up everything in the log, the previous log included.
+/// Lines that can't be read are skipped, a half written line must not hide the rest.
+pub fn statistics() -> Statistics {
+    let _lock = LOG_LOCK.lock().unwrap();
+    let mut statistics = Statistics::default();
+    let mut last_pass: Option<u64> = None;
+
+    for path in [previous_log_path(), log_path()].iter() {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+
+        let entries = BufReader::new(file).lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<HistoryEntry>(&line).ok());
+
+        for entry in entries {
+            if let Some(reason) = entry.skipped {
+                *statistics.skipped.entry(reason).or_insert(0) += 1;
+                continue;
+            }
+
+            let day = statistics.days.entry(entry.date.clone()).or_insert((0, 0));
+            // The entries of a pass are written together, so a new pass starts where the stamp changes
+            if last_pass != Some(entry.pass) {
+                day.0 += 1;
+                last_pass = Some(entry.pass);
+            }
+            day.1 += 1;
+
+            *statistics.displacement.entry(entry.monitor.clone()).or_insert(0.0) += entry.displacement();
+        }
+    }
+
+    return statistics;
+}
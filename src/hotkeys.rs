@@ -0,0 +1,29 @@
+use crate::i18n::{tr_args, Text};
+use winapi::shared::minwindef::UINT;
+use winapi::shared::windef::HWND;
+use winapi::um::winuser::{RegisterHotKey, UnregisterHotKey, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN};
+
+pub use crate::key_bindings::{find_conflicts, Hotkey, HotkeyAction};
+
+/// Returns the Win32 modifier flags of the hotkey for RegisterHotKey
+fn modifiers(hotkey: &Hotkey) -> UINT {
+    let mut modifiers = MOD_NOREPEAT as UINT;
+    if hotkey.ctrl { modifiers |= MOD_CONTROL as UINT; }
+    if hotkey.alt { modifiers |= MOD_ALT as UINT; }
+    if hotkey.shift { modifiers |= MOD_SHIFT as UINT; }
+    if hotkey.win { modifiers |= MOD_WIN as UINT; }
+    return modifiers;
+}
+
+/// Registers the hotkey for the window, WM_HOTKEY is sent to it with the id when pressed.
+/// Fails if another application already owns the same key combination.
+pub fn register(hwnd: HWND, id: i32, hotkey: &Hotkey) -> Result<(), String> {
+    if unsafe { RegisterHotKey(hwnd, id, modifiers(hotkey), hotkey.key) } == 0 {
+        return Err(tr_args(Text::HotkeyInUse, &[hotkey, &std::io::Error::last_os_error()]));
+    }
+    return Ok(());
+}
+
+pub fn unregister(hwnd: HWND, id: i32) {
+    unsafe { UnregisterHotKey(hwnd, id) };
+}
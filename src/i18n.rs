@@ -0,0 +1,703 @@
+use std::fmt::Display;
+use lazy_static::lazy_static;
+#[cfg(windows)]
+use winapi::um::winnls::GetUserDefaultUILanguage;
+#[cfg(windows)]
+use winapi::um::winnt::LANG_GERMAN;
+
+/// The languages the user interface is available in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    German,
+}
+
+impl Locale {
+    /// Picks the language of the Windows user interface, English if it isn't one of ours
+    #[cfg(windows)]
+    fn detect() -> Locale {
+        let language_id = unsafe { GetUserDefaultUILanguage() };
+        // The primary language is in the low 10 bits, the sublanguage (region) doesn't matter here
+        let primary_language = (language_id & 0x3FF) as u16;
+
+        return match primary_language {
+            LANG_GERMAN => Locale::German,
+            _ => Locale::English,
+        };
+    }
+
+    /// There is no Windows user interface to follow elsewhere, that's only where the tests run
+    #[cfg(not(windows))]
+    fn detect() -> Locale {
+        return Locale::English;
+    }
+}
+
+lazy_static! {
+    static ref LOCALE: Locale = Locale::detect();
+}
+
+/// Returns the language the user interface is shown in, detected once on first use
+pub fn locale() -> Locale {
+    return *LOCALE;
+}
+
+/// Declares the `Text` enum along with the list of all its variants, so the list can't miss one
+macro_rules! texts {
+    ($(#[$attribute:meta])* pub enum Text { $($(#[$variant_attribute:meta])* $variant:ident,)* }) => {
+        $(#[$attribute])*
+        pub enum Text {
+            $($(#[$variant_attribute])* $variant,)*
+        }
+
+        impl Text {
+            /// Every text, for the tests that check the translations
+            #[cfg(test)]
+            pub const ALL: &'static [Text] = &[$(Text::$variant,)*];
+        }
+    };
+}
+
+texts! {
+    /// Every user-facing string. Placeholders are written as {0}, {1}, ... so translations can reorder them.
+    /// Each table below matches on all of them, so a missing translation doesn't compile.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Text {
+        // Tray menu
+        MenuEnabled,
+        MenuShiftNow,
+        MenuUndo,
+        MenuRestoreWindows,
+        MenuNextProfile,
+        MenuPauseFor,
+        MenuPauseFifteenMinutes,
+        MenuPauseOneHour,
+        MenuPauseUntilTomorrow,
+        MenuDelay,
+        MenuCustomDelay,
+        MenuMaxDistance,
+        MenuDistancePreset,
+        MenuCustomDistance,
+        MenuScreens,
+        MenuMonitor,
+        MenuSettings,
+        MenuStatistics,
+        MenuDiagnostics,
+        MenuHeatmap,
+        MenuOpenLogFolder,
+        MenuExit,
+
+        // Tooltip
+        Tooltip,
+        StatusRunning,
+        StatusPaused,
+        StatusPausedFor,
+        Custom,
+        And,
+        DistanceSmall,
+        DistanceMedium,
+        DistanceLarge,
+        DistancePreset,
+        DistanceCustom,
+
+        // Notifications and messages
+        MessageRunning,
+        MessageAlreadyRunning,
+        MessageProfile,
+        MessageNoProfiles,
+        HotkeysUnavailableTitle,
+        ConfigParseFailedTitle,
+        ConfigParseFailedMessage,
+        Offline,
+
+        // Dialogs
+        ButtonOk,
+        ButtonCancel,
+        DelayDialogTitle,
+        DelayDialogLabel,
+        DistanceDialogTitle,
+        DistanceXLabel,
+        DistanceYLabel,
+        GlideLabel,
+        DesktopShiftCheck,
+        NudgeMaximizedCheck,
+        SnapGroupsCheck,
+        CoherentShiftCheck,
+        MicroShiftCheck,
+        RecordHeatmapCheck,
+        MicroDelayLabel,
+        MicroDistanceLabel,
+        TaskbarModeLabel,
+        TaskbarModeOff,
+        TaskbarModeAutoHide,
+        TaskbarModeHide,
+        TaskbarModeDim,
+        TaskbarIdleLabel,
+        DimStaticCheck,
+        DimStaticAfterLabel,
+        DimStrengthLabel,
+        OrbitCheck,
+        OrbitRadiusLabel,
+        OrbitDelayLabel,
+
+        // Settings window
+        SettingsTitle,
+        TabGeneral,
+        TabSchedule,
+        TabMonitors,
+        TabRules,
+        TabHotkeys,
+        TabProtection,
+        TabAdvanced,
+        DelaySecondsLabel,
+        MonitorsLabel,
+        HotkeysLabel,
+        ScheduleCheck,
+        ScheduleStartLabel,
+        ScheduleEndLabel,
+        RulesLabel,
+        RuleInvalid,
+        SettingsFileLabel,
+        OpenFolder,
+        OpenFolderFailedTitle,
+        InvalidSettingsTitle,
+
+        // Statistics window
+        StatisticsTitle,
+        StatisticsDaysLabel,
+        StatisticsSkippedLabel,
+        StatisticsDisplacementLabel,
+        ColumnDate,
+        ColumnPasses,
+        ColumnWindowsMoved,
+        ColumnReason,
+        ColumnWindows,
+        ColumnMonitor,
+        ColumnPixels,
+        ButtonClose,
+        SkipMaximized,
+        SkipSnapped,
+        SkipExcludedClass,
+        SkipMonitorDisabled,
+        SkipTooLarge,
+        SkipMoveFailed,
+
+        // Diagnostics window
+        DiagnosticsTitle,
+        DiagnosticsDescription,
+        HeatmapTitle,
+        HeatmapDescription,
+        HeatmapEmpty,
+        HeatmapSummary,
+        HeatmapExport,
+        HeatmapReset,
+        HeatmapResetConfirm,
+        HeatmapExportFailed,
+        ColumnDecision,
+        ColumnTitle,
+        ColumnProcess,
+        ColumnClass,
+        ColumnPosition,
+        ColumnTarget,
+        ButtonRefresh,
+        DecisionMove,
+
+        // Hotkey actions
+        ActionToggle,
+        ActionPauseFor,
+
+        // Settings validation
+        DelayTooLow,
+        ProfileInvalid,
+        DelayTooHigh,
+        DistanceXTooLow,
+        DistanceYTooLow,
+        HotkeyInvalid,
+        GlideTooLong,
+        MicroDelayTooLow,
+        MicroDelayTooHigh,
+        MicroDistanceInvalid,
+        TaskbarIdleInvalid,
+        DimStaticTimeInvalid,
+        DimStrengthInvalid,
+        ScheduleTimeInvalid,
+        OrbitRadiusInvalid,
+        OrbitDelayInvalid,
+        LogLevelInvalid,
+        LogModuleLevelInvalid,
+
+        // Hotkey bindings
+        HotkeyEmptyKey,
+        HotkeyRepeatedModifier,
+        HotkeyMoreThanOneKey,
+        HotkeyUnknownKey,
+        HotkeyNoKey,
+        HotkeyNoModifier,
+        HotkeyConflict,
+        HotkeyInUse,
+    }
+}
+
+/// Units that are counted in the user interface, each with its singular and plural forms
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Pixel,
+    Millisecond,
+}
+
+/// The plural categories of the CLDR plural rules that the shipped languages use
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PluralCategory {
+    One,
+    Other,
+}
+
+/// Returns the text in the user's language
+pub fn tr(text: Text) -> &'static str {
+    return match locale() {
+        Locale::English => english(text),
+        Locale::German => german(text),
+    };
+}
+
+/// Returns the text in the user's language with the placeholders filled in, {0} is the first argument
+pub fn tr_args(text: Text, args: &[&dyn Display]) -> String {
+    let mut result = tr(text).to_string();
+    for (index, arg) in args.iter().enumerate() {
+        result = result.replace(&format!("{{{}}}", index), &arg.to_string());
+    }
+    return result;
+}
+
+/// Returns the count followed by the unit in the right plural form, e.g. "1 second" or "5 seconds"
+pub fn count(unit: Unit, count: u64) -> String {
+    let locale = locale();
+    let (one, other) = unit_forms(locale, unit);
+
+    let form = match plural_category(locale, count) {
+        PluralCategory::One => one,
+        PluralCategory::Other => other,
+    };
+    return format!("{} {}", count, form);
+}
+
+/// English and German share the same rule, only exactly one is singular
+fn plural_category(locale: Locale, count: u64) -> PluralCategory {
+    return match locale {
+        Locale::English | Locale::German => {
+            if count == 1 { PluralCategory::One } else { PluralCategory::Other }
+        }
+    };
+}
+
+/// Returns the singular and plural form of the unit
+fn unit_forms(locale: Locale, unit: Unit) -> (&'static str, &'static str) {
+    return match (locale, unit) {
+        (Locale::English, Unit::Second) => ("second", "seconds"),
+        (Locale::English, Unit::Minute) => ("minute", "minutes"),
+        (Locale::English, Unit::Hour) => ("hour", "hours"),
+        (Locale::English, Unit::Pixel) => ("pixel", "pixels"),
+        (Locale::English, Unit::Millisecond) => ("millisecond", "milliseconds"),
+        (Locale::German, Unit::Second) => ("Sekunde", "Sekunden"),
+        (Locale::German, Unit::Minute) => ("Minute", "Minuten"),
+        (Locale::German, Unit::Hour) => ("Stunde", "Stunden"),
+        (Locale::German, Unit::Pixel) => ("Pixel", "Pixel"),
+        (Locale::German, Unit::Millisecond) => ("Millisekunde", "Millisekunden"),
+    };
+}
+
+fn english(text: Text) -> &'static str {
+    return match text {
+        Text::MenuEnabled => "Enabled",
+        Text::MenuShiftNow => "Shift now",
+        Text::MenuUndo => "Undo last shift",
+        Text::MenuRestoreWindows => "Restore windows",
+        Text::MenuNextProfile => "Next profile",
+        Text::MenuPauseFor => "Pause for",
+        Text::MenuPauseFifteenMinutes => "15 minutes",
+        Text::MenuPauseOneHour => "1 hour",
+        Text::MenuPauseUntilTomorrow => "Until tomorrow",
+        Text::MenuDelay => "Delay",
+        Text::MenuCustomDelay => "Custom delay",
+        Text::MenuMaxDistance => "Max distance",
+        Text::MenuDistancePreset => "{0}, {1} px",
+        Text::MenuCustomDistance => "Custom distance",
+        Text::MenuScreens => "Screens",
+        Text::MenuMonitor => "Monitor - {0} ({1})",
+        Text::MenuSettings => "Settings…",
+        Text::MenuStatistics => "Statistics…",
+        Text::MenuDiagnostics => "Diagnostics…",
+        Text::MenuHeatmap => "Burn-in heatmap…",
+        Text::MenuOpenLogFolder => "Open log folder",
+        Text::MenuExit => "Exit",
+
+        Text::Tooltip => "OLEDShift\nStatus: {0}\nDelay: {1}\nMax distance: {2}",
+        Text::StatusRunning => "running",
+        Text::StatusPaused => "paused",
+        Text::StatusPausedFor => "paused, {0} left",
+        Text::Custom => "{0} (Custom)",
+        Text::And => "{0} and {1}",
+        Text::DistanceSmall => "Small",
+        Text::DistanceMedium => "Medium",
+        Text::DistanceLarge => "Large",
+        Text::DistancePreset => "{0} px ({1})",
+        Text::DistanceCustom => "{0} px (x) {1} px (y)",
+
+        Text::MessageRunning => "OLEDShift is running in the system tray",
+        Text::MessageAlreadyRunning => "OLEDShift is already running in the system tray",
+        Text::MessageProfile => "Switched to the profile \"{0}\"",
+        Text::MessageNoProfiles => "There are no profiles in the settings file to switch between",
+        Text::HotkeysUnavailableTitle => "Some hotkeys are not available",
+        Text::ConfigParseFailedTitle => "Config parsing failed",
+        Text::ConfigParseFailedMessage => "Failed to parse the config file!\nThe default settings will be used instead.\n\nError: {0}",
+        Text::Offline => "(Offline)",
+
+        Text::ButtonOk => "Ok",
+        Text::ButtonCancel => "Cancel",
+        Text::DelayDialogTitle => "Delay Select Dialog",
+        Text::DelayDialogLabel => "Value, in seconds:",
+        Text::DistanceDialogTitle => "Distance Dialog",
+        Text::DistanceXLabel => "Max distance x (pixels):",
+        Text::DistanceYLabel => "Max distance y (pixels):",
+        Text::GlideLabel => "Glide duration (ms, 0 = instant):",
+        Text::DesktopShiftCheck => "Shift the desktop icons and the wallpaper too",
+        Text::NudgeMaximizedCheck => "Nudge maximized windows within the screen",
+        Text::SnapGroupsCheck => "Move snapped windows together with their neighbours",
+        Text::CoherentShiftCheck => "Coherent shift: move all windows of a monitor by the same offset",
+        Text::MicroShiftCheck => "Micro-shift: move the windows a pixel at a time, every few seconds",
+        Text::RecordHeatmapCheck => "Record a burn-in heatmap of every monitor",
+        Text::MicroDelayLabel => "Micro-shift delay (ms):",
+        Text::MicroDistanceLabel => "Micro-shift distance (pixels):",
+        Text::TaskbarModeLabel => "Protect the taskbar while idle:",
+        Text::TaskbarModeOff => "Off",
+        Text::TaskbarModeAutoHide => "Turn on auto-hide",
+        Text::TaskbarModeHide => "Hide it",
+        Text::TaskbarModeDim => "Dim it",
+        Text::TaskbarIdleLabel => "Idle time before protecting (minutes):",
+        Text::DimStaticCheck => "Slowly dim the regions of the screen that don't change",
+        Text::DimStaticAfterLabel => "Unchanged time before dimming (minutes):",
+        Text::DimStrengthLabel => "Dimming strength (%):",
+        Text::OrbitCheck => "Screen orbit: shift the whole image, maximized windows included",
+        Text::OrbitRadiusLabel => "Screen orbit radius (pixels):",
+        Text::OrbitDelayLabel => "Screen orbit step (seconds):",
+
+        Text::SettingsTitle => "OLEDShift Settings",
+        Text::TabGeneral => "General",
+        Text::TabSchedule => "Schedule",
+        Text::TabMonitors => "Monitors",
+        Text::TabRules => "Rules",
+        Text::TabHotkeys => "Hotkeys",
+        Text::TabProtection => "Protection",
+        Text::TabAdvanced => "Advanced",
+        Text::DelaySecondsLabel => "Delay (seconds):",
+        Text::MonitorsLabel => "Move the windows on these screens:",
+        Text::HotkeysLabel => "Bindings like Ctrl+Alt+Shift+S, leave empty to disable:",
+        Text::ScheduleCheck => "Only move the windows at certain times of the day",
+        Text::ScheduleStartLabel => "From (HH:MM):",
+        Text::ScheduleEndLabel => "Until (HH:MM):",
+        Text::RulesLabel => "Nudge maximized windows per program, one per line like vlc.exe = off:",
+        Text::RuleInvalid => "The rule \"{0}\" isn't of the form program.exe = on or program.exe = off.",
+        Text::SettingsFileLabel => "Settings file:",
+        Text::OpenFolder => "Open folder",
+        Text::OpenFolderFailedTitle => "Failed to open the folder",
+        Text::InvalidSettingsTitle => "Invalid settings",
+
+        Text::StatisticsTitle => "OLEDShift Statistics",
+        Text::StatisticsDaysLabel => "Shifts per day:",
+        Text::StatisticsSkippedLabel => "Windows skipped and why:",
+        Text::StatisticsDisplacementLabel => "Distance moved per monitor:",
+        Text::ColumnDate => "Date",
+        Text::ColumnPasses => "Shifts",
+        Text::ColumnWindowsMoved => "Windows moved",
+        Text::ColumnReason => "Reason",
+        Text::ColumnWindows => "Windows",
+        Text::ColumnMonitor => "Monitor",
+        Text::ColumnPixels => "Pixels",
+        Text::ButtonClose => "Close",
+        Text::SkipMaximized => "Maximized",
+        Text::SkipSnapped => "Snapped",
+        Text::SkipExcludedClass => "Excluded window type",
+        Text::SkipMonitorDisabled => "Screen turned off in OLEDShift",
+        Text::SkipTooLarge => "Larger than the screen",
+        Text::SkipMoveFailed => "Refused to be moved",
+
+        Text::DiagnosticsTitle => "OLEDShift diagnostics",
+        Text::DiagnosticsDescription => "What the next shift would do with every visible window. Nothing is moved, the targets are one random pick each.",
+        Text::HeatmapTitle => "OLEDShift burn-in heatmap",
+        Text::HeatmapDescription => "Where each monitor showed bright content that didn't change. The brighter the color, the more wear. Turn on the recording in the Advanced tab of the settings.",
+        Text::HeatmapEmpty => "Nothing recorded yet.",
+        Text::HeatmapSummary => "Recorded over {0}.",
+        Text::HeatmapExport => "Export PNG…",
+        Text::HeatmapReset => "Start over",
+        Text::HeatmapResetConfirm => "Forget the heatmap of this monitor and start recording it over?",
+        Text::HeatmapExportFailed => "The heatmap couldn't be exported: {0}",
+        Text::ColumnDecision => "Decision",
+        Text::ColumnTitle => "Window",
+        Text::ColumnProcess => "Process",
+        Text::ColumnClass => "Class",
+        Text::ColumnPosition => "Position",
+        Text::ColumnTarget => "Target",
+        Text::ButtonRefresh => "Refresh",
+        Text::DecisionMove => "Move",
+
+        Text::ActionToggle => "Enable/disable",
+        Text::ActionPauseFor => "Pause for 15 minutes",
+
+        Text::DelayTooLow => "The delay was too low, it has been set to the lowest possible value of {0}.",
+        Text::ProfileInvalid => "The delay or the max distance of the profile \"{0}\" was out of range, it has been set to the closest allowed value.",
+        Text::DelayTooHigh => "The delay was too high, it has been set to the highest possible value of {0}.",
+        Text::DistanceXTooLow => "The max distance X was too low, it has been set to the lowest possible value of {0}.",
+        Text::DistanceYTooLow => "The max distance Y was too low, it has been set to the lowest possible value of {0}.",
+        Text::HotkeyInvalid => "The {0} hotkey was invalid, {1}. It has been disabled.",
+        Text::GlideTooLong => "The glide duration was too long, it has been set to the longest possible value of {0}.",
+        Text::MicroDelayTooLow => "The micro-shift delay was too low, it has been set to the lowest possible value of {0}.",
+        Text::MicroDelayTooHigh => "The micro-shift delay was too high, it has been set to the highest possible value of {0}.",
+        Text::MicroDistanceInvalid => "The micro-shift distance must be between {0} and {1}, it has been set to the closest one.",
+        Text::TaskbarIdleInvalid => "The taskbar idle time must be between {0} and {1}, it has been set to the closest one.",
+        Text::DimStaticTimeInvalid => "The unchanged time before dimming must be between {0} and {1}, it has been set to the closest one.",
+        Text::DimStrengthInvalid => "The dimming strength must be between {0} and {1}, it has been set to the closest one.",
+        Text::ScheduleTimeInvalid => "The schedule time \"{0}\" isn't a time like 08:30, it has been set to {1}.",
+        Text::OrbitRadiusInvalid => "The screen orbit radius must be between {0} and {1}, it has been set to the closest one.",
+        Text::OrbitDelayInvalid => "The screen orbit step must be between {0} and {1}, it has been set to the closest one.",
+        Text::LogLevelInvalid => "The log level \"{0}\" is unknown, it has been set to info.",
+        Text::LogModuleLevelInvalid => "The log level \"{0}\" of the {1} module is unknown, it has been removed.",
+
+        Text::HotkeyEmptyKey => "\"{0}\" has an empty key",
+        Text::HotkeyRepeatedModifier => "\"{0}\" repeats the {1} modifier",
+        Text::HotkeyMoreThanOneKey => "\"{0}\" has more than one key",
+        Text::HotkeyUnknownKey => "\"{0}\" is not a known key",
+        Text::HotkeyNoKey => "\"{0}\" has no key, only modifiers",
+        Text::HotkeyNoModifier => "\"{0}\" needs at least one modifier",
+        Text::HotkeyConflict => "{0} is already bound to \"{1}\"",
+        Text::HotkeyInUse => "{0} is already in use by another application: {1}",
+    };
+}
+
+fn german(text: Text) -> &'static str {
+    return match text {
+        Text::MenuEnabled => "Aktiviert",
+        Text::MenuShiftNow => "Jetzt verschieben",
+        Text::MenuUndo => "Letzte Verschiebung rückgängig machen",
+        Text::MenuRestoreWindows => "Fenster wiederherstellen",
+        Text::MenuNextProfile => "Nächstes Profil",
+        Text::MenuPauseFor => "Pausieren für",
+        Text::MenuPauseFifteenMinutes => "15 Minuten",
+        Text::MenuPauseOneHour => "1 Stunde",
+        Text::MenuPauseUntilTomorrow => "Bis morgen",
+        Text::MenuDelay => "Verzögerung",
+        Text::MenuCustomDelay => "Eigene Verzögerung",
+        Text::MenuMaxDistance => "Maximale Entfernung",
+        Text::MenuDistancePreset => "{0}, {1} px",
+        Text::MenuCustomDistance => "Eigene Entfernung",
+        Text::MenuScreens => "Bildschirme",
+        Text::MenuMonitor => "Monitor - {0} ({1})",
+        Text::MenuSettings => "Einstellungen…",
+        Text::MenuStatistics => "Statistik…",
+        Text::MenuDiagnostics => "Diagnose…",
+        Text::MenuHeatmap => "Einbrenn-Heatmap…",
+        Text::MenuOpenLogFolder => "Protokollordner öffnen",
+        Text::MenuExit => "Beenden",
+
+        Text::Tooltip => "OLEDShift\nStatus: {0}\nVerzögerung: {1}\nMax. Entfernung: {2}",
+        Text::StatusRunning => "aktiv",
+        Text::StatusPaused => "pausiert",
+        Text::StatusPausedFor => "pausiert, noch {0}",
+        Text::Custom => "{0} (Eigene)",
+        Text::And => "{0} und {1}",
+        Text::DistanceSmall => "Klein",
+        Text::DistanceMedium => "Mittel",
+        Text::DistanceLarge => "Groß",
+        Text::DistancePreset => "{0} px ({1})",
+        Text::DistanceCustom => "{0} px (x) {1} px (y)",
+
+        Text::MessageRunning => "OLEDShift läuft im Infobereich",
+        Text::MessageAlreadyRunning => "OLEDShift läuft bereits im Infobereich",
+        Text::MessageProfile => "Zum Profil \"{0}\" gewechselt",
+        Text::MessageNoProfiles => "Die Einstellungsdatei enthält keine Profile, zwischen denen gewechselt werden kann",
+        Text::HotkeysUnavailableTitle => "Einige Tastenkombinationen sind nicht verfügbar",
+        Text::ConfigParseFailedTitle => "Einstellungen konnten nicht gelesen werden",
+        Text::ConfigParseFailedMessage => "Die Einstellungsdatei konnte nicht gelesen werden!\nStattdessen werden die Standardeinstellungen verwendet.\n\nFehler: {0}",
+        Text::Offline => "(Nicht verbunden)",
+
+        Text::ButtonOk => "OK",
+        Text::ButtonCancel => "Abbrechen",
+        Text::DelayDialogTitle => "Verzögerung wählen",
+        Text::DelayDialogLabel => "Wert in Sekunden:",
+        Text::DistanceDialogTitle => "Entfernung wählen",
+        Text::DistanceXLabel => "Max. Entfernung x (Pixel):",
+        Text::DistanceYLabel => "Max. Entfernung y (Pixel):",
+        Text::GlideLabel => "Gleitdauer (ms, 0 = sofort):",
+        Text::DesktopShiftCheck => "Auch Desktopsymbole und Hintergrundbild verschieben",
+        Text::NudgeMaximizedCheck => "Maximierte Fenster innerhalb des Bildschirms verschieben",
+        Text::SnapGroupsCheck => "Angedockte Fenster zusammen mit ihren Nachbarn verschieben",
+        Text::CoherentShiftCheck => "Einheitliche Verschiebung: alle Fenster eines Bildschirms gleich weit verschieben",
+        Text::MicroShiftCheck => "Mikroverschiebung: Fenster alle paar Sekunden um ein Pixel verschieben",
+        Text::RecordHeatmapCheck => "Einbrenn-Heatmap für jeden Monitor aufzeichnen",
+        Text::MicroDelayLabel => "Verzögerung der Mikroverschiebung (ms):",
+        Text::MicroDistanceLabel => "Entfernung der Mikroverschiebung (Pixel):",
+        Text::TaskbarModeLabel => "Taskleiste bei Inaktivität schützen:",
+        Text::TaskbarModeOff => "Aus",
+        Text::TaskbarModeAutoHide => "Automatisch ausblenden aktivieren",
+        Text::TaskbarModeHide => "Ausblenden",
+        Text::TaskbarModeDim => "Abdunkeln",
+        Text::TaskbarIdleLabel => "Inaktivität vor dem Schutz (Minuten):",
+        Text::DimStaticCheck => "Unveränderte Bildschirmbereiche langsam abdunkeln",
+        Text::DimStaticAfterLabel => "Unveränderte Zeit vor dem Abdunkeln (Minuten):",
+        Text::DimStrengthLabel => "Stärke der Abdunklung (%):",
+        Text::OrbitCheck => "Bildschirmorbit: das ganze Bild verschieben, auch maximierte Fenster",
+        Text::OrbitRadiusLabel => "Radius des Bildschirmorbits (Pixel):",
+        Text::OrbitDelayLabel => "Schritt des Bildschirmorbits (Sekunden):",
+
+        Text::SettingsTitle => "OLEDShift-Einstellungen",
+        Text::TabGeneral => "Allgemein",
+        Text::TabSchedule => "Zeitplan",
+        Text::TabMonitors => "Monitore",
+        Text::TabRules => "Regeln",
+        Text::TabHotkeys => "Tastenkombinationen",
+        Text::TabProtection => "Schutz",
+        Text::TabAdvanced => "Erweitert",
+        Text::DelaySecondsLabel => "Verzögerung (Sekunden):",
+        Text::MonitorsLabel => "Fenster auf diesen Bildschirmen verschieben:",
+        Text::HotkeysLabel => "Kombinationen wie Ctrl+Alt+Shift+S, leer lassen zum Deaktivieren:",
+        Text::ScheduleCheck => "Fenster nur zu bestimmten Tageszeiten verschieben",
+        Text::ScheduleStartLabel => "Von (HH:MM):",
+        Text::ScheduleEndLabel => "Bis (HH:MM):",
+        Text::RulesLabel => "Maximierte Fenster pro Programm verschieben, eines pro Zeile wie vlc.exe = off:",
+        Text::RuleInvalid => "Die Regel \"{0}\" hat nicht die Form programm.exe = on oder programm.exe = off.",
+        Text::SettingsFileLabel => "Einstellungsdatei:",
+        Text::OpenFolder => "Ordner öffnen",
+        Text::OpenFolderFailedTitle => "Der Ordner konnte nicht geöffnet werden",
+        Text::InvalidSettingsTitle => "Ungültige Einstellungen",
+
+        Text::StatisticsTitle => "OLEDShift-Statistik",
+        Text::StatisticsDaysLabel => "Verschiebungen pro Tag:",
+        Text::StatisticsSkippedLabel => "Übersprungene Fenster und Gründe:",
+        Text::StatisticsDisplacementLabel => "Verschobene Strecke pro Monitor:",
+        Text::ColumnDate => "Datum",
+        Text::ColumnPasses => "Verschiebungen",
+        Text::ColumnWindowsMoved => "Verschobene Fenster",
+        Text::ColumnReason => "Grund",
+        Text::ColumnWindows => "Fenster",
+        Text::ColumnMonitor => "Monitor",
+        Text::ColumnPixels => "Pixel",
+        Text::ButtonClose => "Schließen",
+        Text::SkipMaximized => "Maximiert",
+        Text::SkipSnapped => "Angedockt",
+        Text::SkipExcludedClass => "Ausgeschlossener Fenstertyp",
+        Text::SkipMonitorDisabled => "Bildschirm in OLEDShift ausgeschaltet",
+        Text::SkipTooLarge => "Größer als der Bildschirm",
+        Text::SkipMoveFailed => "Ließ sich nicht verschieben",
+
+        Text::DiagnosticsTitle => "OLEDShift-Diagnose",
+        Text::DiagnosticsDescription => "Was die nächste Verschiebung mit jedem sichtbaren Fenster machen würde. Nichts wird verschoben, die Ziele sind jeweils eine zufällige Auswahl.",
+        Text::HeatmapTitle => "OLEDShift-Einbrenn-Heatmap",
+        Text::HeatmapDescription => "Wo jeder Monitor helle Inhalte gezeigt hat, die sich nicht verändert haben. Je heller die Farbe, desto stärker die Abnutzung. Die Aufzeichnung wird im Reiter Erweitert der Einstellungen aktiviert.",
+        Text::HeatmapEmpty => "Noch nichts aufgezeichnet.",
+        Text::HeatmapSummary => "Aufgezeichnet über {0}.",
+        Text::HeatmapExport => "Als PNG exportieren…",
+        Text::HeatmapReset => "Neu beginnen",
+        Text::HeatmapResetConfirm => "Die Heatmap dieses Monitors verwerfen und die Aufzeichnung neu beginnen?",
+        Text::HeatmapExportFailed => "Die Heatmap konnte nicht exportiert werden: {0}",
+        Text::ColumnDecision => "Entscheidung",
+        Text::ColumnTitle => "Fenster",
+        Text::ColumnProcess => "Prozess",
+        Text::ColumnClass => "Klasse",
+        Text::ColumnPosition => "Position",
+        Text::ColumnTarget => "Ziel",
+        Text::ButtonRefresh => "Aktualisieren",
+        Text::DecisionMove => "Verschieben",
+
+        Text::ActionToggle => "Aktivieren/deaktivieren",
+        Text::ActionPauseFor => "15 Minuten pausieren",
+
+        Text::DelayTooLow => "Die Verzögerung war zu kurz, sie wurde auf den kleinstmöglichen Wert von {0} gesetzt.",
+        Text::ProfileInvalid => "Die Verzögerung oder die maximale Distanz des Profils \"{0}\" lag außerhalb des erlaubten Bereichs, sie wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::DelayTooHigh => "Die Verzögerung war zu lang, sie wurde auf den größtmöglichen Wert von {0} gesetzt.",
+        Text::DistanceXTooLow => "Die maximale Entfernung X war zu klein, sie wurde auf den kleinstmöglichen Wert von {0} gesetzt.",
+        Text::DistanceYTooLow => "Die maximale Entfernung Y war zu klein, sie wurde auf den kleinstmöglichen Wert von {0} gesetzt.",
+        Text::HotkeyInvalid => "Die Tastenkombination {0} war ungültig, {1}. Sie wurde deaktiviert.",
+        Text::GlideTooLong => "Die Gleitdauer war zu lang, sie wurde auf den größtmöglichen Wert von {0} gesetzt.",
+        Text::MicroDelayTooLow => "Die Verzögerung der Mikroverschiebung war zu kurz, sie wurde auf den kleinstmöglichen Wert von {0} gesetzt.",
+        Text::MicroDelayTooHigh => "Die Verzögerung der Mikroverschiebung war zu lang, sie wurde auf den größtmöglichen Wert von {0} gesetzt.",
+        Text::MicroDistanceInvalid => "Die Entfernung der Mikroverschiebung muss zwischen {0} und {1} liegen, sie wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::TaskbarIdleInvalid => "Die Inaktivitätszeit der Taskleiste muss zwischen {0} und {1} liegen, sie wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::DimStaticTimeInvalid => "Die unveränderte Zeit vor dem Abdunkeln muss zwischen {0} und {1} liegen, sie wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::DimStrengthInvalid => "Die Stärke der Abdunklung muss zwischen {0} und {1} liegen, sie wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::ScheduleTimeInvalid => "Die Uhrzeit \"{0}\" des Zeitplans ist keine Uhrzeit wie 08:30, sie wurde auf {1} gesetzt.",
+        Text::OrbitRadiusInvalid => "Der Radius des Bildschirmorbits muss zwischen {0} und {1} liegen, er wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::OrbitDelayInvalid => "Der Schritt des Bildschirmorbits muss zwischen {0} und {1} liegen, er wurde auf den nächstgelegenen Wert gesetzt.",
+        Text::LogLevelInvalid => "Die Protokollstufe \"{0}\" ist unbekannt, sie wurde auf info gesetzt.",
+        Text::LogModuleLevelInvalid => "Die Protokollstufe \"{0}\" des Moduls {1} ist unbekannt, sie wurde entfernt.",
+
+        Text::HotkeyEmptyKey => "\"{0}\" enthält eine leere Taste",
+        Text::HotkeyRepeatedModifier => "\"{0}\" enthält die Zusatztaste {1} mehrfach",
+        Text::HotkeyMoreThanOneKey => "\"{0}\" enthält mehr als eine Taste",
+        Text::HotkeyUnknownKey => "\"{0}\" ist keine bekannte Taste",
+        Text::HotkeyNoKey => "\"{0}\" enthält nur Zusatztasten und keine Taste",
+        Text::HotkeyNoModifier => "\"{0}\" braucht mindestens eine Zusatztaste",
+        Text::HotkeyConflict => "{0} ist bereits \"{1}\" zugewiesen",
+        Text::HotkeyInUse => "{0} wird bereits von einer anderen Anwendung verwendet: {1}",
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCALES: [Locale; 2] = [Locale::English, Locale::German];
+
+    fn text(locale: Locale, text: Text) -> &'static str {
+        return match locale {
+            Locale::English => english(text),
+            Locale::German => german(text),
+        };
+    }
+
+    /// Returns how often each placeholder shows up, {0} at index 0 and so on
+    fn placeholders(text: &str) -> Vec<usize> {
+        let mut counts = Vec::new();
+        for (index, _) in text.match_indices('{') {
+            let digits: String = text[index + 1..].chars().take_while(char::is_ascii_digit).collect();
+            if digits.is_empty() || !text[index + 1 + digits.len()..].starts_with('}') {
+                continue;
+            }
+            let placeholder: usize = digits.parse().unwrap();
+            if counts.len() <= placeholder {
+                counts.resize(placeholder + 1, 0);
+            }
+            counts[placeholder] += 1;
+        }
+        return counts;
+    }
+
+    #[test]
+    fn every_text_is_translated_with_the_same_placeholders() {
+        for &text_id in Text::ALL {
+            let english_placeholders = placeholders(english(text_id));
+            for locale in LOCALES {
+                let translated = text(locale, text_id);
+                assert!(!translated.trim().is_empty(), "{:?} is empty in {:?}", text_id, locale);
+                assert_eq!(placeholders(translated), english_placeholders, "{:?} has other placeholders in {:?}", text_id, locale);
+            }
+        }
+    }
+
+    #[test]
+    fn tr_args_fills_in_the_placeholders() {
+        assert_eq!(placeholders("{1} of {0}, {1}"), vec![1, 2]);
+        let filled = tr_args(Text::HotkeyConflict, &[&"Ctrl+Alt+S", &"Shift now"]);
+        assert!(filled.contains("Ctrl+Alt+S") && filled.contains("Shift now") && placeholders(&filled).is_empty());
+    }
+
+    #[test]
+    fn only_exactly_one_is_singular() {
+        for locale in LOCALES {
+            assert_eq!(plural_category(locale, 1), PluralCategory::One);
+            assert_eq!(plural_category(locale, 0), PluralCategory::Other);
+            assert_eq!(plural_category(locale, 2), PluralCategory::Other);
+        }
+    }
+}
@@ -0,0 +1,687 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+
+#[cfg(windows)]
+use crate::controller::Controller;
+
+/// The name of the pipe the control server listens on, Windows only
+pub const PIPE_NAME: &str = r"\\.\pipe\OLEDShift";
+
+/// How often a client connection checks for new requests and events
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+lazy_static! {
+    /// Command lines forwarded by other instances that the UI hasn't handled yet
+    static ref ACTIVATIONS: Mutex<Vec<Vec<String>>> = Mutex::new(Vec::new());
+}
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// The state changes the controller broadcasts to its subscribers
+#[derive(Clone, Debug)]
+pub enum ControllerEvent {
+    IntervalChanged(Duration),
+    MaxMoveChanged(i32, i32),
+    RunningChanged(bool),
+    MonitorStateChanged(String, bool),
+    Shifted,
+}
+
+/// What the control server can do with the app, implemented by the controller.
+/// Kept apart from the controller so the server can be checked without any windows to move.
+pub trait Control {
+    fn get_interval(&self) -> Duration;
+    /// Returns the message for the client if the interval is out of range
+    fn set_interval(&mut self, interval: Duration) -> Result<(), String>;
+    fn get_max_move(&self) -> (i32, i32);
+    /// Returns the message for the client if the distances are out of range
+    fn set_max_move(&mut self, x: u64, y: u64) -> Result<(), String>;
+    /// Returns the device id => (name, enabled, connected) of every monitor
+    fn get_monitors_merged(&self) -> HashMap<String, (String, bool, bool)>;
+    fn set_monitor_state(&mut self, device_id: &str, enabled: bool);
+    fn is_running(&self) -> bool;
+    fn set_running(&mut self, running: bool);
+    fn pause_for(&mut self, duration: Duration);
+    fn get_pause_remaining(&self) -> Option<Duration>;
+    fn toggle_running(&mut self);
+    fn shift_now(&self);
+    fn undo(&self);
+    fn restore_windows(&self);
+    /// A dry run, reads the windows but neither moves nor records anything
+    fn explain(&self) -> Value;
+    fn subscribe(&mut self) -> Receiver<ControllerEvent>;
+}
+
+/// Starts the control server on a background thread.
+/// Every client gets its own thread, the notice is sent whenever a client changed the state so the UI can refresh itself.
+#[cfg(windows)]
+pub fn start(controller: Arc<Mutex<Controller>>, sender: nwg::NoticeSender) {
+    thread::Builder::new().name("ipc_thread".to_string()).spawn(move || {
+        loop {
+            match transport::accept() {
+                Ok(connection) => {
+                    let controller = controller.clone();
+                    let spawned = thread::Builder::new().name("ipc_client_thread".to_string()).spawn(move || {
+                        serve(connection, controller, || sender.notice());
+                    });
+                    if let Err(err) = spawned {
+                        log::error!("Failed to start an IPC client thread: {}", err);
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to accept an IPC connection: {}", err);
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        }
+    }).expect("Thread failed to start");
+}
+
+/// Returns the command lines forwarded by other instances since the last call
+pub fn take_activations() -> Vec<Vec<String>> {
+    return std::mem::take(&mut *ACTIVATIONS.lock().unwrap());
+}
+
+/// Sends a single request to the running instance and waits for its response.
+/// Returns the result, or the error message the server responded with.
+pub fn request(method: &str, params: Value) -> io::Result<Result<Value, String>> {
+    let mut stream = transport::connect()?;
+
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let mut line = request.to_string();
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "The server hung up without responding"));
+        }
+
+        let message: Value = serde_json::from_str(line.trim())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        // Skip the notifications that may arrive before the response
+        if message.get("id") != Some(&json!(1)) {
+            continue;
+        }
+
+        if let Some(error) = message.get("error") {
+            let error_message = error.get("message").and_then(Value::as_str).unwrap_or("Unknown error");
+            return Ok(Err(error_message.to_string()));
+        }
+        return Ok(Ok(message.get("result").cloned().unwrap_or(Value::Null)));
+    }
+}
+
+/// Serves a single client until it disconnects.
+/// Requests are newline delimited JSON-RPC 2.0 messages, state changes are pushed as notifications.
+/// The callback is called whenever the client changed the state.
+fn serve<C: Control>(mut connection: transport::Connection, controller: Arc<Mutex<C>>, changed: impl Fn()) {
+    let events = controller.lock().unwrap().subscribe();
+    let mut buffer: Vec<u8> = Vec::new();
+
+    loop {
+        match connection.read_available(&mut buffer) {
+            Ok(true) => {}
+            Ok(false) => return, // The client hung up
+            Err(err) => {
+                log::error!("Failed to read from an IPC client: {}", err);
+                return;
+            }
+        }
+
+        while let Some(position) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=position).collect();
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (response, state_changed) = handle_message(&controller, line.trim());
+            if state_changed {
+                changed();
+            }
+            if let Some(response) = response {
+                if write_line(&mut connection, &response).is_err() {
+                    return;
+                }
+            }
+        }
+
+        if forward_events(&events, &mut connection).is_err() {
+            return;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Writes all the pending controller events to the client as notifications
+fn forward_events(events: &Receiver<ControllerEvent>, connection: &mut transport::Connection) -> io::Result<()> {
+    loop {
+        match events.try_recv() {
+            Ok(event) => write_line(connection, &event_notification(&event))?,
+            Err(TryRecvError::Empty) => return Ok(()),
+            Err(TryRecvError::Disconnected) => return Err(io::Error::new(io::ErrorKind::BrokenPipe, "The controller is gone")),
+        }
+    }
+}
+
+fn write_line(connection: &mut transport::Connection, message: &Value) -> io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    connection.write_all(line.as_bytes())?;
+    return connection.flush();
+}
+
+/// Converts a controller event to a JSON-RPC notification
+pub fn event_notification(event: &ControllerEvent) -> Value {
+    let (method, params) = match event {
+        ControllerEvent::IntervalChanged(interval) => ("interval_changed", json!({ "milliseconds": interval.as_millis() as u64 })),
+        ControllerEvent::MaxMoveChanged(x, y) => ("max_move_changed", json!({ "x": x, "y": y })),
+        ControllerEvent::RunningChanged(running) => ("running_changed", json!({ "running": running })),
+        ControllerEvent::MonitorStateChanged(device_id, enabled) => ("monitor_state_changed", json!({ "device_id": device_id, "enabled": enabled })),
+        ControllerEvent::Shifted => ("shifted", json!({})),
+    };
+
+    return json!({ "jsonrpc": "2.0", "method": method, "params": params });
+}
+
+/// Handles a single JSON-RPC message.
+/// Returns the response, if any (notifications don't get one), and whether the controller state was changed.
+pub fn handle_message<C: Control>(controller: &Arc<Mutex<C>>, message: &str) -> (Option<Value>, bool) {
+    let request: Value = match serde_json::from_str(message) {
+        Ok(request) => request,
+        Err(err) => return (Some(error_response(Value::Null, PARSE_ERROR, &err.to_string())), false),
+    };
+
+    let id = request.get("id").cloned();
+    let method = request.get("method").and_then(Value::as_str);
+
+    let method = match method {
+        Some(method) if request.get("jsonrpc").and_then(Value::as_str) == Some("2.0") => method,
+        _ => return (Some(error_response(id.unwrap_or(Value::Null), INVALID_REQUEST, "Not a JSON-RPC 2.0 request")), false),
+    };
+
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = call(controller, method, &params);
+    let changed = result.as_ref().map(|(_, changed)| *changed).unwrap_or(false);
+
+    // Requests without an id are notifications and must not be answered
+    let id = match id {
+        Some(id) => id,
+        None => return (None, changed),
+    };
+
+    let response = match result {
+        Ok((value, _)) => json!({ "jsonrpc": "2.0", "id": id, "result": value }),
+        Err((code, message)) => error_response(id, code, &message),
+    };
+
+    return (Some(response), changed);
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    return json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } });
+}
+
+/// Runs the method against the controller, returns the result and whether the state was changed
+fn call<C: Control>(controller: &Arc<Mutex<C>>, method: &str, params: &Value) -> Result<(Value, bool), (i64, String)> {
+    match method {
+        "get_interval" => {
+            let interval = controller.lock().unwrap().get_interval();
+            return Ok((json!({ "milliseconds": interval.as_millis() as u64 }), false));
+        }
+        "set_interval" => {
+            let milliseconds = u64_param(params, "milliseconds")?;
+            controller.lock().unwrap().set_interval(Duration::from_millis(milliseconds))
+                .map_err(|message| (INVALID_PARAMS, message))?;
+            return Ok((Value::Null, true));
+        }
+        "get_max_move" => {
+            let (x, y) = controller.lock().unwrap().get_max_move();
+            return Ok((json!({ "x": x, "y": y }), false));
+        }
+        "set_max_move" => {
+            let x = u64_param(params, "x")?;
+            let y = u64_param(params, "y")?;
+            controller.lock().unwrap().set_max_move(x, y).map_err(|message| (INVALID_PARAMS, message))?;
+            return Ok((Value::Null, true));
+        }
+        "get_monitors" => {
+            let merged = controller.lock().unwrap().get_monitors_merged();
+            let monitors: Vec<Value> = merged.into_iter()
+                .map(|(device_id, (name, enabled, connected))| json!({
+                    "device_id": device_id,
+                    "name": name,
+                    "enabled": enabled,
+                    "connected": connected,
+                }))
+                .collect();
+            return Ok((Value::Array(monitors), false));
+        }
+        "set_monitor_state" => {
+            let device_id = params.get("device_id").and_then(Value::as_str)
+                .ok_or((INVALID_PARAMS, "Missing string parameter \"device_id\"".to_string()))?;
+            let enabled = bool_param(params, "enabled")?;
+            controller.lock().unwrap().set_monitor_state(device_id, enabled);
+            return Ok((Value::Null, true));
+        }
+        "is_running" => {
+            let running = controller.lock().unwrap().is_running();
+            return Ok((json!({ "running": running }), false));
+        }
+        "set_running" => {
+            let running = bool_param(params, "running")?;
+            controller.lock().unwrap().set_running(running);
+            return Ok((Value::Null, true));
+        }
+        "pause_for" => {
+            let milliseconds = u64_param(params, "milliseconds")?;
+            controller.lock().unwrap().pause_for(Duration::from_millis(milliseconds));
+            return Ok((Value::Null, true));
+        }
+        "get_pause" => {
+            let remaining = controller.lock().unwrap().get_pause_remaining();
+            return Ok((json!({ "remaining_milliseconds": remaining.map(|r| r.as_millis() as u64) }), false));
+        }
+        "toggle_running" => {
+            let mut controller = controller.lock().unwrap();
+            controller.toggle_running();
+            return Ok((json!({ "running": controller.is_running() }), true));
+        }
+        "activate" => {
+            // Another instance was launched, it forwards its command line and exits
+            let args = params.get("args").and_then(Value::as_array)
+                .map(|args| args.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                .unwrap_or_default();
+            ACTIVATIONS.lock().unwrap().push(args);
+            return Ok((Value::Null, true));
+        }
+        "shift_now" => {
+            controller.lock().unwrap().shift_now();
+            return Ok((Value::Null, false));
+        }
+        "undo" => {
+            controller.lock().unwrap().undo();
+            return Ok((Value::Null, false));
+        }
+        "restore_windows" => {
+            controller.lock().unwrap().restore_windows();
+            return Ok((Value::Null, false));
+        }
+        "explain" => {
+            let explanations = controller.lock().unwrap().explain();
+            return Ok((explanations, false));
+        }
+        _ => return Err((METHOD_NOT_FOUND, format!("Unknown method \"{}\"", method))),
+    }
+}
+
+fn u64_param(params: &Value, name: &str) -> Result<u64, (i64, String)> {
+    return params.get(name).and_then(Value::as_u64)
+        .ok_or((INVALID_PARAMS, format!("Missing non-negative integer parameter \"{}\"", name)));
+}
+
+fn bool_param(params: &Value, name: &str) -> Result<bool, (i64, String)> {
+    return params.get(name).and_then(Value::as_bool)
+        .ok_or((INVALID_PARAMS, format!("Missing boolean parameter \"{}\"", name)));
+}
+
+#[cfg(windows)]
+mod transport {
+    use std::ffi::OsStr;
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, Write};
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    use winapi::shared::minwindef::{DWORD, LPVOID};
+    use winapi::shared::winerror::{ERROR_BROKEN_PIPE, ERROR_PIPE_CONNECTED};
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::fileapi::{FlushFileBuffers, ReadFile, WriteFile};
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PeekNamedPipe};
+    use winapi::um::winbase::{
+        PIPE_ACCESS_DUPLEX,
+        PIPE_READMODE_BYTE,
+        PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES,
+        PIPE_WAIT,
+    };
+    use winapi::um::winnt::HANDLE;
+
+    use super::PIPE_NAME;
+
+    const BUFFER_SIZE: DWORD = 4096;
+
+    /// A connected instance of the named pipe
+    pub struct Connection {
+        handle: HANDLE,
+    }
+
+    // The handle is only ever used by the thread that owns the connection
+    unsafe impl Send for Connection {}
+
+    /// Connects to the pipe of the running instance as a client
+    pub fn connect() -> io::Result<File> {
+        return OpenOptions::new().read(true).write(true).open(PIPE_NAME);
+    }
+
+    /// Creates a new pipe instance and blocks until a client connects to it
+    pub fn accept() -> io::Result<Connection> {
+        let name: Vec<u16> = OsStr::new(PIPE_NAME).encode_wide().chain(std::iter::once(0)).collect();
+
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+
+        let connection = Connection { handle };
+
+        // A client may connect between CreateNamedPipeW and ConnectNamedPipe, that is reported as ERROR_PIPE_CONNECTED
+        if unsafe { ConnectNamedPipe(handle, ptr::null_mut()) } == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            return Err(io::Error::last_os_error());
+        }
+
+        return Ok(connection);
+    }
+
+    impl Connection {
+        /// Appends whatever the client has sent so far to the buffer without blocking.
+        /// Returns false once the client has disconnected.
+        pub fn read_available(&mut self, buffer: &mut Vec<u8>) -> io::Result<bool> {
+            let mut available: DWORD = 0;
+            let peeked = unsafe {
+                PeekNamedPipe(self.handle, ptr::null_mut(), 0, ptr::null_mut(), &mut available, ptr::null_mut())
+            };
+            if peeked == 0 {
+                return if unsafe { GetLastError() } == ERROR_BROKEN_PIPE { Ok(false) } else { Err(io::Error::last_os_error()) };
+            }
+            if available == 0 {
+                return Ok(true);
+            }
+
+            let mut chunk = vec![0u8; available as usize];
+            let mut read: DWORD = 0;
+            let success = unsafe {
+                ReadFile(self.handle, chunk.as_mut_ptr() as LPVOID, available, &mut read, ptr::null_mut())
+            };
+            if success == 0 {
+                return if unsafe { GetLastError() } == ERROR_BROKEN_PIPE { Ok(false) } else { Err(io::Error::last_os_error()) };
+            }
+
+            buffer.extend_from_slice(&chunk[..read as usize]);
+            return Ok(true);
+        }
+    }
+
+    impl Write for Connection {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut written: DWORD = 0;
+            let success = unsafe {
+                WriteFile(self.handle, buf.as_ptr() as LPVOID, buf.len() as DWORD, &mut written, ptr::null_mut())
+            };
+            if success == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            return Ok(written as usize);
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            if unsafe { FlushFileBuffers(self.handle) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            return Ok(());
+        }
+    }
+
+    impl Drop for Connection {
+        fn drop(&mut self) {
+            unsafe {
+                DisconnectNamedPipe(self.handle);
+                CloseHandle(self.handle);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::io::{self, Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+
+    /// A connected client of the Unix socket
+    pub struct Connection {
+        stream: UnixStream,
+    }
+
+    /// The socket lives in the temp directory, there is no pipe namespace outside of Windows
+    pub fn socket_path() -> PathBuf {
+        return std::env::temp_dir().join("OLEDShift.sock");
+    }
+
+    /// Connects to the socket of the running instance as a client
+    pub fn connect() -> io::Result<UnixStream> {
+        return UnixStream::connect(socket_path());
+    }
+
+    /// Blocks until a client connects to the socket, the listener is bound on the first call
+    pub fn accept() -> io::Result<Connection> {
+        static LISTENER: OnceLock<UnixListener> = OnceLock::new();
+
+        let listener = match LISTENER.get() {
+            Some(listener) => listener,
+            None => {
+                let path = socket_path();
+                let _ = std::fs::remove_file(&path); // Left over from a previous run
+                let listener = UnixListener::bind(&path)?;
+                LISTENER.get_or_init(|| listener)
+            }
+        };
+
+        let (stream, _) = listener.accept()?;
+        stream.set_nonblocking(true)?;
+        return Ok(Connection { stream });
+    }
+
+    impl Connection {
+        /// Appends whatever the client has sent so far to the buffer without blocking.
+        /// Returns false once the client has disconnected.
+        pub fn read_available(&mut self, buffer: &mut Vec<u8>) -> io::Result<bool> {
+            let mut chunk = [0u8; 4096];
+            loop {
+                match self.stream.read(&mut chunk) {
+                    Ok(0) => return Ok(false),
+                    Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+    }
+
+    impl Write for Connection {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            loop {
+                match self.stream.write(buf) {
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => std::thread::yield_now(),
+                    result => return result,
+                }
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return self.stream.flush();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::{channel, Sender};
+
+    use super::*;
+
+    /// Keeps the state in memory and broadcasts the changes the way the controller does
+    #[derive(Default)]
+    struct FakeControl {
+        interval: Duration,
+        running: bool,
+        listeners: Vec<Sender<ControllerEvent>>,
+    }
+
+    impl FakeControl {
+        fn notify(&mut self, event: ControllerEvent) {
+            self.listeners.retain(|listener| listener.send(event.clone()).is_ok());
+        }
+    }
+
+    impl Control for FakeControl {
+        fn get_interval(&self) -> Duration {
+            return self.interval;
+        }
+
+        fn set_interval(&mut self, interval: Duration) -> Result<(), String> {
+            self.interval = interval;
+            self.notify(ControllerEvent::IntervalChanged(interval));
+            return Ok(());
+        }
+
+        fn get_max_move(&self) -> (i32, i32) {
+            return (0, 0);
+        }
+
+        fn set_max_move(&mut self, _x: u64, _y: u64) -> Result<(), String> {
+            return Err("Not supported".to_string());
+        }
+
+        fn get_monitors_merged(&self) -> HashMap<String, (String, bool, bool)> {
+            return HashMap::new();
+        }
+
+        fn set_monitor_state(&mut self, _device_id: &str, _enabled: bool) {}
+
+        fn is_running(&self) -> bool {
+            return self.running;
+        }
+
+        fn set_running(&mut self, running: bool) {
+            self.running = running;
+            self.notify(ControllerEvent::RunningChanged(running));
+        }
+
+        fn pause_for(&mut self, _duration: Duration) {
+            self.set_running(false);
+        }
+
+        fn get_pause_remaining(&self) -> Option<Duration> {
+            return None;
+        }
+
+        fn toggle_running(&mut self) {
+            self.set_running(!self.running);
+        }
+
+        fn shift_now(&self) {}
+
+        fn undo(&self) {}
+
+        fn restore_windows(&self) {}
+
+        fn explain(&self) -> Value {
+            return json!([]);
+        }
+
+        fn subscribe(&mut self) -> Receiver<ControllerEvent> {
+            let (sender, receiver) = channel();
+            self.listeners.push(sender);
+            return receiver;
+        }
+    }
+
+    fn send(client: &mut UnixStream, message: Value) {
+        let mut line = message.to_string();
+        line.push('\n');
+        client.write_all(line.as_bytes()).unwrap();
+    }
+
+    fn receive(reader: &mut BufReader<UnixStream>) -> Value {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        return serde_json::from_str(line.trim()).unwrap();
+    }
+
+    #[test]
+    fn serves_requests_and_pushes_notifications_over_the_socket() {
+        let controller = Arc::new(Mutex::new(FakeControl { interval: Duration::from_secs(60), ..FakeControl::default() }));
+        let changes = Arc::new(AtomicUsize::new(0));
+
+        let server = {
+            let controller = controller.clone();
+            let changes = changes.clone();
+            thread::spawn(move || {
+                let connection = transport::accept().unwrap();
+                serve(connection, controller, || {
+                    changes.fetch_add(1, Ordering::SeqCst);
+                });
+            })
+        };
+
+        // The server binds the socket on its own thread, wait for it
+        let mut client = (0..100)
+            .find_map(|_| transport::connect().ok().or_else(|| {
+                thread::sleep(Duration::from_millis(20));
+                None
+            }))
+            .expect("The server never started listening");
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+
+        send(&mut client, json!({ "jsonrpc": "2.0", "id": 1, "method": "get_interval" }));
+        assert_eq!(receive(&mut reader), json!({ "jsonrpc": "2.0", "id": 1, "result": { "milliseconds": 60000 } }));
+
+        send(&mut client, json!({ "jsonrpc": "2.0", "id": 2, "method": "set_running", "params": { "running": true } }));
+        assert_eq!(receive(&mut reader), json!({ "jsonrpc": "2.0", "id": 2, "result": null }));
+        assert_eq!(receive(&mut reader), json!({ "jsonrpc": "2.0", "method": "running_changed", "params": { "running": true } }));
+
+        assert!(controller.lock().unwrap().is_running());
+        assert_eq!(changes.load(Ordering::SeqCst), 1);
+
+        // The server is done with the client once it hangs up
+        drop(reader);
+        drop(client);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+// The actions a hotkey can be bound to, and the bindings as the settings file writes them, like Ctrl+Alt+S
+
+use std::fmt;
+
+use crate::i18n::{tr, tr_args, Text};
+
+/// The controller actions that can be bound to a global hotkey
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HotkeyAction {
+    Toggle,
+    ShiftNow,
+    Undo,
+    PauseFor,
+    RestoreWindows,
+    NextProfile,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 6] = [
+        HotkeyAction::Toggle,
+        HotkeyAction::ShiftNow,
+        HotkeyAction::Undo,
+        HotkeyAction::PauseFor,
+        HotkeyAction::RestoreWindows,
+        HotkeyAction::NextProfile,
+    ];
+
+    /// The id the hotkey is registered with, WM_HOTKEY carries it in wParam
+    pub fn id(&self) -> i32 {
+        return match self {
+            HotkeyAction::Toggle => 1,
+            HotkeyAction::ShiftNow => 2,
+            HotkeyAction::Undo => 3,
+            HotkeyAction::PauseFor => 4,
+            HotkeyAction::RestoreWindows => 5,
+            HotkeyAction::NextProfile => 6,
+        };
+    }
+
+    pub fn from_id(id: i32) -> Option<HotkeyAction> {
+        return HotkeyAction::ALL.iter().find(|action| action.id() == id).copied();
+    }
+
+    /// The key of the action in the settings file
+    pub fn name(&self) -> &'static str {
+        return match self {
+            HotkeyAction::Toggle => "toggle",
+            HotkeyAction::ShiftNow => "shift_now",
+            HotkeyAction::Undo => "undo",
+            HotkeyAction::PauseFor => "pause_for",
+            HotkeyAction::RestoreWindows => "restore_windows",
+            HotkeyAction::NextProfile => "next_profile",
+        };
+    }
+
+    /// The name of the action as shown to the user
+    pub fn label(&self) -> &'static str {
+        return match self {
+            HotkeyAction::Toggle => tr(Text::ActionToggle),
+            HotkeyAction::ShiftNow => tr(Text::MenuShiftNow),
+            HotkeyAction::Undo => tr(Text::MenuUndo),
+            HotkeyAction::PauseFor => tr(Text::ActionPauseFor),
+            HotkeyAction::RestoreWindows => tr(Text::MenuRestoreWindows),
+            HotkeyAction::NextProfile => tr(Text::MenuNextProfile),
+        };
+    }
+
+    /// The binding the action gets when the settings file doesn't have one
+    pub fn default_binding(&self) -> Option<&'static str> {
+        return match self {
+            HotkeyAction::Toggle => Some("Ctrl+Alt+Shift+O"),
+            HotkeyAction::ShiftNow => Some("Ctrl+Alt+Shift+S"),
+            _ => None,
+        };
+    }
+}
+
+/// A global hotkey, a key plus any combination of modifiers, e.g. "Ctrl+Alt+Shift+S"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub win: bool,
+    /// The Win32 virtual key code
+    pub key: u32,
+}
+
+/// Named keys that can be used in a binding, with their virtual key codes
+const NAMED_KEYS: [(&str, u32); 16] = [
+    ("Space", 0x20),
+    ("Enter", 0x0D),
+    ("Tab", 0x09),
+    ("Esc", 0x1B),
+    ("Escape", 0x1B),
+    ("Backspace", 0x08),
+    ("Pause", 0x13),
+    ("PageUp", 0x21),
+    ("PageDown", 0x22),
+    ("End", 0x23),
+    ("Home", 0x24),
+    ("Left", 0x25),
+    ("Up", 0x26),
+    ("Right", 0x27),
+    ("Down", 0x28),
+    ("Insert", 0x2D),
+];
+
+const DELETE_KEY: u32 = 0x2E;
+const F1_KEY: u32 = 0x70;
+
+impl Hotkey {
+    /// Parses a binding like "Ctrl+Alt+Shift+S", case insensitive.
+    /// Exactly one non-modifier key is expected and at least one modifier, a bare key would swallow normal typing.
+    pub fn parse(binding: &str) -> Result<Hotkey, String> {
+        let mut hotkey = Hotkey { ctrl: false, alt: false, shift: false, win: false, key: 0 };
+        let mut key: Option<u32> = None;
+
+        for part in binding.split('+').map(str::trim) {
+            if part.is_empty() {
+                return Err(tr_args(Text::HotkeyEmptyKey, &[&binding]));
+            }
+
+            let modifier = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Some(&mut hotkey.ctrl),
+                "alt" => Some(&mut hotkey.alt),
+                "shift" => Some(&mut hotkey.shift),
+                "win" | "windows" | "super" => Some(&mut hotkey.win),
+                _ => None,
+            };
+
+            if let Some(modifier) = modifier {
+                if *modifier {
+                    return Err(tr_args(Text::HotkeyRepeatedModifier, &[&binding, &part]));
+                }
+                *modifier = true;
+                continue;
+            }
+
+            if key.is_some() {
+                return Err(tr_args(Text::HotkeyMoreThanOneKey, &[&binding]));
+            }
+            key = Some(Hotkey::parse_key(part).ok_or_else(|| tr_args(Text::HotkeyUnknownKey, &[&part]))?);
+        }
+
+        hotkey.key = key.ok_or_else(|| tr_args(Text::HotkeyNoKey, &[&binding]))?;
+
+        if !(hotkey.ctrl || hotkey.alt || hotkey.shift || hotkey.win) {
+            return Err(tr_args(Text::HotkeyNoModifier, &[&binding]));
+        }
+
+        return Ok(hotkey);
+    }
+
+    /// Converts a single key name to its virtual key code
+    fn parse_key(name: &str) -> Option<u32> {
+        let upper = name.to_ascii_uppercase();
+
+        // Letters and digits map to their ASCII codes
+        if upper.len() == 1 {
+            let c = upper.as_bytes()[0];
+            if c.is_ascii_uppercase() || c.is_ascii_digit() {
+                return Some(c as u32);
+            }
+        }
+
+        // F1 through F24
+        if let Some(number) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+            if (1..=24).contains(&number) {
+                return Some(F1_KEY + number - 1);
+            }
+        }
+
+        if upper == "DELETE" || upper == "DEL" {
+            return Some(DELETE_KEY);
+        }
+
+        return NAMED_KEYS.iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+            .map(|(_, code)| *code);
+    }
+
+    /// Returns the key name as it's written in a binding
+    fn key_name(&self) -> String {
+        let key = self.key;
+        if (b'A' as u32..=b'Z' as u32).contains(&key) || (b'0' as u32..=b'9' as u32).contains(&key) {
+            return (key as u8 as char).to_string();
+        }
+        if (F1_KEY..F1_KEY + 24).contains(&key) {
+            return format!("F{}", key - F1_KEY + 1);
+        }
+        if key == DELETE_KEY {
+            return "Delete".to_string();
+        }
+
+        return NAMED_KEYS.iter()
+            .find(|(_, code)| *code == key)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_else(|| format!("0x{:02X}", key));
+    }
+}
+
+impl fmt::Display for Hotkey {
+    /// Formats the hotkey the same way it's parsed, with the modifiers in a fixed order
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [(self.ctrl, "Ctrl"), (self.alt, "Alt"), (self.shift, "Shift"), (self.win, "Win")];
+        for (_, name) in modifiers.iter().filter(|(pressed, _)| *pressed) {
+            write!(f, "{}+", name)?;
+        }
+        return write!(f, "{}", self.key_name());
+    }
+}
+
+/// Returns a description of every binding that is used by more than one action, in the order of HotkeyAction::ALL.
+/// The first action keeps the binding, the ones after it are the conflicts.
+pub fn find_conflicts(bindings: &[(HotkeyAction, Hotkey)]) -> Vec<(HotkeyAction, String)> {
+    let mut conflicts = Vec::new();
+
+    for (index, (action, hotkey)) in bindings.iter().enumerate() {
+        if let Some((first, _)) = bindings[..index].iter().find(|(_, other)| other == hotkey) {
+            conflicts.push((*action, tr_args(Text::HotkeyConflict, &[hotkey, &first.label()])));
+        }
+    }
+
+    return conflicts;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hotkey(ctrl: bool, alt: bool, shift: bool, win: bool, key: u32) -> Hotkey {
+        return Hotkey { ctrl, alt, shift, win, key };
+    }
+
+    #[test]
+    fn modifiers_in_any_order_and_case() {
+        let expected = hotkey(true, true, true, false, b'S' as u32);
+        for binding in ["Ctrl+Alt+Shift+S", "shift+CTRL+alt+s", "Alt + Shift + Control + S", "S+Ctrl+Alt+Shift"] {
+            assert_eq!(Hotkey::parse(binding), Ok(expected), "{}", binding);
+        }
+        assert_eq!(Hotkey::parse("Win+D"), Ok(hotkey(false, false, false, true, b'D' as u32)));
+        assert_eq!(Hotkey::parse("super+d"), Hotkey::parse("Windows+D"));
+    }
+
+    #[test]
+    fn function_keys_letters_and_digits() {
+        assert_eq!(Hotkey::parse("Ctrl+F1").unwrap().key, F1_KEY);
+        assert_eq!(Hotkey::parse("Ctrl+f12").unwrap().key, F1_KEY + 11);
+        assert_eq!(Hotkey::parse("Ctrl+F24").unwrap().key, F1_KEY + 23);
+        assert!(Hotkey::parse("Ctrl+F25").is_err());
+        assert!(Hotkey::parse("Ctrl+F0").is_err());
+        assert_eq!(Hotkey::parse("Alt+q").unwrap().key, b'Q' as u32);
+        assert_eq!(Hotkey::parse("Alt+7").unwrap().key, b'7' as u32);
+        assert_eq!(Hotkey::parse("Alt+Del").unwrap().key, DELETE_KEY);
+        assert_eq!(Hotkey::parse("Alt+pageup").unwrap().key, 0x21);
+    }
+
+    #[test]
+    fn duplicate_modifier_fails() {
+        assert!(Hotkey::parse("Ctrl+Ctrl+S").is_err());
+        assert!(Hotkey::parse("Ctrl+Control+S").is_err());
+        assert!(Hotkey::parse("Win+Super+S").is_err());
+    }
+
+    #[test]
+    fn missing_modifier_or_key_fails() {
+        assert!(Hotkey::parse("S").is_err());
+        assert!(Hotkey::parse("F5").is_err());
+        assert!(Hotkey::parse("Ctrl+Alt").is_err());
+        assert!(Hotkey::parse("Ctrl+").is_err());
+        assert!(Hotkey::parse("").is_err());
+        assert!(Hotkey::parse("Ctrl+A+B").is_err());
+        assert!(Hotkey::parse("Ctrl+Nope").is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for binding in ["Ctrl+Alt+Shift+S", "Win+F11", "Alt+Shift+7", "Ctrl+Delete", "Ctrl+Win+Space", "Shift+Esc"] {
+            let parsed = Hotkey::parse(binding).unwrap();
+            assert_eq!(parsed.to_string(), binding);
+            assert_eq!(Hotkey::parse(&parsed.to_string()), Ok(parsed));
+        }
+        // The modifiers come out in a fixed order
+        assert_eq!(Hotkey::parse("shift+alt+ctrl+x").unwrap().to_string(), "Ctrl+Alt+Shift+X");
+    }
+
+    #[test]
+    fn conflicts_go_to_the_later_actions() {
+        let shift_now = Hotkey::parse("Ctrl+Alt+S").unwrap();
+        let toggle = Hotkey::parse("Ctrl+Alt+O").unwrap();
+        let bindings = [
+            (HotkeyAction::Toggle, toggle),
+            (HotkeyAction::ShiftNow, shift_now),
+            (HotkeyAction::Undo, shift_now),
+            (HotkeyAction::RestoreWindows, shift_now),
+        ];
+
+        let conflicts = find_conflicts(&bindings);
+        let actions: Vec<HotkeyAction> = conflicts.iter().map(|(action, _)| *action).collect();
+        assert_eq!(actions, vec![HotkeyAction::Undo, HotkeyAction::RestoreWindows]);
+        assert!(conflicts[0].1.contains("Ctrl+Alt+S"));
+        assert!(find_conflicts(&bindings[..2]).is_empty());
+    }
+
+    #[test]
+    fn actions_round_trip_through_their_ids() {
+        for action in HotkeyAction::ALL {
+            assert_eq!(HotkeyAction::from_id(action.id()), Some(action));
+        }
+        assert_eq!(HotkeyAction::from_id(0), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use winapi::um::minwinbase::SYSTEMTIME;
+use winapi::um::sysinfoapi::GetLocalTime;
+use crate::settings_path::settings_path;
+
+/// The log file is rotated once it grows past this size
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// How many rotated log files are kept next to the current one
+const KEPT_LOG_FILES: u32 = 3;
+
+/// The level used until the settings are loaded, and for modules without a filter of their own
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// Which messages are written: a level for every module, with per-module overrides.
+/// Modules are named like the source files, e.g. "mover" or "settings".
+struct Filter {
+    default: LevelFilter,
+    modules: HashMap<String, LevelFilter>,
+}
+
+impl Filter {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        // The target is the module path, e.g. "OLEDShift::mover", the crate name is the same for all of them
+        let module = target.split("::").nth(1).unwrap_or(target);
+        return self.modules.get(module).copied().unwrap_or(self.default);
+    }
+
+    /// The most verbose level any module logs at, lets the log macros skip everything below it cheaply
+    fn max_level(&self) -> LevelFilter {
+        return self.modules.values().copied().fold(self.default, LevelFilter::max);
+    }
+}
+
+/// The open log file and how much has been written to it
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+struct FileLogger {
+    filter: RwLock<Filter>,
+    file: Mutex<Option<LogFile>>,
+}
+
+lazy_static! {
+    static ref LOGGER: FileLogger = FileLogger {
+        filter: RwLock::new(Filter { default: DEFAULT_LEVEL, modules: HashMap::new() }),
+        file: Mutex::new(None),
+    };
+}
+
+/// The folder the log files are written to, the same one as the settings file
+pub fn log_folder() -> PathBuf {
+    return settings_path().parent().map(|folder| folder.to_path_buf()).unwrap_or_default();
+}
+
+/// The current log file, the rotated ones get a number: OLEDShift.1.log is the most recent of them
+fn log_path(index: u32) -> PathBuf {
+    return match index {
+        0 => log_folder().join("OLEDShift.log"),
+        _ => log_folder().join(format!("OLEDShift.{}.log", index)),
+    };
+}
+
+/// Starts logging to the log file with the default level, to be called once at startup
+pub fn init() {
+    if log::set_logger(&*LOGGER).is_ok() {
+        log::set_max_level(DEFAULT_LEVEL);
+    }
+}
+
+/// Parses a level name as written in the settings file, e.g. "debug", case insensitive
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    return level.parse::<LevelFilter>().ok();
+}
+
+/// Applies the levels from the settings. Names that don't parse are skipped, the settings validation reports them.
+pub fn configure(default: &str, modules: &HashMap<String, String>) {
+    let filter = Filter {
+        default: parse_level(default).unwrap_or(DEFAULT_LEVEL),
+        modules: modules.iter()
+            .filter_map(|(module, level)| parse_level(level).map(|level| (module.clone(), level)))
+            .collect(),
+    };
+
+    log::set_max_level(filter.max_level());
+    *LOGGER.filter.write().unwrap() = filter;
+}
+
+/// Returns the local time with milliseconds, for the start of every line
+fn timestamp() -> String {
+    let mut now: SYSTEMTIME = unsafe { std::mem::zeroed() };
+    unsafe { GetLocalTime(&mut now) };
+    return format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute, now.wSecond, now.wMilliseconds
+    );
+}
+
+/// Shifts the rotated files up by one, dropping the oldest, and moves the current file in as the first of them
+fn rotate() {
+    let _ = fs::remove_file(log_path(KEPT_LOG_FILES));
+    for index in (0..KEPT_LOG_FILES).rev() {
+        let _ = fs::rename(log_path(index), log_path(index + 1));
+    }
+}
+
+fn open_log_file() -> Option<LogFile> {
+    let path = log_path(0);
+    let file = OpenOptions::new().create(true).append(true).open(&path).ok()?;
+    let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    return Some(LogFile { file, size });
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        return metadata.level() <= self.filter.read().unwrap().level_for(metadata.target());
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!("{} {:<5} [{}] {}\n", timestamp(), record.level(), record.target(), record.args());
+
+        // Without a console nobody would see these, but they help when running from a terminal during development
+        if cfg!(debug_assertions) || record.level() == Level::Error {
+            eprint!("{}", line);
+        }
+
+        let mut log_file = self.file.lock().unwrap();
+        if log_file.as_ref().map(|log_file| log_file.size > MAX_LOG_BYTES).unwrap_or(false) {
+            *log_file = None;
+            rotate();
+        }
+        if log_file.is_none() {
+            *log_file = open_log_file();
+        }
+
+        // If the file can't be written there's nowhere left to report it, the line is dropped
+        if let Some(log_file) = log_file.as_mut() {
+            if log_file.file.write_all(line.as_bytes()).is_ok() {
+                log_file.size += line.len() as u64;
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(log_file) = self.file.lock().unwrap().as_mut() {
+            let _ = log_file.file.flush();
+        }
+    }
+}
@@ -1,27 +1,189 @@
 #![windows_subsystem = "windows"]
+// Elsewhere only the modules without Windows calls are built, so they can be tested on any platform
+#![cfg_attr(not(windows), allow(dead_code))]
+// Functions end with an explicit return throughout
+#![allow(clippy::needless_return)]
 
 /*!
 An application that runs in the system tray.
 
 Requires the following features: `cargo run --example system_tray --features "tray-notification message-window menu cursor"`
  */
+#[cfg(windows)]
 extern crate native_windows_gui as nwg;
 
+#[cfg(windows)]
+use std::sync::{Arc, Mutex};
+#[cfg(windows)]
+use std::thread;
+#[cfg(windows)]
+use std::time::Duration;
+
+#[cfg(windows)]
 use nwg::NativeUi;
+#[cfg(windows)]
+use serde_json::json;
+#[cfg(windows)]
+use controller::Controller;
+#[cfg(windows)]
+use i18n::{tr, Text};
+#[cfg(windows)]
+use settings::SettingsManager;
+#[cfg(windows)]
 use view::SystemTray;
+#[cfg(windows)]
+use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
 
+#[cfg(windows)]
 mod view;
+#[cfg(windows)]
 mod mover;
+#[cfg(windows)]
 mod controller;
+#[cfg(windows)]
 mod delay_dialog;
+#[cfg(windows)]
 mod distance_dialog;
+#[cfg(windows)]
+mod settings_window;
+#[cfg(windows)]
+mod dialog;
+mod i18n;
+#[cfg(windows)]
+mod history;
+#[cfg(windows)]
+mod statistics_window;
+#[cfg(windows)]
+mod diagnostics_window;
+#[cfg(windows)]
 mod settings;
+#[cfg(windows)]
 mod monitor_info;
+#[cfg(windows)]
 mod settings_path;
+mod ipc;
+#[cfg(windows)]
+mod single_instance;
+#[cfg(windows)]
+mod hotkeys;
+mod tray_icons;
+#[cfg(windows)]
+mod logging;
+mod easing;
+#[cfg(windows)]
+mod glide;
+#[cfg(windows)]
+mod desktop;
+#[cfg(windows)]
+mod overlay;
+#[cfg(windows)]
+mod taskbar;
+mod static_regions;
+#[cfg(windows)]
+mod dimmer;
+#[cfg(windows)]
+mod capture;
+mod rgb_image;
+mod heatmap;
+#[cfg(windows)]
+mod burn_in;
+#[cfg(windows)]
+mod heatmap_window;
+#[cfg(windows)]
+mod orbit;
+#[cfg(windows)]
+mod maximized;
+mod snap_groups;
+mod work_area;
+mod frame_bounds;
+mod key_bindings;
+mod window_classes;
+mod schedule;
 
 
+#[cfg(windows)]
 fn main() {
     nwg::init().expect("Failed to init Native Windows GUI");
+    logging::init();
+    log::info!("Starting OLEDShift {}", env!("CARGO_PKG_VERSION"));
+
+    // A dry run doesn't touch any window, so it doesn't need to be the only instance
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--explain") {
+        print_explanation(args.iter().any(|arg| arg == "--json"));
+        return;
+    }
+
+    // Two instances would run two mover threads that both write the settings file
+    let _instance = match single_instance::acquire() {
+        Some(guard) => guard,
+        None => {
+            notify_running_instance();
+            return;
+        }
+    };
+
     let _ui = SystemTray::build_ui(Default::default()).expect("Failed to build UI");
     nwg::dispatch_thread_events();
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("OLEDShift only runs on Windows");
+}
+
+/// Prints what the next pass would do with every visible window, without moving any of them.
+/// One line per window with tab separated columns, or a JSON array with --json.
+#[cfg(windows)]
+fn print_explanation(json: bool) {
+    // The app has no console of its own, the report goes to the one it was started from
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+
+    // The checks use the enabled monitors and the max distance from the settings, just like a real pass
+    let settings_manager = SettingsManager::new().unwrap_or_else(|(err, manager)| {
+        log::warn!("Explaining with fixed or default settings: {}", err);
+        manager
+    });
+    Controller::set_settings(Arc::new(Mutex::new(Controller::new())), settings_manager);
+
+    let explanations = mover::explain_pass();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanations).unwrap_or_default());
+        return;
+    }
+
+    // The shell doesn't wait for a GUI app, its prompt is already printed, so start on a fresh line
+    println!();
+    for explanation in explanations.iter() {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            diagnostics_window::decision_label(explanation),
+            explanation.process,
+            explanation.class,
+            explanation.monitor,
+            diagnostics_window::format_rect(explanation.rect),
+            explanation.target.map(diagnostics_window::format_rect).unwrap_or_else(|| "-".to_string()),
+            explanation.title,
+        );
+    }
+}
+
+/// Forwards the command line to the instance that is already running, so it can let the user know.
+/// Falls back to a message box if the running instance can't be reached.
+#[cfg(windows)]
+fn notify_running_instance() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // The pipe may be busy for a moment while the running instance is between two clients
+    let forwarded = (0..3).any(|attempt| {
+        if attempt > 0 {
+            thread::sleep(Duration::from_millis(200));
+        }
+        return matches!(ipc::request("activate", json!({ "args": args })), Ok(Ok(_)));
+    });
+
+    if !forwarded {
+        nwg::simple_message("OLEDShift", tr(Text::MessageAlreadyRunning));
+    }
+}
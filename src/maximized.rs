@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{mem, ptr};
+
+use lazy_static::lazy_static;
+use winapi::shared::minwindef::UINT;
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::winuser::{
+    GetForegroundWindow,
+    GetWindowPlacement,
+    GetWindowRect,
+    IsWindow,
+    SetForegroundWindow,
+    SetWindowPlacement,
+    SetWindowPos,
+    ShowWindow,
+    SWP_NOACTIVATE,
+    SWP_NOMOVE,
+    SWP_NOSIZE,
+    SWP_NOZORDER,
+    SW_SHOWMAXIMIZED,
+    SW_SHOWNOACTIVATE,
+    WINDOWPLACEMENT,
+};
+
+use crate::easing::Position;
+use crate::history::Rect;
+use crate::taskbar;
+
+/// How much smaller than the work area a nudged window is on each side, also how far it's shifted at most
+pub const NUDGE_MARGIN: i32 = 8;
+
+/// How long the user has to leave the foreground window alone before it's nudged, it's the one being worked in
+const FOREGROUND_IDLE: Duration = Duration::from_secs(30);
+
+/// How often the nudged windows are checked, also how quickly they are maximized again once the user is back
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Which processes get their maximized windows nudged, synced from the settings by the controller
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NudgeRules {
+    /// Whether the maximized windows are nudged, for the processes without a rule of their own
+    pub enabled: bool,
+    /// Executable file name => whether its maximized windows are nudged
+    pub processes: HashMap<String, bool>,
+}
+
+impl NudgeRules {
+    /// Returns true if the maximized windows of the process are nudged, the rule of the process wins over the default
+    pub fn applies_to(&self, process: &str) -> bool {
+        return self.processes.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(process))
+            .map_or(self.enabled, |(_, &nudged)| nudged);
+    }
+}
+
+/// A maximized window that was restored into a shrunk work area
+struct Nudged {
+    process: String,
+    /// The placement from before it was nudged, to maximize it again with its restored size intact
+    placement: WINDOWPLACEMENT,
+    /// Where it was last put, the user has taken it over once it's somewhere else
+    placed: Rect,
+    /// When it was last put there, any input after that while it's in the foreground maximizes it again
+    nudged_at: Instant,
+}
+
+lazy_static! {
+    static ref RULES: Mutex<NudgeRules> = Mutex::new(NudgeRules::default());
+
+    /// hwnd => the nudged window
+    static ref NUDGED: Mutex<HashMap<isize, Nudged>> = Mutex::new(HashMap::new());
+
+    static ref THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Set when the app exits, the watcher thread maximizes the nudged windows again and stops
+static CLOSED: AtomicBool = AtomicBool::new(false);
+
+/// Sets which processes get their maximized windows nudged, the windows no longer covered are maximized again
+pub fn configure(rules: NudgeRules) {
+    *RULES.lock().unwrap() = rules;
+}
+
+/// Starts watching the nudged windows on a thread of its own
+pub fn start() {
+    let spawned = thread::Builder::new().name("maximized_thread".to_string()).spawn(run);
+    match spawned {
+        Ok(handle) => *THREAD.lock().unwrap() = Some(handle),
+        Err(err) => log::error!("Failed to start the maximized windows thread: {}", err),
+    }
+}
+
+/// Maximizes the nudged windows again and stops watching them, for when the app exits
+pub fn close() {
+    CLOSED.store(true, Ordering::Relaxed);
+    if let Some(handle) = THREAD.lock().unwrap().take() {
+        let _ = handle.join();
+    }
+}
+
+/// Returns true if the maximized windows of the process are nudged
+pub fn applies_to(process: &str) -> bool {
+    return RULES.lock().unwrap().applies_to(process);
+}
+
+/// Returns true if the window is a maximized window that is nudged right now
+pub fn is_nudged(hwnd: HWND) -> bool {
+    return NUDGED.lock().unwrap().contains_key(&(hwnd as isize));
+}
+
+/// Returns false if the user is working in the window, it's left maximized then
+pub fn can_nudge(hwnd: HWND) -> bool {
+    return unsafe { GetForegroundWindow() } != hwnd || taskbar::idle_time() >= FOREGROUND_IDLE;
+}
+
+/// Returns the work area shrunk by the margin on each side and shifted by the offset, which stays within the margin.
+/// The rect never leaves the work area.
+pub fn nudged_rect(work_area: Rect, (dx, dy): Position) -> Rect {
+    let dx = dx.clamp(-NUDGE_MARGIN, NUDGE_MARGIN);
+    let dy = dy.clamp(-NUDGE_MARGIN, NUDGE_MARGIN);
+    return (
+        work_area.0 + NUDGE_MARGIN + dx,
+        work_area.1 + NUDGE_MARGIN + dy,
+        work_area.2 - NUDGE_MARGIN + dx,
+        work_area.3 - NUDGE_MARGIN + dy,
+    );
+}
+
+fn window_rect(hwnd: HWND) -> Option<Rect> {
+    let mut rect: RECT = unsafe { mem::zeroed() };
+    if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+        return None;
+    }
+    return Some((rect.left, rect.top, rect.right, rect.bottom));
+}
+
+/// Restores the maximized window, or moves the nudged one, into the rect. Returns true if the window was moved.
+pub fn nudge(hwnd: HWND, rect: Rect, process: String) -> bool {
+    let key = hwnd as isize;
+    let mut nudged = NUDGED.lock().unwrap();
+
+    let placement = match nudged.get(&key) {
+        Some(entry) => entry.placement,
+        None => {
+            let mut placement: WINDOWPLACEMENT = unsafe { mem::zeroed() };
+            placement.length = mem::size_of::<WINDOWPLACEMENT>() as UINT;
+            if unsafe { GetWindowPlacement(hwnd, &mut placement) } == 0 || placement.showCmd as i32 != SW_SHOWMAXIMIZED {
+                return false;
+            }
+            unsafe { ShowWindow(hwnd, SW_SHOWNOACTIVATE) };
+            placement
+        }
+    };
+
+    let flags = SWP_NOZORDER | SWP_NOACTIVATE;
+    let moved = unsafe { SetWindowPos(hwnd, ptr::null_mut(), rect.0, rect.1, rect.2 - rect.0, rect.3 - rect.1, flags) } != 0;
+    if !moved && !nudged.contains_key(&key) {
+        // It was restored but can't be put in place, it's better off maximized again
+        unsafe { SetWindowPlacement(hwnd, &placement) };
+        return false;
+    }
+
+    // The window may round its size, what it ended up with is what the user would change
+    let placed = window_rect(hwnd).unwrap_or(rect);
+    nudged.insert(key, Nudged { process, placement, placed, nudged_at: Instant::now() });
+    return moved;
+}
+
+/// Maximizes the window again, with the restored size it had before it was nudged
+fn maximize(hwnd: HWND, nudged: &Nudged) {
+    let foreground = unsafe { GetForegroundWindow() };
+    unsafe { SetWindowPlacement(hwnd, &nudged.placement) };
+    // Maximizing activates the window and brings it to the top, the window the user is working in gets the focus back
+    // and the maximized one goes back below it
+    if !foreground.is_null() && foreground != hwnd {
+        unsafe {
+            SetWindowPos(hwnd, foreground, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE);
+            SetForegroundWindow(foreground);
+        }
+    }
+}
+
+/// Maximizes every nudged window again, for restore and when the app exits
+pub fn maximize_all() {
+    let nudged = mem::take(&mut *NUDGED.lock().unwrap());
+    maximize_entries(nudged);
+}
+
+/// Maximizes the given nudged windows again, by hwnd, for undo. The other nudged windows stay nudged.
+pub fn maximize_windows(keys: &[isize]) {
+    let mut nudged = NUDGED.lock().unwrap();
+    let entries: Vec<(isize, Nudged)> = keys.iter().filter_map(|key| nudged.remove_entry(key)).collect();
+    drop(nudged);
+    maximize_entries(entries);
+}
+
+/// Maximizes the windows that are still where they were nudged to, the ones the user moved or resized are theirs now
+fn maximize_entries(entries: impl IntoIterator<Item = (isize, Nudged)>) {
+    for (key, entry) in entries {
+        let hwnd = key as HWND;
+        if unsafe { IsWindow(hwnd) } != 0 && window_rect(hwnd) == Some(entry.placed) {
+            maximize(hwnd, &entry);
+        }
+    }
+}
+
+/// Maximizes the nudged windows the user interacts with, or whose process is no longer nudged.
+/// Windows the user moved or resized are theirs now and are forgotten.
+fn check_nudged() {
+    let rules = RULES.lock().unwrap().clone();
+    let foreground = unsafe { GetForegroundWindow() };
+    let idle = taskbar::idle_time();
+
+    NUDGED.lock().unwrap().retain(|&key, entry| {
+        let hwnd = key as HWND;
+        if unsafe { IsWindow(hwnd) } == 0 || window_rect(hwnd) != Some(entry.placed) {
+            return false;
+        }
+
+        let interacted = foreground == hwnd && idle < entry.nudged_at.elapsed();
+        if interacted || !rules.applies_to(&entry.process) {
+            maximize(hwnd, entry);
+            return false;
+        }
+        return true;
+    });
+}
+
+fn run() {
+    loop {
+        if CLOSED.load(Ordering::Relaxed) {
+            maximize_all();
+            return;
+        }
+
+        check_nudged();
+        thread::sleep(CHECK_INTERVAL);
+    }
+}
@@ -3,58 +3,97 @@ use std::{
     os::raw::c_int,
     ptr,
 };
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
-use std::sync::Once;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Duration;
 
 use lazy_static::lazy_static;
 use libloading::Library;
 use rand::Rng;
+use serde::Serialize;
 use winapi::{
-    shared::minwindef::{BOOL, LPARAM, LPVOID, TRUE, UINT},
+    shared::minwindef::{BOOL, DWORD, FALSE, LPARAM, TRUE, UINT},
     shared::windef::{HDC, HMONITOR, HWND, RECT},
-    um::shellapi::{ABM_GETSTATE, ABS_AUTOHIDE, APPBARDATA, SHAppBarMessage},
+    um::dwmapi::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS},
+    um::handleapi::CloseHandle,
+    um::processthreadsapi::OpenProcess,
+    um::shellapi::{
+        ABE_BOTTOM,
+        ABE_LEFT,
+        ABE_RIGHT,
+        ABE_TOP,
+        ABM_GETSTATE,
+        ABM_GETTASKBARPOS,
+        ABS_AUTOHIDE,
+        APPBARDATA,
+        SHAppBarMessage,
+    },
+    um::winbase::QueryFullProcessImageNameW,
+    um::winnt::PROCESS_QUERY_LIMITED_INFORMATION,
     um::winuser::{
-        AnimateWindow,
-        AW_CENTER,
+        BeginDeferWindowPos,
+        DeferWindowPos,
+        EndDeferWindowPos,
         EnumDisplayMonitors,
         EnumWindows,
         GetClassNameW,
         GetMonitorInfoW,
         GetSystemMetrics,
         GetWindowPlacement,
+        GetWindowRect,
+        GetWindowTextW,
+        GetWindowThreadProcessId,
         HWND_TOP,
+        IsIconic,
+        IsWindow,
         IsWindowVisible,
+        MINMAXINFO,
         MonitorFromWindow,
         MONITORINFO,
         MONITORINFOEXW,
+        SendMessageTimeoutW,
         SetWindowPos,
-        SM_CYSCREEN,
-        SPI_GETWORKAREA,
+        SM_CXMINTRACK,
+        SM_CYMINTRACK,
+        SMTO_ABORTIFHUNG,
         SW_SHOWMAXIMIZED,
+        SWP_NOACTIVATE,
         SWP_NOSIZE,
         SWP_NOZORDER,
-        SystemParametersInfoW,
         WINDOWPLACEMENT,
+        WM_GETMINMAXINFO,
     },
 };
 
-use crate::controller::{ENABLED_MONITORS, MAX_MOVE};
+use crate::controller::{COHERENT_SHIFT, ENABLED_MONITORS, GLIDE_DURATION, MAX_MOVE, SNAP_GROUPS};
+use crate::easing::{self, Position};
+use crate::frame_bounds::Borders;
+use crate::glide::{self, Glide};
+use crate::history::{self, HistoryEntry, Rect, SkipReason};
+use crate::maximized;
 use crate::monitor_info::{get_display_device_info, get_monitor_info_ex, monitor_device_name};
+use crate::snap_groups;
+use crate::window_classes;
+use crate::work_area::{self, Edge, Taskbar};
 
 
 lazy_static! {
-    /// A set of window classes that should be excluded from being moved.
-    static ref CLASS_EXCLUSIONS: HashSet<&'static str> = {
-        [
-            "#32768", // OLEDShift right click menu
-            "NarratorHelperWindow", // A small circle/line, more info here: https://github.com/Marko19907/OLEDShift/issues/12
-            "TopLevelWindowForOverflowXamlIsland", // "Hidden Icon Menu", the flyout menu that appears when you click the arrow on the taskbar
-            "Shell_TrayWnd", // Taskbar
-            "Shell_SecondaryTrayWnd", // Taskbar on secondary monitors
-        ].iter().cloned().collect()
-    };
+    /// The windows moved by the last pass, hwnd => (where it was, where it was moved to), to be able to undo the pass
+    static ref LAST_PASS: Mutex<HashMap<isize, (Position, Position)>> = Mutex::new(HashMap::new());
+
+    /// The maximized windows nudged by the last pass, by hwnd, undo only maximizes these again
+    static ref LAST_NUDGES: Mutex<Vec<isize>> = Mutex::new(Vec::new());
+
+    /// Every window moved so far, hwnd => (where the user left it, where it was last moved to), to be able to restore them
+    static ref ORIGINAL_POSITIONS: Mutex<HashMap<isize, (Position, Position)>> = Mutex::new(HashMap::new());
+
+    /// Every snapped window moved along with its group, hwnd => (where the user snapped it, where it was last put).
+    /// Moving a window unsnaps it, so these are still treated as snapped while they are where they were put.
+    static ref SNAPPED_RECTS: Mutex<HashMap<isize, (Rect, Rect)>> = Mutex::new(HashMap::new());
 }
 
 /// A function pointer to the IsWindowArranged function in user32.dll
@@ -68,13 +107,23 @@ fn is_taskbar_auto_hidden() -> bool {
     return (state & ABS_AUTOHIDE) != 0;
 }
 
-fn get_taskbar_height() -> i32 {
-    let mut work_area_rect: RECT = unsafe { mem::zeroed() };
-    unsafe {
-        SystemParametersInfoW(SPI_GETWORKAREA, 0, &mut work_area_rect as *mut _ as LPVOID, 0);
+/// Returns where the taskbar is docked, None if the shell doesn't say
+fn taskbar_position() -> Option<Taskbar> {
+    let mut app_bar_data: APPBARDATA = unsafe { std::mem::zeroed() };
+    app_bar_data.cbSize = std::mem::size_of::<APPBARDATA>() as u32;
+    if unsafe { SHAppBarMessage(ABM_GETTASKBARPOS, &mut app_bar_data) } == 0 {
+        return None;
     }
-    let screen_height = unsafe { GetSystemMetrics(SM_CYSCREEN) };
-    return screen_height - (work_area_rect.bottom - work_area_rect.top);
+
+    let edge = match app_bar_data.uEdge {
+        ABE_LEFT => Edge::Left,
+        ABE_TOP => Edge::Top,
+        ABE_RIGHT => Edge::Right,
+        ABE_BOTTOM => Edge::Bottom,
+        _ => return None,
+    };
+    let rc = app_bar_data.rc;
+    return Some(Taskbar { edge, rect: (rc.left, rc.top, rc.right, rc.bottom), auto_hide: is_taskbar_auto_hidden() });
 }
 
 fn is_window_snapped(hwnd: HWND) -> bool {
@@ -131,9 +180,10 @@ unsafe extern "system" fn enum_display_monitors_callback(
     return TRUE;
 }
 
-/// Returns true if the window is visible.
+/// Returns true if the window is visible on the screen, minimized windows aren't.
 fn is_window_visible(hwnd: HWND) -> bool {
-    return unsafe { IsWindowVisible(hwnd) != 0 };
+    // A minimized window is only a button on the taskbar, there is nothing on the screen to move
+    return unsafe { IsWindowVisible(hwnd) != 0 && IsIconic(hwnd) == 0 };
 }
 
 /// Returns true if the window is maximized.
@@ -141,107 +191,905 @@ fn is_window_maximized(wp: &WINDOWPLACEMENT) -> bool {
     return wp.showCmd as i32 == SW_SHOWMAXIMIZED;
 }
 
-/// Returns true if the window should be excluded from being moved based on its title or class.
-fn is_excluded(hwnd: HWND) -> bool {
-    // Get the window class
+/// Returns the class name of the window
+fn window_class_name(hwnd: HWND) -> String {
     let mut class_name = [0u16; 1024];
     let class_length = unsafe { GetClassNameW(hwnd, class_name.as_mut_ptr(), 1024) } as usize;
-    let class_name = OsString::from_wide(&class_name[..class_length]);
+    return OsString::from_wide(&class_name[..class_length]).to_string_lossy().to_string();
+}
+
+/// Returns the executable file name of the process that owns the window, empty if it can't be queried
+fn window_process_name(hwnd: HWND) -> String {
+    let mut process_id: DWORD = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) };
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id) };
+    if process.is_null() {
+        // Elevated processes can't be queried from a normal one
+        return String::new();
+    }
+
+    let mut path = [0u16; 1024];
+    let mut path_length = path.len() as DWORD;
+    let queried = unsafe { QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut path_length) } != 0;
+    unsafe { CloseHandle(process) };
+
+    if !queried {
+        return String::new();
+    }
+
+    let path = OsString::from_wide(&path[..path_length as usize]);
+    return Path::new(&path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+}
 
-    return CLASS_EXCLUSIONS.contains(class_name.to_str().unwrap_or(""));
+/// Returns true if the window should be excluded from being moved based on its title or class.
+fn is_excluded(hwnd: HWND) -> bool {
+    return window_classes::is_excluded(&window_class_name(hwnd));
+}
+
+/// Returns the device id of the monitor, or its device name if it has none
+fn monitor_id(h_monitor: HMONITOR) -> String {
+    return get_monitor_info_ex(h_monitor)
+        .map(|mon_info_ex| {
+            let raw_name = monitor_device_name(&mon_info_ex);
+            get_display_device_info(&raw_name).map(|(_, device_id)| device_id).unwrap_or(raw_name)
+        })
+        .unwrap_or_default();
 }
 
 /// Returns true if we should move the window based on the monitor it's on.
 /// The window should be moved if it's not in the settings file, or if it's in the settings file and enabled.
 fn is_monitor_included(h_monitor: &HMONITOR) -> bool {
-    if let Some(mon_info_ex) = get_monitor_info_ex(*h_monitor) {
-        let raw_name = monitor_device_name(&mon_info_ex);
+    return get_monitor_info_ex(*h_monitor).map_or(false, |mon_info_ex| is_monitor_enabled(&mon_info_ex));
+}
 
-        if let Some((_, device_id)) = get_display_device_info(&raw_name) {
-            let enabled = ENABLED_MONITORS.lock().unwrap();
+/// Returns true if the monitor is enabled, or not in the settings file
+pub fn is_monitor_enabled(mon_info_ex: &MONITORINFOEXW) -> bool {
+    let raw_name = monitor_device_name(mon_info_ex);
 
-            if !enabled.contains_key(&device_id) {
-                return true; // If the monitor is not in the settings file, we should move the window.
-            }
+    if let Some((_, device_id)) = get_display_device_info(&raw_name) {
+        let enabled = ENABLED_MONITORS.lock().unwrap();
 
-            return *enabled.get(&device_id).unwrap_or(&false);
+        if !enabled.contains_key(&device_id) {
+            return true; // If the monitor is not in the settings file, we should move the window.
         }
+
+        return *enabled.get(&device_id).unwrap_or(&false);
     }
 
     return false;
 }
 
 
-fn move_window(hwnd: HWND) {
-    if !is_window_visible(hwnd) {
-        return;
-    }
+/// The pass being run, collects what happened to every visible window for the history log
+struct Pass {
+    stamp: u64,
+    date: String,
+    entries: Vec<HistoryEntry>,
+    /// The windows to move, they are only moved once every window has been checked
+    moves: Vec<PlannedMove>,
+    /// The maximized windows to nudge, along with the moves
+    nudges: Vec<PlannedNudge>,
+    /// The snapped windows, they are moved in groups once every window has been checked
+    snapped: Vec<SnapMember>,
+    /// How long the moves glide, zero moves them instantly
+    glide_duration: Duration,
+}
 
-    let mut wp: WINDOWPLACEMENT = unsafe { mem::zeroed() };
-    wp.length = mem::size_of::<WINDOWPLACEMENT>() as UINT;
-    unsafe { GetWindowPlacement(hwnd, &mut wp) };
+/// A window that passed every check, and where it's going to be moved to
+struct PlannedMove {
+    hwnd: HWND,
+    h_monitor: HMONITOR,
+    old_rect: Rect,
+    to: Position,
+}
 
-    if is_window_maximized(&wp) || is_window_snapped(hwnd) || is_excluded(hwnd) {
-        return;
+/// A maximized window that passed every check, and the rect it's going to be restored into
+struct PlannedNudge {
+    hwnd: HWND,
+    h_monitor: HMONITOR,
+    old_rect: Rect,
+    rect: Rect,
+}
+
+/// A snapped window, moved along with the snapped windows it shares edges with
+struct SnapMember {
+    hwnd: HWND,
+    h_monitor: HMONITOR,
+    old_rect: Rect,
+    /// Where the user snapped it, the group is laid out from there so it doesn't shrink pass after pass
+    layout: Rect,
+}
+
+impl PlannedMove {
+    fn from(&self) -> Position {
+        return (self.old_rect.0, self.old_rect.1);
     }
+}
 
-    let h_monitor = unsafe { MonitorFromWindow(hwnd, winapi::um::winuser::MONITOR_DEFAULTTONEAREST) };
+impl Pass {
+    /// Records what happened to the window, new_rect is None if it was skipped
+    fn record(&mut self, hwnd: HWND, h_monitor: HMONITOR, old_rect: Rect, new_rect: Option<Rect>, skipped: Option<SkipReason>) {
+        let entry = self.entry(hwnd, h_monitor, old_rect, new_rect, skipped);
+        self.entries.push(entry);
+    }
 
-    if !is_monitor_included(&h_monitor) {
-        return;
+    /// Returns the history entry of the window, for the pass to record now or a glide once it's done
+    fn entry(&self, hwnd: HWND, h_monitor: HMONITOR, old_rect: Rect, new_rect: Option<Rect>, skipped: Option<SkipReason>) -> HistoryEntry {
+        return HistoryEntry {
+            pass: self.stamp,
+            date: self.date.clone(),
+            process: window_process_name(hwnd),
+            class: window_class_name(hwnd),
+            monitor: monitor_id(h_monitor),
+            old_rect,
+            new_rect,
+            skipped,
+        };
     }
+}
 
+/// What a pass does with a window
+enum Decision {
+    Skip(SkipReason),
+    MoveTo(Position),
+    /// Restore the maximized window into the rect, or move it there if it's nudged already
+    Nudge(Rect),
+    /// Move the snapped window along with its group, where to is only known once every window has been checked
+    JoinGroup(Rect),
+}
+
+/// Returns the info of the monitor, its full rect and its work area
+fn monitor_info(h_monitor: HMONITOR) -> MONITORINFO {
     let mut monitor_info: MONITORINFO = unsafe { mem::zeroed() };
     monitor_info.cbSize = mem::size_of::<MONITORINFO>() as UINT;
     unsafe { GetMonitorInfoW(h_monitor, &mut monitor_info) };
+    return monitor_info;
+}
+
+/// Runs every check on the window.
+/// Returns the info of the monitor the window is on, to keep it within, or why it has to be left where it is.
+fn check(hwnd: HWND, wp: &WINDOWPLACEMENT, h_monitor: HMONITOR) -> Result<MONITORINFO, SkipReason> {
+    if is_window_maximized(wp) {
+        return Err(SkipReason::Maximized);
+    }
+    if is_window_snapped(hwnd) {
+        return Err(SkipReason::Snapped);
+    }
+    if is_excluded(hwnd) {
+        return Err(SkipReason::ExcludedClass);
+    }
+    if !is_monitor_included(&h_monitor) {
+        return Err(SkipReason::MonitorDisabled);
+    }
+
+    let monitor_info = monitor_info(h_monitor);
 
     let screen_width = monitor_info.rcMonitor.right - monitor_info.rcMonitor.left;
     let screen_height = monitor_info.rcMonitor.bottom - monitor_info.rcMonitor.top;
-    let window_width = wp.rcNormalPosition.right - wp.rcNormalPosition.left;
-    let window_height = wp.rcNormalPosition.bottom - wp.rcNormalPosition.top;
+    let (window_width, window_height) = normal_size(wp);
 
     // Check if the window is smaller than the screen, might not be true if the window is a game
     if !(window_width <= screen_width && window_height <= screen_height) {
-        return;
+        return Err(SkipReason::TooLarge);
+    }
+
+    return Ok(monitor_info);
+}
+
+/// Returns the size of the window when it's not minimized or maximized
+fn normal_size(wp: &WINDOWPLACEMENT) -> (i32, i32) {
+    return (
+        wp.rcNormalPosition.right - wp.rcNormalPosition.left,
+        wp.rcNormalPosition.bottom - wp.rcNormalPosition.top,
+    );
+}
+
+/// Moves the position so that the visible frame of the window stays on its monitor and clear of the taskbar.
+/// The invisible borders may reach past the work area, like they do for snapped windows.
+fn clamp_to_monitor(position: Position, size: (i32, i32), borders: &Borders, monitor_info: &MONITORINFO) -> Position {
+    let frame = borders.to_frame(moved_rect((0, 0, size.0, size.1), position));
+    let frame_size = (frame.2 - frame.0, frame.3 - frame.1);
+    let frame_position = work_area::clamp((frame.0, frame.1), frame_size, usable_area(monitor_info));
+    let window = borders.to_window(moved_rect(frame, frame_position));
+    return (window.0, window.1);
+}
+
+/// Returns the invisible borders of the window, none if DWM doesn't draw its frame
+fn window_borders(hwnd: HWND) -> Borders {
+    let window = match window_rect(hwnd) {
+        Some(rect) => rect,
+        None => return Borders::default(),
+    };
+
+    let mut frame: RECT = unsafe { mem::zeroed() };
+    let queried = unsafe {
+        DwmGetWindowAttribute(hwnd, DWMWA_EXTENDED_FRAME_BOUNDS, &mut frame as *mut _ as *mut _, mem::size_of::<RECT>() as DWORD)
+    };
+    if queried != 0 {
+        return Borders::default();
+    }
+    return Borders::between(window, (frame.left, frame.top, frame.right, frame.bottom));
+}
+
+/// Returns the part of the monitor windows may be moved within, its work area clear of the taskbar, wherever it's docked
+fn usable_area(monitor_info: &MONITORINFO) -> Rect {
+    let monitor = monitor_info.rcMonitor;
+    let work = monitor_info.rcWork;
+    return work_area::usable_area(
+        (monitor.left, monitor.top, monitor.right, monitor.bottom),
+        (work.left, work.top, work.right, work.bottom),
+        taskbar_position().as_ref(),
+    );
+}
+
+/// Decides what to do with a maximized window whose process has nudging turned on, or a window nudged already.
+/// Returns None for every other window, they go through the usual checks.
+fn decide_nudge(hwnd: HWND, wp: &WINDOWPLACEMENT, h_monitor: HMONITOR) -> Option<Decision> {
+    let nudged = maximized::is_nudged(hwnd);
+    if !nudged && !(is_window_maximized(wp) && maximized::applies_to(&window_process_name(hwnd))) {
+        return None;
+    }
+
+    if is_excluded(hwnd) {
+        return Some(Decision::Skip(SkipReason::ExcludedClass));
+    }
+    if !is_monitor_included(&h_monitor) {
+        return Some(Decision::Skip(SkipReason::MonitorDisabled));
+    }
+    if !nudged && !maximized::can_nudge(hwnd) {
+        return Some(Decision::Skip(SkipReason::Maximized));
+    }
+
+    let work = monitor_info(h_monitor).rcWork;
+    let margin = maximized::NUDGE_MARGIN;
+    let mut rng = rand::thread_rng();
+    let offset = (rng.gen_range(-margin..=margin), rng.gen_range(-margin..=margin));
+
+    let rect = maximized::nudged_rect((work.left, work.top, work.right, work.bottom), offset);
+    // A window that can't be made that small would reach past the work area, it's better off maximized
+    if !fits_min_track_size(hwnd, rect) {
+        return Some(Decision::Skip(SkipReason::Maximized));
+    }
+    return Some(Decision::Nudge(rect));
+}
+
+/// Returns where the user snapped the window, None if it isn't snapped and wasn't moved along with a group either.
+/// Only reads, a dry run goes through here too.
+fn snapped_layout(hwnd: HWND, rect: Rect) -> Option<Rect> {
+    let moved_along = SNAPPED_RECTS.lock().unwrap().get(&(hwnd as isize))
+        .filter(|(_, placed)| *placed == rect)
+        .map(|(layout, _)| *layout);
+    if moved_along.is_some() {
+        return moved_along;
+    }
+
+    return if is_window_snapped(hwnd) { Some(rect) } else { None };
+}
+
+/// Decides what to do with a snapped window when the snap groups are moved, None for every other window
+fn decide_snapped(hwnd: HWND, h_monitor: HMONITOR) -> Option<Decision> {
+    if !SNAP_GROUPS.lock().map(|guard| *guard).unwrap_or(false) {
+        return None;
+    }
+    let layout = snapped_layout(hwnd, window_rect(hwnd)?)?;
+
+    if is_excluded(hwnd) {
+        return Some(Decision::Skip(SkipReason::ExcludedClass));
+    }
+    if !is_monitor_included(&h_monitor) {
+        return Some(Decision::Skip(SkipReason::MonitorDisabled));
+    }
+    return Some(Decision::JoinGroup(layout));
+}
+
+/// Runs every check on the window and computes where it would be moved to, without moving it.
+/// The target is random, so every call gives a different one within the allowed range.
+/// Snapped windows moved in groups only get theirs once every window has been checked, from `plan_snap_groups`.
+/// The move starts from the window rect, which is in screen coordinates like the monitor and the target.
+fn decide(hwnd: HWND, wp: &WINDOWPLACEMENT, rect: Rect, h_monitor: HMONITOR) -> Decision {
+    if let Some(decision) = decide_nudge(hwnd, wp, h_monitor) {
+        return decision;
+    }
+    if let Some(decision) = decide_snapped(hwnd, h_monitor) {
+        return decision;
     }
 
+    let monitor_info = match check(hwnd, wp, h_monitor) {
+        Ok(monitor_info) => monitor_info,
+        Err(reason) => return Decision::Skip(reason),
+    };
+
+    let screen_width = monitor_info.rcMonitor.right - monitor_info.rcMonitor.left;
+    let screen_height = monitor_info.rcMonitor.bottom - monitor_info.rcMonitor.top;
+    let (window_width, window_height) = (rect.2 - rect.0, rect.3 - rect.1);
+
     let (max_x, max_y) = MAX_MOVE.lock().map(|guard| *guard).unwrap_or((50, 50));
 
     let max_move_x = i32::min(max_x, screen_width - window_width);
     let max_move_y = i32::min(max_y, screen_height - window_height);
 
     let mut rng = rand::thread_rng();
-    let random_x = wp.rcNormalPosition.left + rng.gen_range(0..(2 * max_move_x + 1)) - max_move_x;
-    let random_y = wp.rcNormalPosition.top + rng.gen_range(0..(2 * max_move_y + 1)) - max_move_y;
+    let random_x = rect.0 + rng.gen_range(0..(2 * max_move_x + 1)) - max_move_x;
+    let random_y = rect.1 + rng.gen_range(0..(2 * max_move_y + 1)) - max_move_y;
+
+    let borders = window_borders(hwnd);
+    return Decision::MoveTo(clamp_to_monitor((random_x, random_y), (window_width, window_height), &borders, &monitor_info));
+}
+
+/// Returns the placement of the window, the restored position is in rcNormalPosition
+fn window_placement(hwnd: HWND) -> WINDOWPLACEMENT {
+    let mut wp: WINDOWPLACEMENT = unsafe { mem::zeroed() };
+    wp.length = mem::size_of::<WINDOWPLACEMENT>() as UINT;
+    unsafe { GetWindowPlacement(hwnd, &mut wp) };
+    return wp;
+}
+
+/// Returns the rect the window would have at the position, it keeps its size
+fn moved_rect(rect: Rect, (x, y): Position) -> Rect {
+    return (x, y, x + rect.2 - rect.0, y + rect.3 - rect.1);
+}
+
+fn move_window(hwnd: HWND, pass: &mut Pass) {
+    if !is_window_visible(hwnd) {
+        return;
+    }
+
+    let old_rect = match window_rect(hwnd) {
+        Some(rect) => rect,
+        None => return,
+    };
+
+    let wp = window_placement(hwnd);
+    let h_monitor = unsafe { MonitorFromWindow(hwnd, winapi::um::winuser::MONITOR_DEFAULTTONEAREST) };
+
+    match decide(hwnd, &wp, old_rect, h_monitor) {
+        Decision::MoveTo(to) => pass.moves.push(PlannedMove { hwnd, h_monitor, old_rect, to }),
+        Decision::Nudge(rect) => pass.nudges.push(PlannedNudge { hwnd, h_monitor, old_rect, rect }),
+        Decision::JoinGroup(layout) => pass.snapped.push(SnapMember { hwnd, h_monitor, old_rect, layout }),
+        Decision::Skip(reason) => pass.record(hwnd, h_monitor, old_rect, None, Some(reason)),
+    }
+}
+
+/// Returns the smallest size the user could resize the window to, the window rect included
+fn min_track_size(hwnd: HWND) -> (i32, i32) {
+    let mut info: MINMAXINFO = unsafe { mem::zeroed() };
+    info.ptMinTrackSize.x = unsafe { GetSystemMetrics(SM_CXMINTRACK) };
+    info.ptMinTrackSize.y = unsafe { GetSystemMetrics(SM_CYMINTRACK) };
+    info.ptMaxTrackSize.x = i32::MAX;
+    info.ptMaxTrackSize.y = i32::MAX;
+
+    // The window fills in its own limits over the ones of the system, a window that doesn't answer keeps those
+    let mut result = 0;
+    unsafe {
+        SendMessageTimeoutW(hwnd, WM_GETMINMAXINFO, 0, &mut info as *mut _ as LPARAM, SMTO_ABORTIFHUNG, 100, &mut result);
+    }
+    return (info.ptMinTrackSize.x, info.ptMinTrackSize.y);
+}
+
+/// Returns true if the window can be resized to the rect, it doesn't go below its min track size
+fn fits_min_track_size(hwnd: HWND, rect: Rect) -> bool {
+    let (min_width, min_height) = min_track_size(hwnd);
+    return rect.2 - rect.0 >= min_width && rect.3 - rect.1 >= min_height;
+}
+
+/// Returns where each snapped window goes, in the same order.
+/// The windows of a monitor that share edges form a group, shrunk a little and shifted by one offset, so the layout stays intact.
+/// A group with a window that can't be made that small is left as it is, None for all of its windows.
+fn plan_snap_groups(members: &[SnapMember]) -> Vec<Option<Rect>> {
+    let mut monitors: HashMap<isize, Vec<usize>> = HashMap::new();
+    for (index, member) in members.iter().enumerate() {
+        monitors.entry(member.h_monitor as isize).or_default().push(index);
+    }
+
+    let margin = snap_groups::GROUP_MARGIN;
+    let mut rng = rand::thread_rng();
+    let mut targets: Vec<Option<Rect>> = vec![None; members.len()];
+
+    for indices in monitors.values() {
+        let layouts: Vec<Rect> = indices.iter().map(|&index| members[index].layout).collect();
+        for group in snap_groups::groups(&layouts) {
+            let group_layouts: Vec<Rect> = group.iter().map(|&member| layouts[member]).collect();
+            let offset = (rng.gen_range(-margin..=margin), rng.gen_range(-margin..=margin));
+            let fitted = snap_groups::fit(&group_layouts, offset);
+            if group.iter().zip(fitted.iter()).any(|(&member, target)| !fits_min_track_size(members[indices[member]].hwnd, *target)) {
+                continue;
+            }
+            for (&member, target) in group.iter().zip(fitted) {
+                targets[indices[member]] = Some(target);
+            }
+        }
+    }
+
+    return targets;
+}
+
+/// Returns the offsets along one axis that keep every span, (start, end), within the bounds and within the max distance.
+/// None if there is none, a span is out of the bounds or too long for them already.
+fn shared_offset_range(spans: &[(i32, i32)], (start, end): (i32, i32), max: i32) -> Option<(i32, i32)> {
+    let lowest = spans.iter().map(|span| start - span.0).fold(-max, i32::max);
+    let highest = spans.iter().map(|span| end - span.1).fold(max, i32::min);
+    return if lowest <= highest { Some((lowest, highest)) } else { None };
+}
+
+/// Gives every window of a monitor the same offset, so their layout is kept.
+/// The offset is picked so that none of them leaves the work area; if there's no such offset along an axis,
+/// because a window is out of it already, the windows are clamped one by one along that axis.
+fn make_coherent(moves: &mut [PlannedMove]) {
+    let mut monitors: HashMap<isize, Vec<usize>> = HashMap::new();
+    for (index, planned) in moves.iter().enumerate() {
+        monitors.entry(planned.h_monitor as isize).or_default().push(index);
+    }
+
+    let (max_x, max_y) = MAX_MOVE.lock().map(|guard| *guard).unwrap_or((50, 50));
+    let mut rng = rand::thread_rng();
+
+    for indices in monitors.values() {
+        let monitor_info = monitor_info(moves[indices[0]].h_monitor);
+        let area = usable_area(&monitor_info);
+        // The visible frames have to stay within the work area, the invisible borders may reach past it
+        let borders: Vec<Borders> = indices.iter().map(|&index| window_borders(moves[index].hwnd)).collect();
+        let frames: Vec<Rect> = indices.iter().zip(&borders).map(|(&index, borders)| borders.to_frame(moves[index].old_rect)).collect();
+        let spans_x: Vec<(i32, i32)> = frames.iter().map(|frame| (frame.0, frame.2)).collect();
+        let spans_y: Vec<(i32, i32)> = frames.iter().map(|frame| (frame.1, frame.3)).collect();
+        let range_x = shared_offset_range(&spans_x, (area.0, area.2), max_x);
+        let range_y = shared_offset_range(&spans_y, (area.1, area.3), max_y);
 
-    let random_x = i32::max(monitor_info.rcMonitor.left, i32::min(random_x, monitor_info.rcMonitor.right - window_width));
-    let mut random_y = i32::max(monitor_info.rcMonitor.top, i32::min(random_y, monitor_info.rcMonitor.bottom - window_height));
+        let (lowest_x, highest_x) = range_x.unwrap_or((-max_x, max_x));
+        let (lowest_y, highest_y) = range_y.unwrap_or((-max_y, max_y));
+        let offset = (rng.gen_range(lowest_x..=highest_x), rng.gen_range(lowest_y..=highest_y));
+
+        for (&index, borders) in indices.iter().zip(&borders) {
+            let planned = &mut moves[index];
+            let rect = planned.old_rect;
+            let to = (rect.0 + offset.0, rect.1 + offset.1);
+            let clamped = clamp_to_monitor(to, (rect.2 - rect.0, rect.3 - rect.1), borders, &monitor_info);
+            planned.to = (
+                if range_x.is_some() { to.0 } else { clamped.0 },
+                if range_y.is_some() { to.1 } else { clamped.1 },
+            );
+        }
+    }
+}
 
-    let taskbar_height = get_taskbar_height();
+/// Moves the windows of the pass once all of them have been checked and records what happened to them
+fn apply_moves(pass: &mut Pass) {
+    let moves = mem::take(&mut pass.moves);
 
-    if is_taskbar_auto_hidden() {
-        random_y = i32::max(random_y, monitor_info.rcMonitor.top + taskbar_height);
+    if pass.glide_duration.is_zero() {
+        for (planned, moved) in moves.iter().zip(move_batches(&moves)) {
+            if moved {
+                record_move(planned.hwnd, planned.from(), planned.to);
+                pass.record(planned.hwnd, planned.h_monitor, planned.old_rect, Some(moved_rect(planned.old_rect, planned.to)), None);
+            } else {
+                pass.record(planned.hwnd, planned.h_monitor, planned.old_rect, None, Some(SkipReason::MoveFailed));
+            }
+        }
     } else {
-        random_y = i32::min(random_y, monitor_info.rcMonitor.bottom - window_height - taskbar_height);
+        // The glided windows are only recorded once they are done, the user may grab one on the way
+        let glides = moves.iter()
+            .map(|planned| Glide { hwnd: planned.hwnd as isize, from: planned.from(), to: planned.to })
+            .collect();
+        let entries: Vec<(isize, HistoryEntry)> = moves.iter()
+            .map(|planned| (planned.hwnd as isize, pass.entry(planned.hwnd, planned.h_monitor, planned.old_rect, None, None)))
+            .collect();
+        glide::start(glides, pass.glide_duration, move |placed| record_glides(entries, placed));
+    }
+
+    // Snap groups are resized, so they are placed in one go instead of gliding
+    let snapped = mem::take(&mut pass.snapped);
+    let targets = plan_snap_groups(&snapped);
+    let mut groups: Vec<(SnapMember, Rect)> = Vec::new();
+    for (member, target) in snapped.into_iter().zip(targets) {
+        match target {
+            Some(target) => groups.push((member, target)),
+            None => pass.record(member.hwnd, member.h_monitor, member.old_rect, None, Some(SkipReason::Snapped)),
+        }
+    }
+    let places: Vec<(HWND, Rect)> = groups.iter().map(|(member, target)| (member.hwnd, *target)).collect();
+    let placed = place_batch(&places, 0);
+    for ((member, target), placed) in groups.into_iter().zip(placed) {
+        if placed {
+            // The window may round its size, what it ended up with is what the user would change
+            let rect = window_rect(member.hwnd).unwrap_or(target);
+            SNAPPED_RECTS.lock().unwrap().insert(member.hwnd as isize, (member.layout, rect));
+            pass.record(member.hwnd, member.h_monitor, member.old_rect, Some(target), None);
+        } else {
+            pass.record(member.hwnd, member.h_monitor, member.old_rect, None, Some(SkipReason::MoveFailed));
+        }
     }
 
-    unsafe { SetWindowPos(hwnd, HWND_TOP, random_x, random_y, 0, 0, SWP_NOSIZE | SWP_NOZORDER) };
+    // Nudges resize the windows too, they can't be batched or glided with the moves
+    for planned in mem::take(&mut pass.nudges) {
+        if maximized::nudge(planned.hwnd, planned.rect, window_process_name(planned.hwnd)) {
+            LAST_NUDGES.lock().unwrap().push(planned.hwnd as isize);
+            pass.record(planned.hwnd, planned.h_monitor, planned.old_rect, Some(planned.rect), None);
+        } else {
+            pass.record(planned.hwnd, planned.h_monitor, planned.old_rect, None, Some(SkipReason::MoveFailed));
+        }
+    }
+}
+
+/// What a pass would do with one visible window, reported by a dry run
+#[derive(Serialize, Clone, Debug)]
+pub struct Explanation {
+    pub title: String,
+    /// The executable file name of the process that owns the window
+    pub process: String,
+    pub class: String,
+    /// The device id of the monitor the window is on
+    pub monitor: String,
+    pub rect: Rect,
+    /// Where the window would be moved to, None if it would be skipped
+    pub target: Option<Rect>,
+    pub skipped: Option<SkipReason>,
+}
+
+/// Returns the title of the window, empty if it has none
+fn window_title(hwnd: HWND) -> String {
+    let mut title = [0u16; 512];
+    let title_length = unsafe { GetWindowTextW(hwnd, title.as_mut_ptr(), title.len() as c_int) } as usize;
+    return OsString::from_wide(&title[..title_length]).to_string_lossy().to_string();
+}
+
+/// The dry run being run, the snapped windows get their targets once every window has been checked
+struct DryRun {
+    explanations: Vec<Explanation>,
+    /// The snapped windows, along with the index of their explanation
+    snapped: Vec<(usize, SnapMember)>,
+    /// The windows to move, along with the index of their explanation, their targets change with a coherent shift
+    moves: Vec<(usize, PlannedMove)>,
+}
 
-    if unsafe { AnimateWindow(hwnd, 4000, AW_CENTER) } == 0 {
-        // Failed to animate window movement
+/// Runs every check of a pass on the window without moving it
+fn explain_window(hwnd: HWND, dry_run: &mut DryRun) {
+    if !is_window_visible(hwnd) {
+        return;
     }
 
-    return;
+    let rect = match window_rect(hwnd) {
+        Some(rect) => rect,
+        None => return,
+    };
+    let wp = window_placement(hwnd);
+    let h_monitor = unsafe { MonitorFromWindow(hwnd, winapi::um::winuser::MONITOR_DEFAULTTONEAREST) };
+
+    let (target, skipped) = match decide(hwnd, &wp, rect, h_monitor) {
+        Decision::MoveTo(to) => {
+            dry_run.moves.push((dry_run.explanations.len(), PlannedMove { hwnd, h_monitor, old_rect: rect, to }));
+            (Some(moved_rect(rect, to)), None)
+        }
+        Decision::Nudge(target) => (Some(target), None),
+        Decision::JoinGroup(layout) => {
+            dry_run.snapped.push((dry_run.explanations.len(), SnapMember { hwnd, h_monitor, old_rect: rect, layout }));
+            (None, None)
+        }
+        Decision::Skip(reason) => (None, Some(reason)),
+    };
+
+    dry_run.explanations.push(Explanation {
+        title: window_title(hwnd),
+        process: window_process_name(hwnd),
+        class: window_class_name(hwnd),
+        monitor: monitor_id(h_monitor),
+        rect,
+        target,
+        skipped,
+    });
 }
 
-unsafe extern "system" fn enum_windows_proc(hwnd: HWND, _: LPARAM) -> BOOL {
-    move_window(hwnd);
+unsafe extern "system" fn explain_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let dry_run = &mut *(lparam as *mut DryRun);
+    explain_window(hwnd, dry_run);
+    return TRUE;
+}
+
+/// A dry run of a pass: reports what would happen to every visible window, but doesn't move any of them.
+/// Nothing is recorded either, so undo and the history are left alone.
+pub fn explain_pass() -> Vec<Explanation> {
+    let mut dry_run = DryRun { explanations: Vec::new(), snapped: Vec::new(), moves: Vec::new() };
+
+    unsafe {
+        EnumWindows(Some(explain_windows_proc), &mut dry_run as *mut _ as LPARAM);
+    }
+
+    let (indices, members): (Vec<usize>, Vec<SnapMember>) = dry_run.snapped.into_iter().unzip();
+    for (index, target) in indices.into_iter().zip(plan_snap_groups(&members)) {
+        dry_run.explanations[index].target = target;
+        if target.is_none() {
+            dry_run.explanations[index].skipped = Some(SkipReason::Snapped);
+        }
+    }
+
+    if COHERENT_SHIFT.lock().map(|guard| *guard).unwrap_or(false) {
+        let (indices, mut moves): (Vec<usize>, Vec<PlannedMove>) = dry_run.moves.into_iter().unzip();
+        make_coherent(&mut moves);
+        for (index, planned) in indices.into_iter().zip(moves) {
+            dry_run.explanations[index].target = Some(moved_rect(planned.old_rect, planned.to));
+        }
+    }
+
+    return dry_run.explanations;
+}
+
+/// Returns the current window rect of the window, None if it's gone
+fn window_rect(hwnd: HWND) -> Option<Rect> {
+    let mut rect: RECT = unsafe { mem::zeroed() };
+    if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+        return None;
+    }
+    return Some((rect.left, rect.top, rect.right, rect.bottom));
+}
+
+/// Returns the current position of the window, None if it's gone
+fn window_position(hwnd: HWND) -> Option<Position> {
+    return window_rect(hwnd).map(|rect| (rect.0, rect.1));
+}
+
+/// Moves the window back to a recorded position, unless the user has moved it since it was recorded.
+/// Returns true if the window was moved.
+fn move_back(hwnd: HWND, placed: Position, target: Position) -> bool {
+    if unsafe { IsWindow(hwnd) } == 0 || window_position(hwnd) != Some(placed) {
+        return false;
+    }
+    return unsafe { SetWindowPos(hwnd, HWND_TOP, target.0, target.1, 0, 0, SWP_NOSIZE | SWP_NOZORDER) } != 0;
+}
+
+/// Remembers a move for undo and restore
+fn record_move(hwnd: HWND, from: Position, to: Position) {
+    let key = hwnd as isize;
+    LAST_PASS.lock().unwrap().insert(key, (from, to));
+
+    let mut originals = ORIGINAL_POSITIONS.lock().unwrap();
+    let still_placed = originals.get(&key).map(|(_, placed)| *placed == from).unwrap_or(false);
+    if still_placed {
+        originals.get_mut(&key).unwrap().1 = to;
+    } else {
+        // A new window, or the user has moved it since the last pass, so this is where the user wants it
+        originals.insert(key, (from, to));
+    }
+}
+
+/// Records where the glides of a pass left their windows, for undo, restore and the history.
+/// A window the user grabbed on the way counts as moved as far as it got.
+fn record_glides(mut entries: Vec<(isize, HistoryEntry)>, placed: Vec<Position>) {
+    for ((key, entry), position) in entries.iter_mut().zip(placed) {
+        let from = (entry.old_rect.0, entry.old_rect.1);
+        if position == from {
+            entry.skipped = Some(SkipReason::MoveFailed);
+            continue;
+        }
+        record_move(*key as HWND, from, position);
+        entry.new_rect = Some(moved_rect(entry.old_rect, position));
+    }
+
+    let entries: Vec<HistoryEntry> = entries.into_iter().map(|(_, entry)| entry).collect();
+    let moved = entries.iter().filter(|entry| entry.new_rect.is_some()).count();
+    log::debug!("Glided {} of {} windows", moved, entries.len());
+    history::append(&entries);
+}
+
+unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let pass = &mut *(lparam as *mut Pass);
+    move_window(hwnd, pass);
     return TRUE;
 }
 
 /// Moves the windows just once.
 pub fn move_all_windows() {
+    // The windows of the previous pass must be where it put them before they are checked again
+    glide::finish();
+
+    LAST_PASS.lock().unwrap().clear();
+    LAST_NUDGES.lock().unwrap().clear();
+    // Forget the windows that have been closed
+    ORIGINAL_POSITIONS.lock().unwrap().retain(|&key, _| unsafe { IsWindow(key as HWND) } != 0);
+    // And the snapped windows the user has moved or snapped again since they were moved along with their group
+    SNAPPED_RECTS.lock().unwrap().retain(|&key, (_, placed)| {
+        return unsafe { IsWindow(key as HWND) } != 0 && window_rect(key as HWND) == Some(*placed);
+    });
+
+    let (stamp, date) = history::pass_stamp();
+    let glide_duration = GLIDE_DURATION.lock().map(|guard| *guard).unwrap_or_default();
+    let mut pass = Pass {
+        stamp,
+        date,
+        entries: Vec::new(),
+        moves: Vec::new(),
+        nudges: Vec::new(),
+        snapped: Vec::new(),
+        glide_duration,
+    };
+
     unsafe {
-        EnumWindows(Some(enum_windows_proc), 0);
+        EnumWindows(Some(enum_windows_proc), &mut pass as *mut _ as LPARAM);
+    }
+    if COHERENT_SHIFT.lock().map(|guard| *guard).unwrap_or(false) {
+        make_coherent(&mut pass.moves);
+    }
+    apply_moves(&mut pass);
+
+    let moved = pass.entries.iter().filter(|entry| entry.new_rect.is_some()).count();
+    log::debug!("Moved {} of {} windows", moved, pass.entries.len());
+
+    history::append(&pass.entries);
+}
+
+/// The micro-shift pass being run: the offset from where the user left the windows and the moves collected so far.
+/// Every window is moved a pixel or so along the trajectory around where the user left it.
+struct MicroPass {
+    offset: Position,
+    moves: Vec<PlannedMove>,
+}
+
+/// How far along the micro-shift trajectory the windows are, advanced by one step every micro-shift pass
+static MICRO_STEP: AtomicU64 = AtomicU64::new(0);
+
+/// Returns where the user left the window: where it was before OLEDShift moved it, unless the user has moved it since
+fn user_position(hwnd: HWND, current: Position) -> Position {
+    return match ORIGINAL_POSITIONS.lock().unwrap().get(&(hwnd as isize)) {
+        Some((original, placed)) if *placed == current => *original,
+        _ => current,
+    };
+}
+
+fn micro_shift_window(hwnd: HWND, pass: &mut MicroPass) {
+    // Nudged windows are shifted by the regular passes only, they'd count as moved by the user otherwise
+    if !is_window_visible(hwnd) || maximized::is_nudged(hwnd) {
+        return;
+    }
+
+    let rect = match window_rect(hwnd) {
+        Some(rect) => rect,
+        None => return,
+    };
+    // Same for the snapped windows moved along with their group
+    if SNAPPED_RECTS.lock().unwrap().get(&(hwnd as isize)).map_or(false, |(_, placed)| *placed == rect) {
+        return;
+    }
+
+    let wp = window_placement(hwnd);
+    let h_monitor = unsafe { MonitorFromWindow(hwnd, winapi::um::winuser::MONITOR_DEFAULTTONEAREST) };
+
+    let monitor_info = match check(hwnd, &wp, h_monitor) {
+        Ok(monitor_info) => monitor_info,
+        Err(_) => return,
+    };
+
+    let from = (rect.0, rect.1);
+    let anchor = user_position(hwnd, from);
+    let to = clamp_to_monitor((anchor.0 + pass.offset.0, anchor.1 + pass.offset.1), normal_size(&wp), &window_borders(hwnd), &monitor_info);
+
+    if to != from {
+        pass.moves.push(PlannedMove { hwnd, h_monitor, old_rect: rect, to });
+    }
+}
+
+unsafe extern "system" fn micro_shift_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let pass = &mut *(lparam as *mut MicroPass);
+    micro_shift_window(hwnd, pass);
+    return TRUE;
+}
+
+/// Moves the windows in one batch per monitor. Returns whether each window was moved, in the same order.
+fn move_batches(moves: &[PlannedMove]) -> Vec<bool> {
+    let mut monitors: HashMap<isize, Vec<usize>> = HashMap::new();
+    for (index, planned) in moves.iter().enumerate() {
+        monitors.entry(planned.h_monitor as isize).or_default().push(index);
+    }
+
+    let mut moved = vec![false; moves.len()];
+    for indices in monitors.values() {
+        let batch: Vec<(HWND, Position)> = indices.iter().map(|&index| (moves[index].hwnd, moves[index].to)).collect();
+        for (&index, batch_moved) in indices.iter().zip(move_batch(&batch)) {
+            moved[index] = batch_moved;
+        }
+    }
+
+    return moved;
+}
+
+/// Moves the windows in one batch, so they are repainted together instead of one after the other.
+/// If the batch can't be applied, every window is moved on its own. Returns whether each window was moved.
+fn move_batch(moves: &[(HWND, Position)]) -> Vec<bool> {
+    let places: Vec<(HWND, Rect)> = moves.iter().map(|&(hwnd, (x, y))| (hwnd, (x, y, x, y))).collect();
+    return place_batch(&places, SWP_NOSIZE);
+}
+
+/// Moves and resizes the windows in one batch, like `move_batch`. SWP_NOSIZE in the flags keeps their size.
+fn place_batch(places: &[(HWND, Rect)], flags: UINT) -> Vec<bool> {
+    if places.is_empty() {
+        return Vec::new();
+    }
+    let flags = flags | SWP_NOZORDER | SWP_NOACTIVATE;
+
+    let mut hdwp = unsafe { BeginDeferWindowPos(places.len() as c_int) };
+    for (hwnd, rect) in places.iter() {
+        if hdwp.is_null() {
+            break;
+        }
+        // On failure the whole batch is freed and null is returned
+        hdwp = unsafe { DeferWindowPos(hdwp, *hwnd, ptr::null_mut(), rect.0, rect.1, rect.2 - rect.0, rect.3 - rect.1, flags) };
+    }
+
+    if !hdwp.is_null() && unsafe { EndDeferWindowPos(hdwp) } != 0 {
+        return vec![true; places.len()];
+    }
+
+    log::debug!("Failed to move {} windows in one batch, moving them one by one", places.len());
+    return places.iter()
+        .map(|(hwnd, rect)| unsafe { SetWindowPos(*hwnd, HWND_TOP, rect.0, rect.1, rect.2 - rect.0, rect.3 - rect.1, flags) } != 0)
+        .collect();
+}
+
+/// Puts the snapped windows moved along with their group back where the user snapped them.
+/// Windows the user has moved since are left alone.
+fn restore_snapped() {
+    let snapped_rects = mem::take(&mut *SNAPPED_RECTS.lock().unwrap());
+    let places: Vec<(HWND, Rect)> = snapped_rects.into_iter()
+        .filter(|&(key, (_, placed))| unsafe { IsWindow(key as HWND) } != 0 && window_rect(key as HWND) == Some(placed))
+        .map(|(key, (layout, _))| (key as HWND, layout))
+        .collect();
+    place_batch(&places, 0);
+}
+
+/// Moves every window a step along the micro-shift trajectory, a circle of the radius around where the user left it.
+/// These passes run every few seconds, so they aren't written to the history log.
+pub fn micro_shift_all_windows(radius: i32) {
+    glide::finish();
+
+    LAST_PASS.lock().unwrap().clear();
+    LAST_NUDGES.lock().unwrap().clear();
+    // Forget the windows that have been closed
+    ORIGINAL_POSITIONS.lock().unwrap().retain(|&key, _| unsafe { IsWindow(key as HWND) } != 0);
+
+    let step = MICRO_STEP.fetch_add(1, Ordering::Relaxed);
+    let mut pass = MicroPass { offset: easing::orbit_offset(step, radius), moves: Vec::new() };
+
+    unsafe {
+        EnumWindows(Some(micro_shift_windows_proc), &mut pass as *mut _ as LPARAM);
+    }
+
+    let moved = move_batches(&pass.moves);
+
+    for (planned, moved) in pass.moves.iter().zip(moved) {
+        if moved {
+            record_move(planned.hwnd, planned.from(), planned.to);
+        }
+    }
+
+    log::trace!("Micro-shifted {} windows by {:?}", pass.moves.len(), pass.offset);
+}
+
+/// Moves the windows of the last pass back to where they were before it, maximizes the windows it nudged again
+/// and puts the snap groups back. Windows the user has moved since are left alone.
+pub fn undo_last_pass() {
+    glide::finish();
+    maximized::maximize_windows(&std::mem::take(&mut *LAST_NUDGES.lock().unwrap()));
+    restore_snapped();
+    let last_pass = std::mem::take(&mut *LAST_PASS.lock().unwrap());
+    let mut originals = ORIGINAL_POSITIONS.lock().unwrap();
+
+    for (key, (from, to)) in last_pass {
+        if move_back(key as HWND, to, from) {
+            if let Some((_, placed)) = originals.get_mut(&key) {
+                *placed = from;
+            }
+        }
+    }
+}
+
+/// Moves every window OLEDShift has moved back to where the user left it, maximizes the nudged windows again
+/// and puts the snap groups back. Windows the user has moved since are left alone.
+pub fn restore_windows() {
+    glide::finish();
+    maximized::maximize_all();
+    restore_snapped();
+    LAST_PASS.lock().unwrap().clear();
+    LAST_NUDGES.lock().unwrap().clear();
+    let originals = std::mem::take(&mut *ORIGINAL_POSITIONS.lock().unwrap());
+
+    for (key, (original, placed)) in originals {
+        move_back(key as HWND, placed, original);
     }
 }
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::ptr;
+
+use lazy_static::lazy_static;
+use libloading::Library;
+use winapi::shared::minwindef::{BOOL, DWORD};
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::{CreateWindowExW, InvalidateRect, WS_CHILD, WS_VISIBLE};
+
+use crate::easing::{self, Position};
+use crate::monitor_info::{get_all_monitors_info, monitor_device_name};
+use crate::mover;
+use crate::overlay::{self, Overlay};
+
+/// Highest orbit radius allowed, in pixels. The clicks land where the image would be without the orbit, so it has to stay small.
+pub const MAX_ORBIT_RADIUS: i32 = 10;
+
+/// Time between two frames of the shifted image, the magnifier only redraws when asked to
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// How often the monitors are checked for changes
+const MONITOR_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the thread wakes up while the orbit is off, to check whether it was turned on or the app exits
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The window class of the magnifier control, registered by MagInitialize
+const WC_MAGNIFIER: &str = "Magnifier";
+
+/// Leaves the listed windows out of what the magnifier shows
+const MW_FILTERMODE_EXCLUDE: DWORD = 0;
+
+/// The orbit radius and how long the image stays at each step, synced from the settings by the controller
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitConfig {
+    pub radius: i32,
+    pub step_delay: Duration,
+}
+
+/// The functions of Magnification.dll, loaded at runtime as winapi has no bindings for them
+struct Magnification {
+    /// Kept loaded for as long as the functions are used
+    _library: Library,
+    uninitialize: unsafe extern "system" fn() -> BOOL,
+    set_window_source: unsafe extern "system" fn(HWND, RECT) -> BOOL,
+    set_window_filter_list: unsafe extern "system" fn(HWND, DWORD, i32, *mut HWND) -> BOOL,
+}
+
+impl Magnification {
+    /// Loads the library and initializes it for the calling thread
+    fn load() -> Result<Magnification, String> {
+        unsafe {
+            let library = Library::new("Magnification.dll").map_err(|err| err.to_string())?;
+            let initialize = *library.get::<unsafe extern "system" fn() -> BOOL>(b"MagInitialize").map_err(|err| err.to_string())?;
+            let uninitialize = *library.get::<unsafe extern "system" fn() -> BOOL>(b"MagUninitialize").map_err(|err| err.to_string())?;
+            let set_window_source = *library.get::<unsafe extern "system" fn(HWND, RECT) -> BOOL>(b"MagSetWindowSource").map_err(|err| err.to_string())?;
+            let set_window_filter_list = *library.get::<unsafe extern "system" fn(HWND, DWORD, i32, *mut HWND) -> BOOL>(b"MagSetWindowFilterList").map_err(|err| err.to_string())?;
+
+            if initialize() == 0 {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+
+            return Ok(Magnification { _library: library, uninitialize, set_window_source, set_window_filter_list });
+        }
+    }
+}
+
+impl Drop for Magnification {
+    fn drop(&mut self) {
+        unsafe { (self.uninitialize)() };
+    }
+}
+
+/// A monitor showing its shifted image: a click-through window covering it, with a magnifier control inside.
+/// The window is an overlay, so the passes never move it and only the orbit shifts the image.
+struct Host {
+    rect: RECT,
+    window: Overlay,
+    magnifier: HWND,
+}
+
+lazy_static! {
+    /// The orbit, None when it's off
+    static ref CONFIG: Mutex<Option<OrbitConfig>> = Mutex::new(None);
+
+    static ref THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Set when the app exits, the orbit thread puts the image back and stops
+static CLOSED: AtomicBool = AtomicBool::new(false);
+
+fn wide(text: &str) -> Vec<u16> {
+    return OsStr::new(text).encode_wide().chain(std::iter::once(0)).collect();
+}
+
+/// Sets the orbit, None turns it off and shows the desktop as it is again
+pub fn configure(config: Option<OrbitConfig>) {
+    *CONFIG.lock().unwrap() = config;
+}
+
+/// Starts the orbit thread, the magnifier windows live on that thread
+pub fn start() {
+    let spawned = thread::Builder::new().name("orbit_thread".to_string()).spawn(run);
+    match spawned {
+        Ok(handle) => *THREAD.lock().unwrap() = Some(handle),
+        Err(err) => log::error!("Failed to start the orbit thread: {}", err),
+    }
+}
+
+/// Puts the image back and stops the orbit, for when the app exits
+pub fn close() {
+    CLOSED.store(true, Ordering::Relaxed);
+    if let Some(handle) = THREAD.lock().unwrap().take() {
+        let _ = handle.join();
+    }
+}
+
+fn same_rect(a: &RECT, b: &RECT) -> bool {
+    return a.left == b.left && a.top == b.top && a.right == b.right && a.bottom == b.bottom;
+}
+
+/// Covers the monitor with a host window showing the magnified screen, at 1x so it's the screen as it is
+fn create_host(rect: RECT) -> Option<Host> {
+    // Invisible until the magnifier is in place, a black screen would flash otherwise
+    let window = Overlay::new(rect, 0)?;
+    let magnifier = unsafe {
+        CreateWindowExW(
+            0,
+            wide(WC_MAGNIFIER).as_ptr(),
+            ptr::null(),
+            WS_CHILD | WS_VISIBLE,
+            0,
+            0,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            window.hwnd(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        )
+    };
+    if magnifier.is_null() {
+        log::error!("Failed to create a magnifier window: {}", std::io::Error::last_os_error());
+        return None;
+    }
+    return Some(Host { rect, window, magnifier });
+}
+
+/// Creates and removes the hosts so every enabled monitor has one. Returns true if anything changed.
+fn sync_hosts(hosts: &mut HashMap<String, Host>) -> bool {
+    let mut changed = false;
+    let mut seen = Vec::new();
+
+    for info in get_all_monitors_info() {
+        if !mover::is_monitor_enabled(&info) {
+            continue;
+        }
+        let name = monitor_device_name(&info);
+        let rect = info.rcMonitor;
+
+        if hosts.get(&name).map_or(false, |host| !same_rect(&host.rect, &rect)) {
+            hosts.remove(&name);
+        }
+        if !hosts.contains_key(&name) {
+            if let Some(host) = create_host(rect) {
+                hosts.insert(name.clone(), host);
+                changed = true;
+            }
+        }
+        seen.push(name);
+    }
+
+    let count = hosts.len();
+    hosts.retain(|name, _| seen.contains(name));
+    return changed || hosts.len() != count;
+}
+
+/// Shows the screen shifted by the offset on every host
+fn draw(magnification: &Magnification, hosts: &HashMap<String, Host>, (dx, dy): Position) {
+    for host in hosts.values() {
+        // The image moves by the offset, so the magnifier looks at the screen moved the other way
+        let source = RECT {
+            left: host.rect.left - dx,
+            top: host.rect.top - dy,
+            right: host.rect.right - dx,
+            bottom: host.rect.bottom - dy,
+        };
+        unsafe {
+            (magnification.set_window_source)(host.magnifier, source);
+            InvalidateRect(host.magnifier, ptr::null(), 1);
+        }
+    }
+}
+
+/// Shifts the image of every enabled monitor along the orbit, a step every step delay
+fn run() {
+    let mut magnification: Option<Magnification> = None;
+    let mut load_failed = false;
+    let mut hosts: HashMap<String, Host> = HashMap::new();
+    let mut last_monitor_check: Option<Instant> = None;
+    let mut last_step = Instant::now();
+    let mut step: u64 = 0;
+
+    loop {
+        overlay::pump_messages();
+
+        if CLOSED.load(Ordering::Relaxed) {
+            hosts.clear(); // The windows must be gone before the magnification is uninitialized
+            return;
+        }
+
+        let config = *CONFIG.lock().unwrap();
+        let config = match config {
+            Some(config) if !load_failed => config,
+            _ => {
+                hosts.clear();
+                magnification = None;
+                last_monitor_check = None;
+                thread::sleep(CHECK_INTERVAL);
+                continue;
+            }
+        };
+
+        if magnification.is_none() {
+            match Magnification::load() {
+                Ok(loaded) => magnification = Some(loaded),
+                Err(err) => {
+                    log::error!("Failed to initialize the magnification, the screen orbit is off: {}", err);
+                    load_failed = true;
+                    continue;
+                }
+            }
+        }
+        let magnification = magnification.as_ref().unwrap();
+
+        if last_monitor_check.map_or(true, |last| last.elapsed() >= MONITOR_CHECK_INTERVAL) {
+            last_monitor_check = Some(Instant::now());
+            if sync_hosts(&mut hosts) {
+                // Every magnifier leaves out all the hosts, or it would show itself and the other ones
+                let mut excluded: Vec<HWND> = hosts.values().map(|host| host.window.hwnd()).collect();
+                for host in hosts.values() {
+                    unsafe {
+                        (magnification.set_window_filter_list)(host.magnifier, MW_FILTERMODE_EXCLUDE, excluded.len() as i32, excluded.as_mut_ptr());
+                    }
+                }
+                for host in hosts.values() {
+                    host.window.set_alpha(255);
+                }
+            }
+            for host in hosts.values() {
+                host.window.raise();
+            }
+        }
+
+        if last_step.elapsed() >= config.step_delay {
+            last_step = Instant::now();
+            step = step.wrapping_add(1);
+        }
+
+        draw(magnification, &hosts, easing::orbit_offset(step, config.radius));
+        thread::sleep(FRAME_INTERVAL);
+    }
+}
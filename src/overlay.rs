@@ -0,0 +1,279 @@
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::Once;
+use std::{mem, ptr};
+
+use winapi::ctypes::c_void;
+use winapi::shared::windef::{HBITMAP, HBRUSH, HDC, HGDIOBJ, HWND, POINT, RECT, SIZE};
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::{
+    CreateCompatibleDC,
+    CreateDIBSection,
+    DeleteDC,
+    DeleteObject,
+    GetStockObject,
+    SelectObject,
+    AC_SRC_ALPHA,
+    AC_SRC_OVER,
+    BITMAPINFO,
+    BITMAPINFOHEADER,
+    BI_RGB,
+    BLACK_BRUSH,
+    BLENDFUNCTION,
+    DIB_RGB_COLORS,
+};
+use winapi::um::winuser::{
+    CreateWindowExW,
+    DefWindowProcW,
+    DestroyWindow,
+    DispatchMessageW,
+    PeekMessageW,
+    RegisterClassExW,
+    SetLayeredWindowAttributes,
+    SetWindowDisplayAffinity,
+    SetWindowPos,
+    ShowWindow,
+    TranslateMessage,
+    UpdateLayeredWindow,
+    HWND_TOPMOST,
+    LWA_ALPHA,
+    MSG,
+    PM_REMOVE,
+    SW_SHOWNOACTIVATE,
+    SWP_NOACTIVATE,
+    SWP_NOMOVE,
+    SWP_NOSIZE,
+    ULW_ALPHA,
+    WNDCLASSEXW,
+    WS_EX_LAYERED,
+    WS_EX_NOACTIVATE,
+    WS_EX_TOOLWINDOW,
+    WS_EX_TOPMOST,
+    WS_EX_TRANSPARENT,
+    WS_POPUP,
+};
+
+use crate::window_classes::OVERLAY_CLASS;
+
+static REGISTER_CLASS: Once = Once::new();
+
+/// Keeps a window out of screenshots and screen captures, Windows 10 2004 and later
+const WDA_EXCLUDEFROMCAPTURE: u32 = 0x00000011;
+
+fn wide(text: &str) -> Vec<u16> {
+    return OsStr::new(text).encode_wide().chain(std::iter::once(0)).collect();
+}
+
+/// A black, click-through window that stays on top and darkens whatever is below it.
+/// It belongs to the thread that created it, that thread must pump its messages with `pump_messages`.
+pub struct Overlay {
+    hwnd: HWND,
+}
+
+/// Creates a black, click-through, topmost window covering the rect, not shown yet
+fn create_window(rect: &RECT) -> Option<HWND> {
+    let class_name = wide(OVERLAY_CLASS);
+    let instance = unsafe { GetModuleHandleW(ptr::null()) };
+
+    REGISTER_CLASS.call_once(|| {
+        let mut class: WNDCLASSEXW = unsafe { mem::zeroed() };
+        class.cbSize = mem::size_of::<WNDCLASSEXW>() as u32;
+        class.lpfnWndProc = Some(DefWindowProcW);
+        class.hInstance = instance;
+        class.hbrBackground = unsafe { GetStockObject(BLACK_BRUSH as i32) } as HBRUSH;
+        class.lpszClassName = class_name.as_ptr();
+        if unsafe { RegisterClassExW(&class) } == 0 {
+            log::error!("Failed to register the overlay window class: {}", std::io::Error::last_os_error());
+        }
+    });
+
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+            class_name.as_ptr(),
+            ptr::null(),
+            WS_POPUP,
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            instance,
+            ptr::null_mut(),
+        )
+    };
+    if hwnd.is_null() {
+        log::error!("Failed to create an overlay window: {}", std::io::Error::last_os_error());
+        return None;
+    }
+    return Some(hwnd);
+}
+
+/// Puts the window back on top, other topmost windows like the taskbar raise themselves above it when clicked
+fn raise(hwnd: HWND) {
+    unsafe { SetWindowPos(hwnd, HWND_TOPMOST, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE) };
+}
+
+impl Overlay {
+    /// Creates an overlay covering the rect, in screen coordinates. 0 alpha is invisible, 255 is black.
+    pub fn new(rect: RECT, alpha: u8) -> Option<Overlay> {
+        let overlay = Overlay { hwnd: create_window(&rect)? };
+        overlay.set_alpha(alpha);
+        unsafe { ShowWindow(overlay.hwnd, SW_SHOWNOACTIVATE) };
+        return Some(overlay);
+    }
+
+    /// Changes how dark the overlay is, 0 is invisible and 255 is black
+    pub fn set_alpha(&self, alpha: u8) {
+        unsafe { SetLayeredWindowAttributes(self.hwnd, 0, alpha, LWA_ALPHA) };
+    }
+
+    pub fn raise(&self) {
+        raise(self.hwnd);
+    }
+
+    pub fn hwnd(&self) -> HWND {
+        return self.hwnd;
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        unsafe { DestroyWindow(self.hwnd) };
+    }
+}
+
+/// An overlay split into a grid of tiles, each darkened on its own.
+/// It's left out of screen captures where Windows allows it, so the screen below can still be sampled.
+/// The passes never move it, like any overlay, so the tiles stay lined up with the monitor and not its work area.
+pub struct TileOverlay {
+    hwnd: HWND,
+    rect: RECT,
+    columns: usize,
+    rows: usize,
+    /// The memory device context holding the bitmap that is drawn to the window
+    dc: HDC,
+    bitmap: HBITMAP,
+    previous_bitmap: HGDIOBJ,
+    /// The pixels of the bitmap, premultiplied BGRA, top row first
+    pixels: *mut u32,
+    excluded_from_capture: bool,
+}
+
+impl TileOverlay {
+    /// Creates an overlay covering the rect, in screen coordinates, with every tile invisible
+    pub fn new(rect: RECT, columns: usize, rows: usize) -> Option<TileOverlay> {
+        let hwnd = create_window(&rect)?;
+        let width = rect.right - rect.left;
+        let height = rect.bottom - rect.top;
+
+        let mut info: BITMAPINFO = unsafe { mem::zeroed() };
+        info.bmiHeader.biSize = mem::size_of::<BITMAPINFOHEADER>() as u32;
+        info.bmiHeader.biWidth = width;
+        info.bmiHeader.biHeight = -height; // Top-down
+        info.bmiHeader.biPlanes = 1;
+        info.bmiHeader.biBitCount = 32;
+        info.bmiHeader.biCompression = BI_RGB;
+
+        let dc = unsafe { CreateCompatibleDC(ptr::null_mut()) };
+        let mut bits: *mut c_void = ptr::null_mut();
+        let bitmap = unsafe { CreateDIBSection(dc, &info, DIB_RGB_COLORS, &mut bits, ptr::null_mut(), 0) };
+        if dc.is_null() || bitmap.is_null() || bits.is_null() {
+            log::error!("Failed to create the bitmap of a tile overlay: {}", std::io::Error::last_os_error());
+            unsafe {
+                if !bitmap.is_null() {
+                    DeleteObject(bitmap as HGDIOBJ);
+                }
+                if !dc.is_null() {
+                    DeleteDC(dc);
+                }
+                DestroyWindow(hwnd);
+            }
+            return None;
+        }
+        let previous_bitmap = unsafe { SelectObject(dc, bitmap as HGDIOBJ) };
+
+        let excluded_from_capture = unsafe { SetWindowDisplayAffinity(hwnd, WDA_EXCLUDEFROMCAPTURE) } != 0;
+
+        let overlay = TileOverlay {
+            hwnd,
+            rect,
+            columns: columns.max(1),
+            rows: rows.max(1),
+            dc,
+            bitmap,
+            previous_bitmap,
+            pixels: bits as *mut u32,
+            excluded_from_capture,
+        };
+        overlay.set_alphas(&[]);
+        unsafe { ShowWindow(hwnd, SW_SHOWNOACTIVATE) };
+        return Some(overlay);
+    }
+
+    /// Returns false if the overlay shows up in screen captures, so sampling the screen would see the dimming
+    pub fn is_excluded_from_capture(&self) -> bool {
+        return self.excluded_from_capture;
+    }
+
+    /// Darkens each tile, row by row, 0 is invisible and 255 is black. Missing tiles are invisible.
+    pub fn set_alphas(&self, alphas: &[u8]) {
+        let width = (self.rect.right - self.rect.left) as usize;
+        let height = (self.rect.bottom - self.rect.top) as usize;
+        let pixels = unsafe { std::slice::from_raw_parts_mut(self.pixels, width * height) };
+
+        for y in 0..height {
+            let row = y * self.rows / height;
+            let line = &mut pixels[y * width..(y + 1) * width];
+            for (x, pixel) in line.iter_mut().enumerate() {
+                let column = x * self.columns / width;
+                let alpha = alphas.get(row * self.columns + column).copied().unwrap_or(0);
+                // Premultiplied black is only the alpha
+                *pixel = (alpha as u32) << 24;
+            }
+        }
+
+        let mut position = POINT { x: self.rect.left, y: self.rect.top };
+        let mut size = SIZE { cx: width as i32, cy: height as i32 };
+        let mut source = POINT { x: 0, y: 0 };
+        let mut blend = BLENDFUNCTION {
+            BlendOp: AC_SRC_OVER,
+            BlendFlags: 0,
+            SourceConstantAlpha: 255,
+            AlphaFormat: AC_SRC_ALPHA,
+        };
+        let updated = unsafe {
+            UpdateLayeredWindow(self.hwnd, ptr::null_mut(), &mut position, &mut size, self.dc, &mut source, 0, &mut blend, ULW_ALPHA)
+        };
+        if updated == 0 {
+            log::warn!("Failed to update a tile overlay: {}", std::io::Error::last_os_error());
+        }
+    }
+
+    pub fn raise(&self) {
+        raise(self.hwnd);
+    }
+}
+
+impl Drop for TileOverlay {
+    fn drop(&mut self) {
+        unsafe {
+            DestroyWindow(self.hwnd);
+            SelectObject(self.dc, self.previous_bitmap);
+            DeleteObject(self.bitmap as HGDIOBJ);
+            DeleteDC(self.dc);
+        }
+    }
+}
+
+/// Handles the pending messages of the overlays of the calling thread, without waiting for new ones
+pub fn pump_messages() {
+    let mut message: MSG = unsafe { mem::zeroed() };
+    while unsafe { PeekMessageW(&mut message, ptr::null_mut(), 0, 0, PM_REMOVE) } != 0 {
+        unsafe {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
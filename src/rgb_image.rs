@@ -0,0 +1,203 @@
+// A plain RGB image, written out as PNG or BMP by hand so exporting a heatmap needs no image library
+
+/// The biggest block a stored deflate block can hold
+const MAX_STORED_BLOCK: usize = 65535;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// An image as 3 bytes per pixel, red, green and blue, row by row from the top
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbImage {
+    /// Creates a black image
+    pub fn new(width: usize, height: usize) -> Self {
+        return RgbImage { width, height, pixels: vec![0; width * height * 3] };
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: [u8; 3]) {
+        let offset = (y * self.width + x) * 3;
+        self.pixels[offset..offset + 3].copy_from_slice(&color);
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> [u8; 3] {
+        let offset = (y * self.width + x) * 3;
+        return [self.pixels[offset], self.pixels[offset + 1], self.pixels[offset + 2]];
+    }
+
+    /// Returns the image scaled up by a whole factor, every pixel becomes a square
+    pub fn scaled(&self, factor: usize) -> RgbImage {
+        let factor = factor.max(1);
+        let mut scaled = RgbImage::new(self.width * factor, self.height * factor);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.set(x, y, self.get(x / factor, y / factor));
+            }
+        }
+        return scaled;
+    }
+
+    /// Encodes the image as a PNG file. The data is stored without compression, the heatmaps are small anyway.
+    pub fn to_png(&self) -> Vec<u8> {
+        // Every row starts with its filter type, 0 is none
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width * 3).take(self.height) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bits per channel, RGB, deflate, standard filters, not interlaced
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut png, b"IEND", &[]);
+        return png;
+    }
+
+    /// Encodes the image as a 24-bit BMP file, the format NWG can load bitmaps from without the image decoder
+    pub fn to_bmp(&self) -> Vec<u8> {
+        const HEADERS_SIZE: usize = 14 + 40;
+        // Rows are padded to 4 bytes and stored from the bottom up, in BGR order
+        #[allow(clippy::manual_div_ceil)] // div_ceil needs a newer Rust than the one the app supports
+        let row_size = (self.width * 3 + 3) / 4 * 4;
+        let data_size = row_size * self.height;
+
+        let mut bmp = Vec::with_capacity(HEADERS_SIZE + data_size);
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&((HEADERS_SIZE + data_size) as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&(HEADERS_SIZE as u32).to_le_bytes());
+
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&(self.width as i32).to_le_bytes());
+        bmp.extend_from_slice(&(self.height as i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes()); // Planes
+        bmp.extend_from_slice(&24u16.to_le_bytes()); // Bits per pixel
+        bmp.extend_from_slice(&0u32.to_le_bytes()); // No compression
+        bmp.extend_from_slice(&(data_size as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 16]); // Resolution and palette, unused
+
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let [red, green, blue] = self.get(x, y);
+                bmp.extend_from_slice(&[blue, green, red]);
+            }
+            bmp.resize(bmp.len() + row_size - self.width * 3, 0);
+        }
+        return bmp;
+    }
+}
+
+/// Appends a PNG chunk: length, type, data and the CRC of the type and data
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps the data in a zlib stream made of stored deflate blocks, which any PNG reader accepts
+pub fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01]; // Deflate with a 32K window, no preset dictionary, fastest compression
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]); // A single empty final block
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let length = block.len() as u16;
+        stream.push(if last { 1 } else { 0 });
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    return stream;
+}
+
+/// The CRC-32 PNG chunks end with, the same as zip's
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    return !crc;
+}
+
+/// The Adler-32 checksum zlib streams end with
+pub fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % MODULO;
+        b = (b + a) % MODULO;
+    }
+    return (b << 16) | a;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn adler32_matches_known_values() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn zlib_stored_splits_into_blocks_of_at_most_65535_bytes() {
+        let data = vec![7u8; MAX_STORED_BLOCK + 10];
+        let stream = zlib_stored(&data);
+
+        assert_eq!(&stream[..2], &[0x78, 0x01]);
+        // The first block is full and not final
+        assert_eq!(&stream[2..7], &[0, 0xFF, 0xFF, 0x00, 0x00]);
+        // The second one holds the rest and is final
+        let second = 7 + MAX_STORED_BLOCK;
+        assert_eq!(&stream[second..second + 5], &[1, 10, 0, !10u8, 0xFF]);
+        assert_eq!(stream.len(), 2 + 5 + MAX_STORED_BLOCK + 5 + 10 + 4);
+        assert_eq!(&stream[stream.len() - 4..], &adler32(&data).to_be_bytes());
+    }
+
+    #[test]
+    fn zlib_stored_of_nothing_is_a_single_empty_block() {
+        assert_eq!(zlib_stored(&[]), vec![0x78, 0x01, 1, 0, 0, 0xFF, 0xFF, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn png_has_a_valid_header_and_ends_with_iend() {
+        let mut image = RgbImage::new(3, 2);
+        image.set(1, 1, [10, 20, 30]);
+        let png = image.to_png();
+
+        assert_eq!(&png[..8], &PNG_SIGNATURE);
+        assert_eq!(&png[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&png[24..29], &[8, 2, 0, 0, 0]);
+        assert_eq!(&png[29..33], &crc32(&png[12..29]).to_be_bytes());
+        assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+}
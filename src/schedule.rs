@@ -0,0 +1,97 @@
+// The daily window of time the windows are shifted in, for screens that are only on during working hours
+
+/// The minutes in a day, a time of day is counted in minutes since midnight
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// The time of day the windows are shifted from, up to the time they stop.
+/// When it ends before it starts, it runs overnight, e.g. from 22:00 to 06:00.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Schedule {
+    /// Returns true if the minute of the day is within the schedule, the start included and the end not.
+    /// A schedule that ends when it starts lasts the whole day.
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start == self.end {
+            return true;
+        }
+        if self.start < self.end {
+            return self.start <= minute && minute < self.end;
+        }
+        return minute >= self.start || minute < self.end;
+    }
+}
+
+/// Parses a 24-hour time like "8:30" or "22:05" into minutes since midnight
+pub fn parse_time(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    if hours.is_empty() || minutes.len() != 2 || !hours.chars().chain(minutes.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    if hours >= 24 || minutes >= 60 {
+        return None;
+    }
+    return Some(hours * 60 + minutes);
+}
+
+/// Formats minutes since midnight the way the settings file and the settings window show them, e.g. "08:30"
+pub fn format_time(minute: u32) -> String {
+    let minute = minute % MINUTES_PER_DAY;
+    return format!("{:02}:{:02}", minute / 60, minute % 60);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_24_hour_times() {
+        assert_eq!(parse_time("08:30"), Some(8 * 60 + 30));
+        assert_eq!(parse_time(" 8:30 "), Some(8 * 60 + 30));
+        assert_eq!(parse_time("23:59"), Some(MINUTES_PER_DAY - 1));
+        assert_eq!(parse_time("00:00"), Some(0));
+
+        for invalid in ["24:00", "12:60", "12:5", "12", ":30", "noon", "-1:30", "12:+5"] {
+            assert_eq!(parse_time(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn formatting_round_trips() {
+        for minute in [0, 5, 8 * 60 + 30, MINUTES_PER_DAY - 1] {
+            assert_eq!(parse_time(&format_time(minute)), Some(minute));
+        }
+        assert_eq!(format_time(9 * 60 + 5), "09:05");
+    }
+
+    #[test]
+    fn a_daytime_schedule_covers_its_start_but_not_its_end() {
+        let schedule = Schedule { start: 8 * 60, end: 18 * 60 };
+        assert!(schedule.contains(8 * 60));
+        assert!(schedule.contains(12 * 60));
+        assert!(!schedule.contains(18 * 60));
+        assert!(!schedule.contains(7 * 60 + 59));
+        assert!(!schedule.contains(0));
+    }
+
+    #[test]
+    fn an_overnight_schedule_wraps_around_midnight() {
+        let schedule = Schedule { start: 22 * 60, end: 6 * 60 };
+        assert!(schedule.contains(22 * 60));
+        assert!(schedule.contains(0));
+        assert!(schedule.contains(5 * 60 + 59));
+        assert!(!schedule.contains(6 * 60));
+        assert!(!schedule.contains(12 * 60));
+    }
+
+    #[test]
+    fn a_schedule_ending_when_it_starts_lasts_all_day() {
+        let schedule = Schedule { start: 9 * 60, end: 9 * 60 };
+        assert!((0..MINUTES_PER_DAY).all(|minute| schedule.contains(minute)));
+    }
+}
@@ -1,13 +1,25 @@
 use std::collections::{HashMap, HashSet};
-use std::fs::{File, write};
+use std::fs::{File, rename, write};
 use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use crate::controller::Delays;
+use crate::dimmer;
+use crate::glide::MAX_GLIDE_MILLISECONDS;
+use crate::hotkeys::{Hotkey, HotkeyAction};
+use crate::i18n::{self, tr_args, Text, Unit};
+use crate::logging;
+use crate::maximized::NudgeRules;
+use crate::orbit::{self, OrbitConfig};
+use crate::schedule::{self, Schedule};
 use crate::settings_path::settings_path;
+use crate::static_regions::DimConfig;
+use crate::taskbar::{self, TaskbarMode};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Settings {
     running: bool,
     delay_milliseconds: i32,
@@ -15,8 +27,121 @@ pub struct Settings {
     max_distance_y: i32,
     #[serde(default)] // If the field is missing, default to an empty HashMap. TODO: Maybe introduce a version field to handle future changes?
     enabled_monitors: HashMap<String, bool>,
+    #[serde(default = "default_hotkeys")] // Action name => binding, null disables the hotkey
+    hotkeys: HashMap<String, Option<String>>,
+    #[serde(default)] // Named delays and max distances, the next_profile hotkey switches to the next one
+    profiles: Vec<Profile>,
+    #[serde(default)] // The name of the profile switched to last, null before the first switch
+    active_profile: Option<String>,
+    #[serde(default)] // Seconds since the Unix epoch, null when not paused for a fixed duration
+    paused_until: Option<u64>,
+    #[serde(default)] // Only move the windows between schedule_start and schedule_end, every day
+    schedule: bool,
+    #[serde(default = "default_schedule_start")] // A 24-hour time like "08:00", the schedule runs overnight if it ends before it starts
+    schedule_start: String,
+    #[serde(default = "default_schedule_end")]
+    schedule_end: String,
+    #[serde(default)] // How long a move glides, 0 moves the windows instantly
+    glide_milliseconds: u32,
+    #[serde(default)] // Shift the desktop icons and the wallpaper along with the windows
+    shift_desktop: bool,
+    #[serde(default)] // Restore maximized windows into a slightly smaller rect and shift it, maximized again once used
+    nudge_maximized: bool,
+    #[serde(default)] // Executable file name => whether its maximized windows are nudged, overrides nudge_maximized, e.g. "vlc.exe": false
+    nudge_maximized_processes: HashMap<String, bool>,
+    #[serde(default)] // Move snapped windows too, each group of windows sharing edges by the same offset
+    move_snap_groups: bool,
+    #[serde(default)] // Move every window of a monitor by the same offset, so their layout is kept
+    coherent_shift: bool,
+    #[serde(default)] // Tiny moves every few seconds instead of the regular ones
+    micro_shift: bool,
+    #[serde(default = "default_micro_delay")]
+    micro_delay_milliseconds: i32,
+    #[serde(default = "default_micro_distance")] // The radius of the micro-shift trajectory, in pixels
+    micro_distance: i32,
+    #[serde(default)] // off, auto_hide, hide or dim, what happens to the taskbar while the user is idle
+    taskbar_mode: TaskbarMode,
+    #[serde(default = "default_taskbar_idle_minutes")]
+    taskbar_idle_minutes: u32,
+    #[serde(default)] // Slowly dim the regions of the screen that haven't changed for a long time
+    dim_static: bool,
+    #[serde(default = "default_dim_static_minutes")] // How long a region has to stay unchanged before it's dimmed
+    dim_static_minutes: u32,
+    #[serde(default = "default_dim_strength")] // How dark a dimmed region gets, in percent
+    dim_strength: u32,
+    #[serde(default)] // Record where the monitors show bright content that doesn't change, into heatmap.json
+    record_heatmap: bool,
+    #[serde(default)] // Shift the whole image of every enabled monitor along a small circle, maximized windows included
+    screen_orbit: bool,
+    #[serde(default = "default_screen_orbit_radius")] // The radius of the circle, in pixels
+    screen_orbit_radius: i32,
+    #[serde(default = "default_screen_orbit_delay")] // How long the image stays at each step of the circle
+    screen_orbit_delay_seconds: u32,
+    #[serde(default = "default_log_level")] // off, error, warn, info, debug or trace
+    log_level: String,
+    #[serde(default)] // Module name => level, overrides log_level for that module, e.g. "mover": "debug"
+    log_modules: HashMap<String, String>,
 }
 
+/// A named delay and max distance to switch to in one go, e.g. a calm one for work and a busier one for the evening
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub delay_milliseconds: i32,
+    pub max_distance_x: i32,
+    pub max_distance_y: i32,
+}
+
+fn default_schedule_start() -> String {
+    return "08:00".to_string();
+}
+
+fn default_schedule_end() -> String {
+    return "18:00".to_string();
+}
+
+fn default_micro_delay() -> i32 {
+    return 3000;
+}
+
+fn default_micro_distance() -> i32 {
+    return 2;
+}
+
+fn default_taskbar_idle_minutes() -> u32 {
+    return 5;
+}
+
+fn default_dim_static_minutes() -> u32 {
+    return 10;
+}
+
+fn default_dim_strength() -> u32 {
+    return 30;
+}
+
+fn default_screen_orbit_radius() -> i32 {
+    return 3;
+}
+
+fn default_screen_orbit_delay() -> u32 {
+    return 10;
+}
+
+fn default_log_level() -> String {
+    return "info".to_string();
+}
+
+/// Lists every action so that the settings file shows what can be bound
+fn default_hotkeys() -> HashMap<String, Option<String>> {
+    return HotkeyAction::ALL.iter()
+        .map(|action| (action.name().to_string(), action.default_binding().map(str::to_string)))
+        .collect();
+}
+
+/// Set while the settings file couldn't be read or written, the app then runs on settings that aren't saved
+static SETTINGS_ERROR: AtomicBool = AtomicBool::new(false);
+
 /// Lowest delay allowed, in milliseconds (1 second)
 pub const LOWEST_DELAY: Duration = Duration::from_secs(1);
 
@@ -26,6 +151,13 @@ pub const MAX_DELAY: Duration = Duration::from_secs(30 * 60);
 /// Lowest max distance allowed, in pixels
 pub const LOWEST_MAX_DISTANCE: i32 = 1;
 
+/// Lowest micro-shift delay allowed, in milliseconds (100 milliseconds).
+/// Micro-shift moves the windows by a pixel at most, so it may run more often than the regular passes.
+pub const MICRO_LOWEST_DELAY: Duration = Duration::from_millis(100);
+
+/// Highest micro-shift distance allowed, in pixels. Anything bigger is what the regular passes are for.
+pub const MICRO_MAX_DISTANCE: i32 = 5;
+
 impl Settings {
     fn default() -> Self {
         // The default settings
@@ -35,9 +167,220 @@ impl Settings {
             max_distance_x: 50,
             max_distance_y: 50,
             enabled_monitors: HashMap::new(),
+            hotkeys: default_hotkeys(),
+            profiles: Vec::new(),
+            active_profile: None,
+            paused_until: None,
+            schedule: false,
+            schedule_start: default_schedule_start(),
+            schedule_end: default_schedule_end(),
+            glide_milliseconds: 0,
+            shift_desktop: false,
+            nudge_maximized: false,
+            nudge_maximized_processes: HashMap::new(),
+            move_snap_groups: false,
+            coherent_shift: false,
+            micro_shift: false,
+            micro_delay_milliseconds: default_micro_delay(),
+            micro_distance: default_micro_distance(),
+            taskbar_mode: TaskbarMode::Off,
+            taskbar_idle_minutes: default_taskbar_idle_minutes(),
+            dim_static: false,
+            dim_static_minutes: default_dim_static_minutes(),
+            dim_strength: default_dim_strength(),
+            record_heatmap: false,
+            screen_orbit: false,
+            screen_orbit_radius: default_screen_orbit_radius(),
+            screen_orbit_delay_seconds: default_screen_orbit_delay(),
+            log_level: default_log_level(),
+            log_modules: HashMap::new(),
         };
     }
 
+    /// Applies the values that were changed from the original to the edited copy, the other values are left as they are.
+    /// Whatever changed in the meantime, like the running state or a monitor toggled from the tray, isn't reverted.
+    pub fn apply_changes(&mut self, original: &Settings, edited: &Settings) {
+        let mut current = serde_json::to_value(&*self).expect("Failed to serialize the settings");
+        let original = serde_json::to_value(original).expect("Failed to serialize the settings");
+        let edited = serde_json::to_value(edited).expect("Failed to serialize the settings");
+        apply_changed_values(&mut current, &original, &edited);
+
+        match serde_json::from_value::<Settings>(current) {
+            Ok(mut settings) => {
+                // Each value is valid on its own, together with the ones changed in the meantime they may not be
+                let errors = settings.validate();
+                if !errors.is_empty() {
+                    log::warn!("Fixed the edited settings: {}", errors.join(" "));
+                }
+                *self = settings;
+            }
+            Err(err) => log::error!("Failed to apply the edited settings: {}", err),
+        }
+    }
+
+    /// Checks the values against the allowed ranges and fixes the invalid ones.
+    /// Returns a message for every value that had to be fixed, empty if all of them were valid.
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut errors: Vec<String> = Vec::new();
+
+        if self.delay_milliseconds < LOWEST_DELAY.as_millis() as i32 {
+            self.delay_milliseconds = LOWEST_DELAY.as_millis() as i32;
+            errors.push(
+                tr_args(Text::DelayTooLow, &[&i18n::count(Unit::Second, LOWEST_DELAY.as_secs())])
+            );
+        }
+        if self.delay_milliseconds > MAX_DELAY.as_millis() as i32 {
+            self.delay_milliseconds = MAX_DELAY.as_millis() as i32;
+            errors.push(
+                tr_args(Text::DelayTooHigh, &[&i18n::count(Unit::Second, MAX_DELAY.as_secs())])
+            );
+        }
+
+        if self.max_distance_x < LOWEST_MAX_DISTANCE {
+            self.max_distance_x = LOWEST_MAX_DISTANCE;
+            errors.push(
+                tr_args(Text::DistanceXTooLow, &[&i18n::count(Unit::Pixel, LOWEST_MAX_DISTANCE as u64)])
+            );
+        }
+        if self.max_distance_y < LOWEST_MAX_DISTANCE {
+            self.max_distance_y = LOWEST_MAX_DISTANCE;
+            errors.push(
+                tr_args(Text::DistanceYTooLow, &[&i18n::count(Unit::Pixel, LOWEST_MAX_DISTANCE as u64)])
+            );
+        }
+
+        let (lowest_delay, max_delay) = (LOWEST_DELAY.as_millis() as i32, MAX_DELAY.as_millis() as i32);
+        for profile in self.profiles.iter_mut() {
+            let delay = profile.delay_milliseconds.clamp(lowest_delay, max_delay);
+            let max_distance_x = profile.max_distance_x.max(LOWEST_MAX_DISTANCE);
+            let max_distance_y = profile.max_distance_y.max(LOWEST_MAX_DISTANCE);
+            if (delay, max_distance_x, max_distance_y) != (profile.delay_milliseconds, profile.max_distance_x, profile.max_distance_y) {
+                profile.delay_milliseconds = delay;
+                profile.max_distance_x = max_distance_x;
+                profile.max_distance_y = max_distance_y;
+                errors.push(
+                    tr_args(Text::ProfileInvalid, &[&profile.name])
+                );
+            }
+        }
+
+        for (time, default) in [(&mut self.schedule_start, default_schedule_start()), (&mut self.schedule_end, default_schedule_end())] {
+            if schedule::parse_time(time).is_none() {
+                errors.push(
+                    tr_args(Text::ScheduleTimeInvalid, &[&*time, &default])
+                );
+                *time = default;
+            }
+        }
+
+        for (name, binding) in self.hotkeys.iter_mut() {
+            if let Some(Err(err)) = binding.as_deref().map(Hotkey::parse) {
+                *binding = None;
+                errors.push(
+                    tr_args(Text::HotkeyInvalid, &[name, &err])
+                );
+            }
+        }
+
+        if self.glide_milliseconds > MAX_GLIDE_MILLISECONDS {
+            self.glide_milliseconds = MAX_GLIDE_MILLISECONDS;
+            errors.push(
+                tr_args(Text::GlideTooLong, &[&i18n::count(Unit::Millisecond, MAX_GLIDE_MILLISECONDS as u64)])
+            );
+        }
+
+        if self.micro_delay_milliseconds < MICRO_LOWEST_DELAY.as_millis() as i32 {
+            self.micro_delay_milliseconds = MICRO_LOWEST_DELAY.as_millis() as i32;
+            errors.push(
+                tr_args(Text::MicroDelayTooLow, &[&i18n::count(Unit::Millisecond, MICRO_LOWEST_DELAY.as_millis() as u64)])
+            );
+        }
+        if self.micro_delay_milliseconds > MAX_DELAY.as_millis() as i32 {
+            self.micro_delay_milliseconds = MAX_DELAY.as_millis() as i32;
+            errors.push(
+                tr_args(Text::MicroDelayTooHigh, &[&i18n::count(Unit::Second, MAX_DELAY.as_secs())])
+            );
+        }
+
+        if self.micro_distance < LOWEST_MAX_DISTANCE || self.micro_distance > MICRO_MAX_DISTANCE {
+            self.micro_distance = self.micro_distance.clamp(LOWEST_MAX_DISTANCE, MICRO_MAX_DISTANCE);
+            errors.push(
+                tr_args(Text::MicroDistanceInvalid, &[
+                    &i18n::count(Unit::Pixel, LOWEST_MAX_DISTANCE as u64),
+                    &i18n::count(Unit::Pixel, MICRO_MAX_DISTANCE as u64),
+                ])
+            );
+        }
+
+        if self.taskbar_idle_minutes < taskbar::LOWEST_IDLE_MINUTES || self.taskbar_idle_minutes > taskbar::MAX_IDLE_MINUTES {
+            self.taskbar_idle_minutes = self.taskbar_idle_minutes.clamp(taskbar::LOWEST_IDLE_MINUTES, taskbar::MAX_IDLE_MINUTES);
+            errors.push(
+                tr_args(Text::TaskbarIdleInvalid, &[
+                    &i18n::count(Unit::Minute, taskbar::LOWEST_IDLE_MINUTES as u64),
+                    &i18n::count(Unit::Minute, taskbar::MAX_IDLE_MINUTES as u64),
+                ])
+            );
+        }
+
+        if self.dim_static_minutes < dimmer::LOWEST_STATIC_MINUTES || self.dim_static_minutes > dimmer::MAX_STATIC_MINUTES {
+            self.dim_static_minutes = self.dim_static_minutes.clamp(dimmer::LOWEST_STATIC_MINUTES, dimmer::MAX_STATIC_MINUTES);
+            errors.push(
+                tr_args(Text::DimStaticTimeInvalid, &[
+                    &i18n::count(Unit::Minute, dimmer::LOWEST_STATIC_MINUTES as u64),
+                    &i18n::count(Unit::Minute, dimmer::MAX_STATIC_MINUTES as u64),
+                ])
+            );
+        }
+        if self.dim_strength < dimmer::LOWEST_STRENGTH || self.dim_strength > dimmer::MAX_STRENGTH {
+            self.dim_strength = self.dim_strength.clamp(dimmer::LOWEST_STRENGTH, dimmer::MAX_STRENGTH);
+            errors.push(
+                tr_args(Text::DimStrengthInvalid, &[
+                    &format!("{}%", dimmer::LOWEST_STRENGTH),
+                    &format!("{}%", dimmer::MAX_STRENGTH),
+                ])
+            );
+        }
+
+        if self.screen_orbit_radius < LOWEST_MAX_DISTANCE || self.screen_orbit_radius > orbit::MAX_ORBIT_RADIUS {
+            self.screen_orbit_radius = self.screen_orbit_radius.clamp(LOWEST_MAX_DISTANCE, orbit::MAX_ORBIT_RADIUS);
+            errors.push(
+                tr_args(Text::OrbitRadiusInvalid, &[
+                    &i18n::count(Unit::Pixel, LOWEST_MAX_DISTANCE as u64),
+                    &i18n::count(Unit::Pixel, orbit::MAX_ORBIT_RADIUS as u64),
+                ])
+            );
+        }
+        let (lowest_seconds, max_seconds) = (LOWEST_DELAY.as_secs() as u32, MAX_DELAY.as_secs() as u32);
+        if self.screen_orbit_delay_seconds < lowest_seconds || self.screen_orbit_delay_seconds > max_seconds {
+            self.screen_orbit_delay_seconds = self.screen_orbit_delay_seconds.clamp(lowest_seconds, max_seconds);
+            errors.push(
+                tr_args(Text::OrbitDelayInvalid, &[
+                    &i18n::count(Unit::Second, lowest_seconds as u64),
+                    &i18n::count(Unit::Second, max_seconds as u64),
+                ])
+            );
+        }
+
+        if logging::parse_level(&self.log_level).is_none() {
+            errors.push(
+                tr_args(Text::LogLevelInvalid, &[&self.log_level])
+            );
+            self.log_level = default_log_level();
+        }
+        let invalid_modules: Vec<String> = self.log_modules.iter()
+            .filter(|(_, level)| logging::parse_level(level).is_none())
+            .map(|(module, _)| module.clone())
+            .collect();
+        for module in invalid_modules {
+            let level = self.log_modules.remove(&module).unwrap_or_default();
+            errors.push(
+                tr_args(Text::LogModuleLevelInvalid, &[&level, &module])
+            );
+        }
+
+        return errors;
+    }
+
     pub fn get_running(&self) -> bool {
         return self.running;
     }
@@ -72,10 +415,252 @@ impl Settings {
     pub fn set_monitor_state(&mut self, monitor: &str, enabled: bool) {
         self.enabled_monitors.insert(monitor.to_string(), enabled);
     }
+
+    /// Returns the binding of the action, if any
+    pub fn get_hotkey(&self, action: HotkeyAction) -> Option<String> {
+        return self.hotkeys.get(action.name()).cloned().flatten();
+    }
+
+    /// Sets the binding of the action, None disables the hotkey
+    pub fn set_hotkey(&mut self, action: HotkeyAction, binding: Option<String>) {
+        self.hotkeys.insert(action.name().to_string(), binding);
+    }
+
+    /// Switches to the profile after the one switched to last, the first one after the last one.
+    /// Returns the profile, None if there are none.
+    pub fn next_profile(&mut self) -> Option<Profile> {
+        let active = self.active_profile.as_ref()
+            .and_then(|active| self.profiles.iter().position(|profile| &profile.name == active));
+        let next = active.map_or(0, |index| (index + 1) % self.profiles.len());
+
+        let profile = self.profiles.get(next)?.clone();
+        self.delay_milliseconds = profile.delay_milliseconds;
+        self.max_distance_x = profile.max_distance_x;
+        self.max_distance_y = profile.max_distance_y;
+        self.active_profile = Some(profile.name.clone());
+        return Some(profile);
+    }
+
+    pub fn get_paused_until(&self) -> Option<SystemTime> {
+        return self.paused_until.map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+    }
+
+    pub fn set_paused_until(&mut self, paused_until: Option<SystemTime>) {
+        self.paused_until = paused_until
+            .map(|time| time.duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0));
+    }
+
+    pub fn is_schedule(&self) -> bool {
+        return self.schedule;
+    }
+
+    pub fn set_schedule(&mut self, schedule: bool) {
+        self.schedule = schedule;
+    }
+
+    /// Returns the times the schedule starts and ends at, as they are written in the settings file
+    pub fn get_schedule_times(&self) -> (String, String) {
+        return (self.schedule_start.clone(), self.schedule_end.clone());
+    }
+
+    /// Sets the times the schedule starts and ends at, validate() puts back the defaults of invalid ones
+    pub fn set_schedule_times(&mut self, start: &str, end: &str) {
+        self.schedule_start = start.trim().to_string();
+        self.schedule_end = end.trim().to_string();
+    }
+
+    /// Returns how long a move glides, zero if the windows are moved instantly
+    pub fn get_glide_duration(&self) -> Duration {
+        return Duration::from_millis(self.glide_milliseconds as u64);
+    }
+
+    pub fn set_glide_duration(&mut self, duration: Duration) {
+        self.glide_milliseconds = duration.as_millis() as u32;
+    }
+
+    pub fn is_desktop_shift(&self) -> bool {
+        return self.shift_desktop;
+    }
+
+    pub fn set_desktop_shift(&mut self, shift_desktop: bool) {
+        self.shift_desktop = shift_desktop;
+    }
+
+    pub fn is_nudge_maximized(&self) -> bool {
+        return self.nudge_maximized;
+    }
+
+    pub fn set_nudge_maximized(&mut self, nudge_maximized: bool) {
+        self.nudge_maximized = nudge_maximized;
+    }
+
+    pub fn is_move_snap_groups(&self) -> bool {
+        return self.move_snap_groups;
+    }
+
+    pub fn set_move_snap_groups(&mut self, move_snap_groups: bool) {
+        self.move_snap_groups = move_snap_groups;
+    }
+
+    pub fn is_coherent_shift(&self) -> bool {
+        return self.coherent_shift;
+    }
+
+    pub fn set_coherent_shift(&mut self, coherent_shift: bool) {
+        self.coherent_shift = coherent_shift;
+    }
+
+    /// Returns the executable file name => whether its maximized windows are nudged, overriding nudge_maximized
+    pub fn get_nudge_processes(&self) -> HashMap<String, bool> {
+        return self.nudge_maximized_processes.clone();
+    }
+
+    pub fn set_nudge_processes(&mut self, processes: HashMap<String, bool>) {
+        self.nudge_maximized_processes = processes;
+    }
+
+    /// Returns which processes get their maximized windows nudged
+    pub fn get_nudge_rules(&self) -> NudgeRules {
+        return NudgeRules {
+            enabled: self.nudge_maximized,
+            processes: self.nudge_maximized_processes.clone(),
+        };
+    }
+
+    pub fn is_micro_shift(&self) -> bool {
+        return self.micro_shift;
+    }
+
+    pub fn set_micro_shift(&mut self, micro_shift: bool) {
+        self.micro_shift = micro_shift;
+    }
+
+    /// Returns the delay between two micro-shift passes
+    pub fn get_micro_delay(&self) -> Duration {
+        return Duration::from_millis(self.micro_delay_milliseconds as u64);
+    }
+
+    pub fn set_micro_delay(&mut self, delay: Duration) {
+        self.micro_delay_milliseconds = delay.as_millis() as i32;
+    }
+
+    /// Returns the radius of the micro-shift trajectory, in pixels
+    pub fn get_micro_distance(&self) -> i32 {
+        return self.micro_distance;
+    }
+
+    pub fn set_micro_distance(&mut self, distance: i32) {
+        self.micro_distance = distance;
+    }
+
+    pub fn get_taskbar_mode(&self) -> TaskbarMode {
+        return self.taskbar_mode;
+    }
+
+    pub fn set_taskbar_mode(&mut self, mode: TaskbarMode) {
+        self.taskbar_mode = mode;
+    }
+
+    /// Returns how long the user has to be idle before the taskbar is protected
+    pub fn get_taskbar_idle(&self) -> Duration {
+        return Duration::from_secs(self.taskbar_idle_minutes as u64 * 60);
+    }
+
+    pub fn set_taskbar_idle(&mut self, idle: Duration) {
+        self.taskbar_idle_minutes = (idle.as_secs() / 60) as u32;
+    }
+
+    pub fn is_dim_static(&self) -> bool {
+        return self.dim_static;
+    }
+
+    pub fn set_dim_static(&mut self, dim_static: bool) {
+        self.dim_static = dim_static;
+    }
+
+    /// Returns how long a region has to stay unchanged before it's dimmed
+    pub fn get_dim_static_after(&self) -> Duration {
+        return Duration::from_secs(self.dim_static_minutes as u64 * 60);
+    }
+
+    pub fn set_dim_static_after(&mut self, after: Duration) {
+        self.dim_static_minutes = (after.as_secs() / 60) as u32;
+    }
+
+    /// Returns how dark a dimmed region gets, in percent
+    pub fn get_dim_strength(&self) -> u32 {
+        return self.dim_strength;
+    }
+
+    pub fn set_dim_strength(&mut self, strength: u32) {
+        self.dim_strength = strength;
+    }
+
+    pub fn is_record_heatmap(&self) -> bool {
+        return self.record_heatmap;
+    }
+
+    pub fn set_record_heatmap(&mut self, record_heatmap: bool) {
+        self.record_heatmap = record_heatmap;
+    }
+
+    pub fn is_screen_orbit(&self) -> bool {
+        return self.screen_orbit;
+    }
+
+    pub fn set_screen_orbit(&mut self, screen_orbit: bool) {
+        self.screen_orbit = screen_orbit;
+    }
+
+    /// Returns the radius of the screen orbit, in pixels
+    pub fn get_screen_orbit_radius(&self) -> i32 {
+        return self.screen_orbit_radius;
+    }
+
+    pub fn set_screen_orbit_radius(&mut self, radius: i32) {
+        self.screen_orbit_radius = radius;
+    }
+
+    /// Returns how long the image stays at each step of the screen orbit
+    pub fn get_screen_orbit_delay(&self) -> Duration {
+        return Duration::from_secs(self.screen_orbit_delay_seconds as u64);
+    }
+
+    pub fn set_screen_orbit_delay(&mut self, delay: Duration) {
+        self.screen_orbit_delay_seconds = delay.as_secs() as u32;
+    }
+
+    /// Returns the log level and the per-module overrides
+    pub fn get_log_levels(&self) -> (String, HashMap<String, String>) {
+        return (self.log_level.clone(), self.log_modules.clone());
+    }
 }
 
 
 
+/// Copies the values that differ between the original and the edited object into the current one.
+/// Maps like the monitors and the hotkeys are compared key by key, so only the entries that were edited are copied.
+fn apply_changed_values(current: &mut Value, original: &Value, edited: &Value) {
+    let (Some(current), Some(original), Some(edited)) = (current.as_object_mut(), original.as_object(), edited.as_object()) else {
+        return;
+    };
+
+    for (key, value) in edited {
+        match (original.get(key), current.get_mut(key)) {
+            (Some(original), _) if original == value => {}
+            (Some(original), Some(current)) if original.is_object() && value.is_object() && current.is_object() => {
+                apply_changed_values(current, original, value);
+            }
+            _ => {
+                current.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in original.keys().filter(|key| !edited.contains_key(*key)) {
+        current.remove(key);
+    }
+}
+
 pub struct SettingsManager {
     settings: Arc<Mutex<Settings>>,
 }
@@ -90,16 +675,16 @@ impl Default for SettingsManager {
 
 impl SettingsManager {
     pub fn new() -> Result<SettingsManager, (String, SettingsManager)> {
-        println!("Loading settings...");
+        log::info!("Loading settings...");
 
         let path = settings_path();
 
         if !path.exists() {
-            println!("No settings file found, creating default settings...");
+            log::info!("No settings file found, creating default settings...");
             let settings = Settings::default();
             let serialized = serde_json::to_string_pretty(&settings).unwrap();
             if let Err(err) = write(&path, serialized) {
-                eprintln!("Failed to create the default settings file: {}", err);
+                log::error!("Failed to create the default settings file: {}", err);
             }
         }
 
@@ -112,42 +697,16 @@ impl SettingsManager {
 
         return match result {
             Ok(mut settings) => {
-                let mut errors: Vec<String> = Vec::new();
-
                 // Validate the settings and update them if necessary since the user could have edited the settings file
-                if settings.delay_milliseconds < LOWEST_DELAY.as_millis() as i32 {
-                    settings.delay_milliseconds = LOWEST_DELAY.as_millis() as i32;
-                    errors.push(
-                        format!("The delay was too low, it has been set to the lowest possible value of {} second.", LOWEST_DELAY.as_millis() as i32 / 1000)
-                    );
-                }
-                if settings.delay_milliseconds > MAX_DELAY.as_millis() as i32 {
-                    settings.delay_milliseconds = MAX_DELAY.as_millis() as i32;
-                    errors.push(
-                        format!("The delay was too high, it has been set to the highest possible value of {} seconds.", MAX_DELAY.as_millis() as i32 / 1000)
-                    );
-                }
-
-                if settings.max_distance_x < LOWEST_MAX_DISTANCE {
-                    settings.max_distance_x = LOWEST_MAX_DISTANCE;
-                    errors.push(
-                        format!("The max distance X was too low, it has been set to the lowest possible value of {} pixel.", LOWEST_MAX_DISTANCE)
-                    );
-                }
-                if settings.max_distance_y < LOWEST_MAX_DISTANCE {
-                    settings.max_distance_y = LOWEST_MAX_DISTANCE;
-                    errors.push(
-                        format!("The max distance Y was too low, it has been set to the lowest possible value of {} pixel.", LOWEST_MAX_DISTANCE)
-                    );
-                }
+                let errors = settings.validate();
 
                 if !errors.is_empty() {
-                    println!("Found invalid values in the settings file!");
+                    log::warn!("Found invalid values in the settings file: {}", errors.join(" "));
 
                     // Update the settings file with the valid settings
                     let serialized = serde_json::to_string_pretty(&settings).expect("Failed to serialize the settings");
                     if let Err(err) = write(&path, serialized) {
-                        eprintln!("Failed to update the settings file: {}", err);
+                        log::error!("Failed to update the settings file: {}", err);
                     }
 
                     return Err((
@@ -158,12 +717,15 @@ impl SettingsManager {
                     ));
                 }
 
-                println!("Settings loaded successfully!");
+                log::info!("Settings loaded successfully");
                 Ok(SettingsManager {
                     settings: Arc::new(Mutex::new(settings)),
                 })
             }
             Err(err) => {
+                SETTINGS_ERROR.store(true, Ordering::Relaxed);
+                log::error!("Failed to read the settings file {:?}: {}", path, err);
+
                 // Send the error message back to the UI with the default settings
                 Err((
                     err.to_string(),
@@ -173,20 +735,45 @@ impl SettingsManager {
         }
     }
 
-    /// Serializes and saves the settings to the settings file
+    /// Serializes and saves the settings to the settings file.
+    /// The file is written next to it first and then moved over it, so it's never left half written.
     fn save_settings(settings: &Settings) {
         let path = settings_path();
         let serialized = serde_json::to_string_pretty(settings).unwrap();
+        let temp_path = path.with_extension("json.tmp");
 
-        if let Err(err) = write(&path, serialized) {
-            eprintln!("Failed to write settings file {:?}: {}", path, err);
+        match write(&temp_path, serialized).and_then(|_| rename(&temp_path, &path)) {
+            Ok(()) => SETTINGS_ERROR.store(false, Ordering::Relaxed),
+            Err(err) => {
+                SETTINGS_ERROR.store(true, Ordering::Relaxed);
+                log::error!("Failed to write settings file {:?}: {}", path, err);
+            }
         }
     }
 
+    /// Returns true if the settings file couldn't be read or written the last time it was tried
+    pub fn has_error(&self) -> bool {
+        return SETTINGS_ERROR.load(Ordering::Relaxed);
+    }
+
     // ---------------------------------------------------------------------------------------------
     // Getters and setters
     // ---------------------------------------------------------------------------------------------
 
+    /// Returns a copy of all the settings, to be edited as a draft
+    pub fn get_settings(&self) -> Settings {
+        let settings = self.settings.lock().unwrap();
+        return settings.clone();
+    }
+
+    /// Edits the current settings in one go, and saves them to the settings file.
+    /// The edited values are expected to be validated already.
+    pub fn update(&self, edit: impl FnOnce(&mut Settings)) {
+        let mut settings = self.settings.lock().unwrap();
+        edit(&mut settings);
+        SettingsManager::save_settings(&*settings);
+    }
+
     pub fn is_running(&self) -> bool {
         let settings = self.settings.lock().unwrap();
         return settings.get_running();
@@ -223,6 +810,82 @@ impl SettingsManager {
         return settings.get_max_distance();
     }
 
+    pub fn get_glide_duration(&self) -> Duration {
+        let settings = self.settings.lock().unwrap();
+        return settings.get_glide_duration();
+    }
+
+    pub fn is_desktop_shift(&self) -> bool {
+        let settings = self.settings.lock().unwrap();
+        return settings.is_desktop_shift();
+    }
+
+    pub fn is_move_snap_groups(&self) -> bool {
+        let settings = self.settings.lock().unwrap();
+        return settings.is_move_snap_groups();
+    }
+
+    pub fn is_coherent_shift(&self) -> bool {
+        let settings = self.settings.lock().unwrap();
+        return settings.is_coherent_shift();
+    }
+
+    pub fn get_nudge_rules(&self) -> NudgeRules {
+        let settings = self.settings.lock().unwrap();
+        return settings.get_nudge_rules();
+    }
+
+    /// Returns the micro-shift delay and distance, None if micro-shift is off
+    pub fn get_micro_shift(&self) -> Option<(Duration, i32)> {
+        let settings = self.settings.lock().unwrap();
+        if !settings.is_micro_shift() {
+            return None;
+        }
+        return Some((settings.get_micro_delay(), settings.get_micro_distance()));
+    }
+
+    /// Returns the taskbar protection mode and how long the user has to be idle before it kicks in
+    pub fn get_taskbar_protection(&self) -> (TaskbarMode, Duration) {
+        let settings = self.settings.lock().unwrap();
+        return (settings.get_taskbar_mode(), settings.get_taskbar_idle());
+    }
+
+    pub fn is_record_heatmap(&self) -> bool {
+        let settings = self.settings.lock().unwrap();
+        return settings.is_record_heatmap();
+    }
+
+    /// Returns the screen orbit, None if it's off
+    pub fn get_screen_orbit(&self) -> Option<OrbitConfig> {
+        let settings = self.settings.lock().unwrap();
+        if !settings.is_screen_orbit() {
+            return None;
+        }
+        return Some(OrbitConfig {
+            radius: settings.get_screen_orbit_radius(),
+            step_delay: settings.get_screen_orbit_delay(),
+        });
+    }
+
+    /// Returns how static regions are dimmed, None if they aren't
+    pub fn get_static_dimming(&self) -> Option<DimConfig> {
+        let settings = self.settings.lock().unwrap();
+        if !settings.is_dim_static() {
+            return None;
+        }
+        return Some(DimConfig {
+            static_after: settings.get_dim_static_after(),
+            fade_in: dimmer::FADE_IN,
+            max_alpha: (settings.get_dim_strength().min(100) * 255 / 100) as u8,
+        });
+    }
+
+    /// Returns the log level and the per-module overrides
+    pub fn get_log_levels(&self) -> (String, HashMap<String, String>) {
+        let settings = self.settings.lock().unwrap();
+        return settings.get_log_levels();
+    }
+
     /// Sets the max distance, in pixels, and saves the settings to the settings file
     pub fn set_max_distance(&self, max_distance_x: i32, max_distance_y: i32) {
         let mut settings = self.settings.lock().unwrap();
@@ -250,4 +913,44 @@ impl SettingsManager {
         settings.set_monitor_state(monitor, enabled);
         SettingsManager::save_settings(&*settings);
     }
+
+    pub fn get_paused_until(&self) -> Option<SystemTime> {
+        let settings = self.settings.lock().unwrap();
+        return settings.get_paused_until();
+    }
+
+    /// Sets the time the timed pause ends, None to clear it, and saves the settings to the settings file
+    pub fn set_paused_until(&self, paused_until: Option<SystemTime>) {
+        let mut settings = self.settings.lock().unwrap();
+        settings.set_paused_until(paused_until);
+        SettingsManager::save_settings(&*settings);
+    }
+
+    /// Returns the daily schedule, None if the windows are moved all day
+    pub fn get_schedule(&self) -> Option<Schedule> {
+        let settings = self.settings.lock().unwrap();
+        if !settings.is_schedule() {
+            return None;
+        }
+        // The times were validated when the settings were loaded or edited
+        let (start, end) = settings.get_schedule_times();
+        return Some(Schedule {
+            start: schedule::parse_time(&start)?,
+            end: schedule::parse_time(&end)?,
+        });
+    }
+
+    /// Returns the hotkey binding of the action, if any
+    pub fn get_hotkey(&self, action: HotkeyAction) -> Option<String> {
+        let settings = self.settings.lock().unwrap();
+        return settings.get_hotkey(action);
+    }
+
+    /// Switches to the next profile, and saves the settings to the settings file. Returns None if there are no profiles.
+    pub fn next_profile(&self) -> Option<Profile> {
+        let mut settings = self.settings.lock().unwrap();
+        let profile = settings.next_profile()?;
+        SettingsManager::save_settings(&*settings);
+        return Some(profile);
+    }
 }
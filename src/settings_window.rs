@@ -0,0 +1,1033 @@
+use crate::dialog;
+use crate::dimmer;
+use crate::glide::MAX_GLIDE_MILLISECONDS;
+use crate::hotkeys::HotkeyAction;
+use crate::i18n::{tr, tr_args, Text};
+use crate::mover;
+use crate::orbit;
+use crate::settings::{Settings, LOWEST_DELAY, LOWEST_MAX_DISTANCE, MAX_DELAY, MICRO_LOWEST_DELAY, MICRO_MAX_DISTANCE};
+use crate::settings_path::settings_path;
+use crate::taskbar::{self, TaskbarMode};
+use std::{thread, cell::RefCell};
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::Duration;
+use nwg::{CheckBoxState, ControlHandle, NativeUi, NumberSelectData};
+
+pub enum SettingsWindowData {
+    Cancel,
+    /// The settings as the window was opened with them, and as they were edited
+    Apply { original: Settings, edited: Settings },
+}
+
+/// A connected or known monitor as shown in the Monitors tab: (device_id, friendly_name, is_enabled, is_connected)
+pub type MonitorEntry = (String, String, bool, bool);
+
+#[derive(Default)]
+pub struct SettingsWindow {
+    window: nwg::Window,
+    icon: nwg::Icon,
+    big_icon: nwg::Icon,
+    tabs: nwg::TabsContainer,
+
+    general_tab: nwg::Tab,
+    enabled_check: nwg::CheckBox,
+    delay_label: nwg::Label,
+    delay_select: nwg::NumberSelect,
+    distance_x_label: nwg::Label,
+    distance_x_select: nwg::NumberSelect,
+    distance_y_label: nwg::Label,
+    distance_y_select: nwg::NumberSelect,
+    glide_label: nwg::Label,
+    glide_select: nwg::NumberSelect,
+    desktop_check: nwg::CheckBox,
+    nudge_check: nwg::CheckBox,
+    snap_groups_check: nwg::CheckBox,
+
+    schedule_tab: nwg::Tab,
+    schedule_check: nwg::CheckBox,
+    schedule_start_label: nwg::Label,
+    schedule_start_input: nwg::TextInput,
+    schedule_end_label: nwg::Label,
+    schedule_end_input: nwg::TextInput,
+
+    monitors_tab: nwg::Tab,
+    monitors_label: nwg::Label,
+    monitor_checks: Vec<(String, nwg::CheckBox)>,
+
+    rules_tab: nwg::Tab,
+    rules_label: nwg::Label,
+    rules_box: nwg::TextBox,
+
+    hotkeys_tab: nwg::Tab,
+    hotkeys_label: nwg::Label,
+    hotkey_inputs: Vec<(HotkeyAction, nwg::Label, nwg::TextInput)>,
+
+    protection_tab: nwg::Tab,
+    taskbar_mode_label: nwg::Label,
+    taskbar_mode_combo: nwg::ComboBox<&'static str>,
+    taskbar_idle_label: nwg::Label,
+    taskbar_idle_select: nwg::NumberSelect,
+    dim_check: nwg::CheckBox,
+    dim_after_label: nwg::Label,
+    dim_after_select: nwg::NumberSelect,
+    dim_strength_label: nwg::Label,
+    dim_strength_select: nwg::NumberSelect,
+    orbit_check: nwg::CheckBox,
+    orbit_radius_label: nwg::Label,
+    orbit_radius_select: nwg::NumberSelect,
+    orbit_delay_label: nwg::Label,
+    orbit_delay_select: nwg::NumberSelect,
+
+    advanced_tab: nwg::Tab,
+    settings_path_label: nwg::Label,
+    settings_path_input: nwg::TextInput,
+    open_folder_button: nwg::Button,
+    micro_check: nwg::CheckBox,
+    micro_delay_label: nwg::Label,
+    micro_delay_select: nwg::NumberSelect,
+    micro_distance_label: nwg::Label,
+    micro_distance_select: nwg::NumberSelect,
+    heatmap_check: nwg::CheckBox,
+    coherent_check: nwg::CheckBox,
+
+    ok_button: nwg::Button,
+    cancel_button: nwg::Button,
+
+    /// The settings being edited, only handed back to the tray when the user presses OK
+    draft: RefCell<Option<Settings>>,
+    /// The settings the window was opened with, only what differs from them is applied
+    original: Option<Settings>,
+    monitors: Vec<MonitorEntry>,
+    data: RefCell<Option<SettingsWindowData>>,
+}
+
+impl SettingsWindow {
+
+    /// Create the window on a new thread. The edited settings will be returned by the thread handle.
+    /// To alert the main GUI that the window closed, this function takes a notice sender object.
+    pub(crate) fn popup(sender: nwg::NoticeSender, draft: Settings, mut monitors: Vec<MonitorEntry>) -> thread::JoinHandle<SettingsWindowData> {
+        return thread::spawn(move || {
+            // Keep the monitor list in a stable order, connected ones first
+            monitors.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.1.cmp(&b.1)).then_with(|| a.0.cmp(&b.0)));
+
+            let window = SettingsWindow {
+                original: Some(draft.clone()),
+                draft: RefCell::new(Some(draft)),
+                monitors,
+                ..Default::default()
+            };
+
+            // Create the UI just like in the main function
+            let app = SettingsWindow::build_ui(window).expect("Failed to build UI");
+            app.load_draft();
+            app.label_controls();
+            if let Some(hwnd) = app.enabled_check.handle.hwnd() {
+                dialog::focus_on_open(&app.window, hwnd);
+            }
+
+            nwg::dispatch_thread_events();
+
+            // Notice the main thread that the window closed
+            sender.notice();
+
+            // Return the window data
+            return app.data.take().unwrap_or(SettingsWindowData::Cancel)
+        })
+    }
+
+    /// Names the text boxes for screen readers after the labels next to them
+    fn label_controls(&self) {
+        let number_selects = [
+            (&self.delay_select, &self.delay_label),
+            (&self.distance_x_select, &self.distance_x_label),
+            (&self.distance_y_select, &self.distance_y_label),
+            (&self.glide_select, &self.glide_label),
+            (&self.micro_delay_select, &self.micro_delay_label),
+            (&self.micro_distance_select, &self.micro_distance_label),
+            (&self.taskbar_idle_select, &self.taskbar_idle_label),
+            (&self.dim_after_select, &self.dim_after_label),
+            (&self.dim_strength_select, &self.dim_strength_label),
+            (&self.orbit_radius_select, &self.orbit_radius_label),
+            (&self.orbit_delay_select, &self.orbit_delay_label),
+        ];
+        for (number_select, label) in number_selects.iter() {
+            if let Some(edit) = dialog::number_select_edit(number_select) {
+                dialog::set_accessible_name(edit, &label.text());
+            }
+        }
+
+        for (_, label, input) in self.hotkey_inputs.iter() {
+            if let Some(hwnd) = input.handle.hwnd() {
+                dialog::set_accessible_name(hwnd, &label.text());
+            }
+        }
+
+        let text_inputs = [
+            (&self.schedule_start_input, &self.schedule_start_label),
+            (&self.schedule_end_input, &self.schedule_end_label),
+            (&self.settings_path_input, &self.settings_path_label),
+        ];
+        for (input, label) in text_inputs.iter() {
+            if let Some(hwnd) = input.handle.hwnd() {
+                dialog::set_accessible_name(hwnd, &label.text());
+            }
+        }
+
+        if let Some(hwnd) = self.rules_box.handle.hwnd() {
+            dialog::set_accessible_name(hwnd, &self.rules_label.text());
+        }
+
+        if let Some(hwnd) = self.taskbar_mode_combo.handle.hwnd() {
+            dialog::set_accessible_name(hwnd, &self.taskbar_mode_label.text());
+        }
+    }
+
+    /// Fills the controls with the values of the draft
+    fn load_draft(&self) {
+        let draft = self.draft.borrow();
+        let draft = match draft.as_ref() {
+            Some(draft) => draft,
+            None => return,
+        };
+
+        self.enabled_check.set_check_state(
+            if draft.get_running() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+
+        self.delay_select.set_data(NumberSelectData::Int {
+            value: draft.get_delay().as_secs() as i64,
+            step: 1, // 1 second steps
+            max: MAX_DELAY.as_secs() as i64,
+            min: LOWEST_DELAY.as_secs() as i64,
+        });
+
+        let (smallest_x, smallest_y) = mover::get_smallest_screen_size().unwrap_or((400, 400));
+        let (max_x, max_y) = draft.get_max_distance();
+
+        self.distance_x_select.set_data(NumberSelectData::Int {
+            value: max_x as i64,
+            step: 1,
+            max: smallest_x as i64 / 4,
+            min: LOWEST_MAX_DISTANCE as i64,
+        });
+        self.distance_y_select.set_data(NumberSelectData::Int {
+            value: max_y as i64,
+            step: 1,
+            max: smallest_y as i64 / 4,
+            min: LOWEST_MAX_DISTANCE as i64,
+        });
+
+        self.glide_select.set_data(NumberSelectData::Int {
+            value: draft.get_glide_duration().as_millis() as i64,
+            step: 50,
+            max: MAX_GLIDE_MILLISECONDS as i64,
+            min: 0,
+        });
+
+        self.desktop_check.set_check_state(
+            if draft.is_desktop_shift() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+        self.nudge_check.set_check_state(
+            if draft.is_nudge_maximized() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+        self.snap_groups_check.set_check_state(
+            if draft.is_move_snap_groups() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+
+        self.schedule_check.set_check_state(
+            if draft.is_schedule() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+        let (start, end) = draft.get_schedule_times();
+        self.schedule_start_input.set_text(&start);
+        self.schedule_end_input.set_text(&end);
+
+        self.rules_box.set_text(&rules_text(&draft.get_nudge_processes()));
+
+        self.micro_check.set_check_state(
+            if draft.is_micro_shift() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+        self.micro_delay_select.set_data(NumberSelectData::Int {
+            value: draft.get_micro_delay().as_millis() as i64,
+            step: 100,
+            max: MAX_DELAY.as_millis() as i64,
+            min: MICRO_LOWEST_DELAY.as_millis() as i64,
+        });
+        self.micro_distance_select.set_data(NumberSelectData::Int {
+            value: draft.get_micro_distance() as i64,
+            step: 1,
+            max: MICRO_MAX_DISTANCE as i64,
+            min: LOWEST_MAX_DISTANCE as i64,
+        });
+
+        let mode = draft.get_taskbar_mode();
+        self.taskbar_mode_combo.set_selection(TaskbarMode::ALL.iter().position(|m| *m == mode));
+        self.taskbar_idle_select.set_data(NumberSelectData::Int {
+            value: (draft.get_taskbar_idle().as_secs() / 60) as i64,
+            step: 1,
+            max: taskbar::MAX_IDLE_MINUTES as i64,
+            min: taskbar::LOWEST_IDLE_MINUTES as i64,
+        });
+
+        self.dim_check.set_check_state(
+            if draft.is_dim_static() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+        self.dim_after_select.set_data(NumberSelectData::Int {
+            value: (draft.get_dim_static_after().as_secs() / 60) as i64,
+            step: 1,
+            max: dimmer::MAX_STATIC_MINUTES as i64,
+            min: dimmer::LOWEST_STATIC_MINUTES as i64,
+        });
+        self.dim_strength_select.set_data(NumberSelectData::Int {
+            value: draft.get_dim_strength() as i64,
+            step: 5,
+            max: dimmer::MAX_STRENGTH as i64,
+            min: dimmer::LOWEST_STRENGTH as i64,
+        });
+
+        self.orbit_check.set_check_state(
+            if draft.is_screen_orbit() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+        self.orbit_radius_select.set_data(NumberSelectData::Int {
+            value: draft.get_screen_orbit_radius() as i64,
+            step: 1,
+            max: orbit::MAX_ORBIT_RADIUS as i64,
+            min: LOWEST_MAX_DISTANCE as i64,
+        });
+        self.orbit_delay_select.set_data(NumberSelectData::Int {
+            value: draft.get_screen_orbit_delay().as_secs() as i64,
+            step: 1,
+            max: MAX_DELAY.as_secs() as i64,
+            min: LOWEST_DELAY.as_secs() as i64,
+        });
+
+        self.coherent_check.set_check_state(
+            if draft.is_coherent_shift() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+
+        self.heatmap_check.set_check_state(
+            if draft.is_record_heatmap() { CheckBoxState::Checked } else { CheckBoxState::Unchecked }
+        );
+
+        let known_monitors = draft.get_all_monitors();
+        for (device_id, check) in self.monitor_checks.iter() {
+            // Monitors missing from the settings file are enabled by default
+            let enabled = known_monitors.get(device_id).cloned().unwrap_or(true);
+            check.set_check_state(if enabled { CheckBoxState::Checked } else { CheckBoxState::Unchecked });
+        }
+
+        for (action, _, input) in self.hotkey_inputs.iter() {
+            input.set_text(&draft.get_hotkey(*action).unwrap_or_default());
+        }
+    }
+
+    /// Returns the draft with the values of the controls applied to it
+    fn read_draft(&self) -> Option<Settings> {
+        let mut draft = self.draft.borrow().clone()?;
+
+        draft.set_running(self.enabled_check.check_state() == CheckBoxState::Checked);
+
+        // An empty or garbled number keeps the previous value, the user would notice it in the tray anyway
+        if let Ok(seconds) = self.delay_select.data().formatted_value().parse::<u64>() {
+            draft.set_delay(Duration::from_secs(seconds));
+        }
+
+        let (mut max_x, mut max_y) = draft.get_max_distance();
+        if let Ok(value) = self.distance_x_select.data().formatted_value().parse::<i32>() {
+            max_x = value;
+        }
+        if let Ok(value) = self.distance_y_select.data().formatted_value().parse::<i32>() {
+            max_y = value;
+        }
+        draft.set_max_distance(max_x, max_y);
+
+        if let Ok(milliseconds) = self.glide_select.data().formatted_value().parse::<u64>() {
+            draft.set_glide_duration(Duration::from_millis(milliseconds));
+        }
+
+        draft.set_desktop_shift(self.desktop_check.check_state() == CheckBoxState::Checked);
+        draft.set_nudge_maximized(self.nudge_check.check_state() == CheckBoxState::Checked);
+        draft.set_move_snap_groups(self.snap_groups_check.check_state() == CheckBoxState::Checked);
+
+        draft.set_schedule(self.schedule_check.check_state() == CheckBoxState::Checked);
+        draft.set_schedule_times(&self.schedule_start_input.text(), &self.schedule_end_input.text());
+
+        // Garbled rules keep the previous ones, choose() points them out before they could get here
+        if let Ok(processes) = parse_rules(&self.rules_box.text()) {
+            draft.set_nudge_processes(processes);
+        }
+
+        draft.set_micro_shift(self.micro_check.check_state() == CheckBoxState::Checked);
+        if let Ok(milliseconds) = self.micro_delay_select.data().formatted_value().parse::<u64>() {
+            draft.set_micro_delay(Duration::from_millis(milliseconds));
+        }
+        if let Ok(distance) = self.micro_distance_select.data().formatted_value().parse::<i32>() {
+            draft.set_micro_distance(distance);
+        }
+
+        if let Some(index) = self.taskbar_mode_combo.selection() {
+            draft.set_taskbar_mode(TaskbarMode::ALL[index]);
+        }
+        if let Ok(minutes) = self.taskbar_idle_select.data().formatted_value().parse::<u64>() {
+            draft.set_taskbar_idle(Duration::from_secs(minutes * 60));
+        }
+
+        draft.set_dim_static(self.dim_check.check_state() == CheckBoxState::Checked);
+        if let Ok(minutes) = self.dim_after_select.data().formatted_value().parse::<u64>() {
+            draft.set_dim_static_after(Duration::from_secs(minutes * 60));
+        }
+        if let Ok(strength) = self.dim_strength_select.data().formatted_value().parse::<u32>() {
+            draft.set_dim_strength(strength);
+        }
+
+        draft.set_screen_orbit(self.orbit_check.check_state() == CheckBoxState::Checked);
+        if let Ok(radius) = self.orbit_radius_select.data().formatted_value().parse::<i32>() {
+            draft.set_screen_orbit_radius(radius);
+        }
+        if let Ok(seconds) = self.orbit_delay_select.data().formatted_value().parse::<u64>() {
+            draft.set_screen_orbit_delay(Duration::from_secs(seconds));
+        }
+
+        draft.set_record_heatmap(self.heatmap_check.check_state() == CheckBoxState::Checked);
+        draft.set_coherent_shift(self.coherent_check.check_state() == CheckBoxState::Checked);
+
+        for (device_id, check) in self.monitor_checks.iter() {
+            draft.set_monitor_state(device_id, check.check_state() == CheckBoxState::Checked);
+        }
+
+        for (action, _, input) in self.hotkey_inputs.iter() {
+            let binding = input.text().trim().to_string();
+            draft.set_hotkey(*action, if binding.is_empty() { None } else { Some(binding) });
+        }
+
+        return Some(draft);
+    }
+
+    fn choose(&self, btn: &ControlHandle) {
+        if btn == &self.ok_button {
+            // A rule can't be fixed the way a number is, so it's left to the user to fix it
+            if let Err(line) = parse_rules(&self.rules_box.text()) {
+                nwg::modal_error_message(&self.window, tr(Text::InvalidSettingsTitle), &tr_args(Text::RuleInvalid, &[&line]));
+                return;
+            }
+
+            let mut draft = match self.read_draft() {
+                Some(draft) => draft,
+                None => return,
+            };
+
+            let errors = draft.validate();
+            if !errors.is_empty() {
+                // Show the fixed values and let the user look them over before applying
+                *self.draft.borrow_mut() = Some(draft);
+                self.load_draft();
+                nwg::modal_error_message(&self.window, tr(Text::InvalidSettingsTitle), &errors.join("\n"));
+                return;
+            }
+
+            let original = self.original.clone().unwrap_or_else(|| draft.clone());
+            *self.data.borrow_mut() = Some(SettingsWindowData::Apply { original, edited: draft });
+        } else if btn == &self.cancel_button {
+            *self.data.borrow_mut() = Some(SettingsWindowData::Cancel);
+        }
+
+        self.window.close();
+    }
+
+    /// Opens the folder with the settings file in Explorer
+    fn open_settings_folder(&self) {
+        if let Some(folder) = settings_path().parent() {
+            if let Err(err) = Command::new("explorer").arg(folder).spawn() {
+                nwg::modal_error_message(&self.window, tr(Text::OpenFolderFailedTitle), &err.to_string());
+            }
+        }
+    }
+
+    fn exit(&self) {
+        nwg::stop_thread_dispatch();
+    }
+}
+
+/// Writes the nudge rules one per line as the Rules tab shows them, sorted so they don't jump around between openings
+fn rules_text(processes: &HashMap<String, bool>) -> String {
+    let mut rules: Vec<(&String, &bool)> = processes.iter().collect();
+    rules.sort_by_key(|(process, _)| process.to_lowercase());
+    return rules.iter()
+        .map(|(process, nudged)| format!("{} = {}", process, if **nudged { "on" } else { "off" }))
+        .collect::<Vec<String>>()
+        .join("\r\n");
+}
+
+/// Reads the rules back from the Rules tab, empty lines are skipped. Returns the first line that isn't a rule as the error.
+fn parse_rules(text: &str) -> Result<HashMap<String, bool>, String> {
+    let mut processes = HashMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let (process, nudged) = match line.split_once('=') {
+            Some((process, nudged)) => (process.trim(), nudged.trim()),
+            None => return Err(line.to_string()),
+        };
+        let nudged = match nudged.to_lowercase().as_str() {
+            "on" => true,
+            "off" => false,
+            _ => return Err(line.to_string()),
+        };
+        if process.is_empty() {
+            return Err(line.to_string());
+        }
+        processes.insert(process.to_string(), nudged);
+    }
+    return Ok(processes);
+}
+
+mod settings_window_ui {
+    use native_windows_gui as nwg;
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use std::ops::Deref;
+    use crate::dialog::{self, DialogKey};
+
+    pub struct SettingsWindowUI {
+        inner: Rc<SettingsWindow>,
+        default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
+    }
+
+    /// The number of rows each tab is laid out in, so the rows keep the same height on every tab
+    const TAB_ROWS: u32 = 8;
+
+    impl NativeUi<SettingsWindowUI> for SettingsWindow {
+        fn build_ui(mut data: SettingsWindow) -> Result<SettingsWindowUI, nwg::NwgError> {
+            // Controls
+            nwg::Window::builder()
+                .size((440, 360))
+                .center(true)
+                .title(tr(Text::SettingsTitle))
+                .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE | nwg::WindowFlags::POPUP)
+                .build(&mut data.window)?;
+
+            dialog::set_window_icons(&data.window, &mut data.icon, &mut data.big_icon)?;
+
+            nwg::TabsContainer::builder()
+                .parent(&data.window)
+                .build(&mut data.tabs)?;
+
+            // Lets the arrow keys switch between the tabs once Tab lands on them
+            if let Some(hwnd) = data.tabs.handle.hwnd() {
+                dialog::add_tab_stop(hwnd);
+            }
+
+            nwg::Button::builder()
+                .text(tr(Text::ButtonOk))
+                .parent(&data.window)
+                .build(&mut data.ok_button)?;
+
+            nwg::Button::builder()
+                .text(tr(Text::ButtonCancel))
+                .parent(&data.window)
+                .build(&mut data.cancel_button)?;
+
+            // General tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabGeneral))
+                .parent(&data.tabs)
+                .build(&mut data.general_tab)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::MenuEnabled))
+                .parent(&data.general_tab)
+                .build(&mut data.enabled_check)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::DelaySecondsLabel))
+                .parent(&data.general_tab)
+                .build(&mut data.delay_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.general_tab)
+                .build(&mut data.delay_select)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::DistanceXLabel))
+                .parent(&data.general_tab)
+                .build(&mut data.distance_x_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.general_tab)
+                .build(&mut data.distance_x_select)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::DistanceYLabel))
+                .parent(&data.general_tab)
+                .build(&mut data.distance_y_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.general_tab)
+                .build(&mut data.distance_y_select)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::GlideLabel))
+                .parent(&data.general_tab)
+                .build(&mut data.glide_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.general_tab)
+                .build(&mut data.glide_select)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::DesktopShiftCheck))
+                .parent(&data.general_tab)
+                .build(&mut data.desktop_check)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::NudgeMaximizedCheck))
+                .parent(&data.general_tab)
+                .build(&mut data.nudge_check)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::SnapGroupsCheck))
+                .parent(&data.general_tab)
+                .build(&mut data.snap_groups_check)?;
+
+            // Schedule tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabSchedule))
+                .parent(&data.tabs)
+                .build(&mut data.schedule_tab)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::ScheduleCheck))
+                .parent(&data.schedule_tab)
+                .build(&mut data.schedule_check)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::ScheduleStartLabel))
+                .parent(&data.schedule_tab)
+                .build(&mut data.schedule_start_label)?;
+
+            nwg::TextInput::builder()
+                .flags(nwg::TextInputFlags::VISIBLE | nwg::TextInputFlags::TAB_STOP)
+                .parent(&data.schedule_tab)
+                .build(&mut data.schedule_start_input)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::ScheduleEndLabel))
+                .parent(&data.schedule_tab)
+                .build(&mut data.schedule_end_label)?;
+
+            nwg::TextInput::builder()
+                .flags(nwg::TextInputFlags::VISIBLE | nwg::TextInputFlags::TAB_STOP)
+                .parent(&data.schedule_tab)
+                .build(&mut data.schedule_end_input)?;
+
+            // Monitors tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabMonitors))
+                .parent(&data.tabs)
+                .build(&mut data.monitors_tab)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::MonitorsLabel))
+                .parent(&data.monitors_tab)
+                .build(&mut data.monitors_label)?;
+
+            for (device_id, friendly_name, _, is_connected) in data.monitors.iter() {
+                let mut flags = nwg::CheckBoxFlags::VISIBLE | nwg::CheckBoxFlags::TAB_STOP;
+                if !*is_connected {
+                    // Offline monitors keep their setting, but it can't be changed until they are back
+                    flags |= nwg::CheckBoxFlags::DISABLED;
+                }
+
+                let mut check = nwg::CheckBox::default();
+                nwg::CheckBox::builder()
+                    .text(&format!("{} ({})", friendly_name, device_id))
+                    .flags(flags)
+                    .parent(&data.monitors_tab)
+                    .build(&mut check)?;
+                data.monitor_checks.push((device_id.clone(), check));
+            }
+
+            // Rules tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabRules))
+                .parent(&data.tabs)
+                .build(&mut data.rules_tab)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::RulesLabel))
+                .parent(&data.rules_tab)
+                .build(&mut data.rules_label)?;
+
+            nwg::TextBox::builder()
+                .flags(nwg::TextBoxFlags::VISIBLE | nwg::TextBoxFlags::TAB_STOP | nwg::TextBoxFlags::VSCROLL | nwg::TextBoxFlags::AUTOVSCROLL)
+                .parent(&data.rules_tab)
+                .build(&mut data.rules_box)?;
+
+            // Hotkeys tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabHotkeys))
+                .parent(&data.tabs)
+                .build(&mut data.hotkeys_tab)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::HotkeysLabel))
+                .parent(&data.hotkeys_tab)
+                .build(&mut data.hotkeys_label)?;
+
+            for action in HotkeyAction::ALL.iter() {
+                let mut label = nwg::Label::default();
+                nwg::Label::builder()
+                    .text(&format!("{}:", action.label()))
+                    .parent(&data.hotkeys_tab)
+                    .build(&mut label)?;
+
+                let mut input = nwg::TextInput::default();
+                nwg::TextInput::builder()
+                    .flags(nwg::TextInputFlags::VISIBLE | nwg::TextInputFlags::TAB_STOP)
+                    .parent(&data.hotkeys_tab)
+                    .build(&mut input)?;
+
+                data.hotkey_inputs.push((*action, label, input));
+            }
+
+            // Protection tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabProtection))
+                .parent(&data.tabs)
+                .build(&mut data.protection_tab)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::TaskbarModeLabel))
+                .parent(&data.protection_tab)
+                .build(&mut data.taskbar_mode_label)?;
+
+            nwg::ComboBox::builder()
+                .collection(TaskbarMode::ALL.iter().map(TaskbarMode::label).collect())
+                .parent(&data.protection_tab)
+                .build(&mut data.taskbar_mode_combo)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::TaskbarIdleLabel))
+                .parent(&data.protection_tab)
+                .build(&mut data.taskbar_idle_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.protection_tab)
+                .build(&mut data.taskbar_idle_select)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::DimStaticCheck))
+                .parent(&data.protection_tab)
+                .build(&mut data.dim_check)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::DimStaticAfterLabel))
+                .parent(&data.protection_tab)
+                .build(&mut data.dim_after_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.protection_tab)
+                .build(&mut data.dim_after_select)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::DimStrengthLabel))
+                .parent(&data.protection_tab)
+                .build(&mut data.dim_strength_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.protection_tab)
+                .build(&mut data.dim_strength_select)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::OrbitCheck))
+                .parent(&data.protection_tab)
+                .build(&mut data.orbit_check)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::OrbitRadiusLabel))
+                .parent(&data.protection_tab)
+                .build(&mut data.orbit_radius_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.protection_tab)
+                .build(&mut data.orbit_radius_select)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::OrbitDelayLabel))
+                .parent(&data.protection_tab)
+                .build(&mut data.orbit_delay_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.protection_tab)
+                .build(&mut data.orbit_delay_select)?;
+
+            // Advanced tab
+            nwg::Tab::builder()
+                .text(tr(Text::TabAdvanced))
+                .parent(&data.tabs)
+                .build(&mut data.advanced_tab)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::SettingsFileLabel))
+                .parent(&data.advanced_tab)
+                .build(&mut data.settings_path_label)?;
+
+            nwg::TextInput::builder()
+                .text(&settings_path().to_string_lossy())
+                .readonly(true)
+                .flags(nwg::TextInputFlags::VISIBLE | nwg::TextInputFlags::TAB_STOP)
+                .parent(&data.advanced_tab)
+                .build(&mut data.settings_path_input)?;
+
+            nwg::Button::builder()
+                .text(tr(Text::OpenFolder))
+                .parent(&data.advanced_tab)
+                .build(&mut data.open_folder_button)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::MicroShiftCheck))
+                .parent(&data.advanced_tab)
+                .build(&mut data.micro_check)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::MicroDelayLabel))
+                .parent(&data.advanced_tab)
+                .build(&mut data.micro_delay_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.advanced_tab)
+                .build(&mut data.micro_delay_select)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::MicroDistanceLabel))
+                .parent(&data.advanced_tab)
+                .build(&mut data.micro_distance_label)?;
+
+            nwg::NumberSelect::builder()
+                .decimals(0)
+                .flags(nwg::NumberSelectFlags::VISIBLE | nwg::NumberSelectFlags::TAB_STOP)
+                .parent(&data.advanced_tab)
+                .build(&mut data.micro_distance_select)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::RecordHeatmapCheck))
+                .parent(&data.advanced_tab)
+                .build(&mut data.heatmap_check)?;
+
+            nwg::CheckBox::builder()
+                .text(tr(Text::CoherentShiftCheck))
+                .parent(&data.advanced_tab)
+                .build(&mut data.coherent_check)?;
+
+            // Layouts
+            let window_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.window)
+                .spacing(2)
+                .max_row(Some(8))
+                .child_item(nwg::GridLayoutItem::new(&data.tabs, 0, 0, 2, 7))
+                .child(0, 7, &data.ok_button)
+                .child(1, 7, &data.cancel_button)
+                .build(&window_grid)?;
+
+            let general_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.general_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS))
+                .child_item(nwg::GridLayoutItem::new(&data.enabled_check, 0, 0, 2, 1))
+                .child(0, 1, &data.delay_label)
+                .child(1, 1, &data.delay_select)
+                .child(0, 2, &data.distance_x_label)
+                .child(1, 2, &data.distance_x_select)
+                .child(0, 3, &data.distance_y_label)
+                .child(1, 3, &data.distance_y_select)
+                .child(0, 4, &data.glide_label)
+                .child(1, 4, &data.glide_select)
+                .child_item(nwg::GridLayoutItem::new(&data.desktop_check, 0, 5, 2, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.nudge_check, 0, 6, 2, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.snap_groups_check, 0, 7, 2, 1))
+                .build(&general_grid)?;
+
+            let schedule_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.schedule_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS))
+                .child_item(nwg::GridLayoutItem::new(&data.schedule_check, 0, 0, 2, 1))
+                .child(0, 1, &data.schedule_start_label)
+                .child(1, 1, &data.schedule_start_input)
+                .child(0, 2, &data.schedule_end_label)
+                .child(1, 2, &data.schedule_end_input)
+                .build(&schedule_grid)?;
+
+            let monitors_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.monitors_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS.max(data.monitor_checks.len() as u32 + 1)))
+                .child(0, 0, &data.monitors_label)
+                .build(&monitors_grid)?;
+            for (row, (_, check)) in data.monitor_checks.iter().enumerate() {
+                monitors_grid.add_child(0, row as u32 + 1, check);
+            }
+
+            // The rules get all the rows below the label, there may be many of them
+            let rules_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.rules_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS))
+                .child(0, 0, &data.rules_label)
+                .child_item(nwg::GridLayoutItem::new(&data.rules_box, 0, 1, 1, TAB_ROWS - 1))
+                .build(&rules_grid)?;
+
+            let hotkeys_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.hotkeys_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS))
+                .child_item(nwg::GridLayoutItem::new(&data.hotkeys_label, 0, 0, 2, 1))
+                .build(&hotkeys_grid)?;
+            for (row, (_, label, input)) in data.hotkey_inputs.iter().enumerate() {
+                hotkeys_grid.add_child(0, row as u32 + 1, label);
+                hotkeys_grid.add_child(1, row as u32 + 1, input);
+            }
+
+            let protection_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.protection_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS))
+                .child(0, 0, &data.taskbar_mode_label)
+                .child(1, 0, &data.taskbar_mode_combo)
+                .child(0, 1, &data.taskbar_idle_label)
+                .child(1, 1, &data.taskbar_idle_select)
+                .child_item(nwg::GridLayoutItem::new(&data.dim_check, 0, 2, 2, 1))
+                .child(0, 3, &data.dim_after_label)
+                .child(1, 3, &data.dim_after_select)
+                .child(0, 4, &data.dim_strength_label)
+                .child(1, 4, &data.dim_strength_select)
+                .child_item(nwg::GridLayoutItem::new(&data.orbit_check, 0, 5, 2, 1))
+                .child(0, 6, &data.orbit_radius_label)
+                .child(1, 6, &data.orbit_radius_select)
+                .child(0, 7, &data.orbit_delay_label)
+                .child(1, 7, &data.orbit_delay_select)
+                .build(&protection_grid)?;
+
+            let advanced_grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.advanced_tab)
+                .spacing(1)
+                .max_row(Some(TAB_ROWS))
+                .max_column(Some(3))
+                .child(0, 0, &data.settings_path_label)
+                .child_item(nwg::GridLayoutItem::new(&data.settings_path_input, 0, 1, 3, 1))
+                .child(2, 2, &data.open_folder_button)
+                .child_item(nwg::GridLayoutItem::new(&data.micro_check, 0, 3, 3, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.micro_delay_label, 0, 4, 2, 1))
+                .child(2, 4, &data.micro_delay_select)
+                .child_item(nwg::GridLayoutItem::new(&data.micro_distance_label, 0, 5, 2, 1))
+                .child(2, 5, &data.micro_distance_select)
+                .child_item(nwg::GridLayoutItem::new(&data.heatmap_check, 0, 6, 3, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.coherent_check, 0, 7, 3, 1))
+                .build(&advanced_grid)?;
+
+            // Wrap-up
+            let ui = SettingsWindowUI {
+                inner: Rc::new(data),
+                default_handler: Default::default(),
+                raw_handler: Default::default(),
+            };
+
+            use nwg::Event as E;
+
+            // Events
+            let evt_ui = Rc::downgrade(&ui.inner);
+            let handle_events = move |evt, _evt_data, handle: ControlHandle| {
+                if let Some(ui) = evt_ui.upgrade() {
+                    match evt {
+                        E::OnButtonClick => {
+                            if &handle == &ui.ok_button || &handle == &ui.cancel_button {
+                                SettingsWindow::choose(&ui, &handle);
+                            }
+                            else if &handle == &ui.open_folder_button {
+                                SettingsWindow::open_settings_folder(&ui);
+                            }
+                        }
+                        E::OnWindowClose => {
+                            if &handle == &ui.window {
+                                SettingsWindow::exit(&ui);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            };
+
+            ui.default_handler.borrow_mut().push(
+                nwg::full_bind_event_handler(&ui.window.handle, handle_events)
+            );
+
+            // Enter and Escape
+            let key_ui = Rc::downgrade(&ui.inner);
+            let handle_keys = move |key| {
+                if let Some(ui) = key_ui.upgrade() {
+                    match key {
+                        DialogKey::Ok => SettingsWindow::choose(&ui, &ui.ok_button.handle),
+                        DialogKey::Cancel => SettingsWindow::choose(&ui, &ui.cancel_button.handle),
+                    }
+                }
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                dialog::bind_dialog_keys(&ui.window, handle_keys)?
+            );
+
+            return Ok(ui);
+        }
+    }
+
+    impl Drop for SettingsWindowUI {
+        /// To make sure that everything is freed without issues, the default handler must be unbound.
+        fn drop(&mut self) {
+            let mut handlers = self.default_handler.borrow_mut();
+            for handler in handlers.drain(0..) {
+                nwg::unbind_event_handler(&handler);
+            }
+
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
+        }
+    }
+
+    impl Deref for SettingsWindowUI {
+        type Target = SettingsWindow;
+
+        fn deref(&self) -> &SettingsWindow {
+            &self.inner
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+
+use winapi::shared::minwindef::FALSE;
+use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::synchapi::CreateMutexW;
+use winapi::um::winnt::HANDLE;
+
+/// The name of the mutex that marks a running instance.
+/// It's per session, so the Store build and the portable build of the same user share it.
+const MUTEX_NAME: &str = "Local\\OLEDShift-SingleInstance";
+
+/// Holds the instance mutex for as long as it lives, the first instance keeps it until it exits
+pub struct InstanceGuard {
+    handle: HANDLE,
+}
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        if !self.handle.is_null() {
+            unsafe { CloseHandle(self.handle) };
+        }
+    }
+}
+
+/// Tries to become the only running instance.
+/// Returns None if another instance already holds the mutex.
+pub fn acquire() -> Option<InstanceGuard> {
+    let name: Vec<u16> = OsStr::new(MUTEX_NAME).encode_wide().chain(std::iter::once(0)).collect();
+
+    let handle = unsafe { CreateMutexW(ptr::null_mut(), FALSE, name.as_ptr()) };
+    if handle.is_null() {
+        // Something is very wrong, but that's no reason not to run
+        log::error!("Failed to create the instance mutex: {}", std::io::Error::last_os_error());
+        return Some(InstanceGuard { handle });
+    }
+
+    let already_exists = unsafe { GetLastError() } == ERROR_ALREADY_EXISTS;
+    let guard = InstanceGuard { handle };
+
+    if already_exists {
+        return None;
+    }
+
+    return Some(guard);
+}
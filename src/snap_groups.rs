@@ -0,0 +1,140 @@
+// Groups the snapped windows by the edges they share, and shrinks each group as a whole so a shift doesn't pull the layout apart
+
+use crate::easing::{Position, Rect};
+
+/// How much smaller a group gets on each side to make room for the shift, also how far it's shifted at most
+pub const GROUP_MARGIN: i32 = 8;
+
+/// How far apart two edges may be and still count as shared.
+/// The window rects include the invisible resize borders, so snapped neighbours overlap by a few pixels.
+const EDGE_TOLERANCE: i32 = 16;
+
+/// Returns true if the windows are side by side or stacked, sharing an edge
+fn touches(a: &Rect, b: &Rect) -> bool {
+    let overlap_x = a.0 < b.2 && b.0 < a.2;
+    let overlap_y = a.1 < b.3 && b.1 < a.3;
+    let side_by_side = overlap_y && ((a.2 - b.0).abs() <= EDGE_TOLERANCE || (b.2 - a.0).abs() <= EDGE_TOLERANCE);
+    let stacked = overlap_x && ((a.3 - b.1).abs() <= EDGE_TOLERANCE || (b.3 - a.1).abs() <= EDGE_TOLERANCE);
+    return side_by_side || stacked;
+}
+
+/// Splits the windows into groups that share edges, directly or through other windows of the group.
+/// Returns the indices of the windows of each group, a window sharing no edge is a group of its own.
+pub fn groups(rects: &[Rect]) -> Vec<Vec<usize>> {
+    let mut group_of: Vec<Option<usize>> = vec![None; rects.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for start in 0..rects.len() {
+        if group_of[start].is_some() {
+            continue;
+        }
+        let group = groups.len();
+        group_of[start] = Some(group);
+        let mut members = vec![start];
+
+        let mut next = 0;
+        while next < members.len() {
+            let current = members[next];
+            for other in 0..rects.len() {
+                if group_of[other].is_none() && touches(&rects[current], &rects[other]) {
+                    group_of[other] = Some(group);
+                    members.push(other);
+                }
+            }
+            next += 1;
+        }
+
+        members.sort_unstable();
+        groups.push(members);
+    }
+
+    return groups;
+}
+
+/// Returns the smallest rect holding every window
+pub fn bounds(rects: &[Rect]) -> Rect {
+    return rects.iter().fold((i32::MAX, i32::MAX, i32::MIN, i32::MIN), |bounds, rect| {
+        (bounds.0.min(rect.0), bounds.1.min(rect.1), bounds.2.max(rect.2), bounds.3.max(rect.3))
+    });
+}
+
+/// Maps a coordinate from one span onto another, the same coordinate always maps to the same place
+fn scale(value: i32, from_start: i32, from_length: i32, to_start: i32, to_length: i32) -> i32 {
+    return to_start + ((value - from_start) as i64 * to_length as i64 / from_length as i64) as i32;
+}
+
+/// Lays the group out again within its bounds shrunk by the margin on each side and shifted by the offset, which stays within the margin.
+/// Every window keeps its place in the layout, so the edges it shares stay shared, and the group never leaves its bounds.
+/// A group too small to shrink is left as it is.
+pub fn fit(rects: &[Rect], (dx, dy): Position) -> Vec<Rect> {
+    let from = bounds(rects);
+    let (from_width, from_height) = (from.2 - from.0, from.3 - from.1);
+    if rects.is_empty() || from_width <= 4 * GROUP_MARGIN || from_height <= 4 * GROUP_MARGIN {
+        return rects.to_vec();
+    }
+
+    let dx = dx.clamp(-GROUP_MARGIN, GROUP_MARGIN);
+    let dy = dy.clamp(-GROUP_MARGIN, GROUP_MARGIN);
+    let (left, top) = (from.0 + GROUP_MARGIN + dx, from.1 + GROUP_MARGIN + dy);
+    let (width, height) = (from_width - 2 * GROUP_MARGIN, from_height - 2 * GROUP_MARGIN);
+
+    return rects.iter()
+        .map(|rect| (
+            scale(rect.0, from.0, from_width, left, width),
+            scale(rect.1, from.1, from_height, top, height),
+            scale(rect.2, from.0, from_width, left, width),
+            scale(rect.3, from.1, from_height, top, height),
+        ))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_sharing_an_edge_are_grouped() {
+        let rects = [
+            // Snapped to the left and right half, overlapping by their invisible borders
+            (-7, 0, 967, 1087),
+            (953, 0, 1927, 1087),
+            // Stacked under the right half, so grouped through it
+            (960, 1080, 1920, 1400),
+            // Far away from the others
+            (3000, 0, 3500, 500),
+        ];
+        assert_eq!(groups(&rects), vec![vec![0, 1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn windows_touching_only_at_a_corner_stay_apart() {
+        let rects = [
+            (0, 0, 960, 540),
+            (960, 540, 1920, 1080),
+        ];
+        assert_eq!(groups(&rects), vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn fit_keeps_the_shared_edges_when_the_offset_is_clamped() {
+        let rects = [
+            (0, 0, 960, 1080),
+            (960, 0, 1920, 540),
+            (960, 540, 1920, 1080),
+        ];
+        let fitted = fit(&rects, (100, -100));
+
+        // The offset is clamped to the margin, so the group stays within its bounds
+        assert_eq!(bounds(&fitted), (2 * GROUP_MARGIN, 0, 1920, 1080 - 2 * GROUP_MARGIN));
+        assert_eq!(fitted[0].2, fitted[1].0);
+        assert_eq!(fitted[0].2, fitted[2].0);
+        assert_eq!(fitted[1].3, fitted[2].1);
+        assert_eq!(fitted[0].3, fitted[2].3);
+    }
+
+    #[test]
+    fn fit_leaves_a_group_too_small_to_shrink() {
+        let rects = [(0, 0, 4 * GROUP_MARGIN, 500)];
+        assert_eq!(fit(&rects, (GROUP_MARGIN, GROUP_MARGIN)), rects.to_vec());
+    }
+}
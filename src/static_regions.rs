@@ -0,0 +1,318 @@
+// Compares captured frames tile by tile and tracks how long each tile has stayed the same, to decide what gets dimmed
+
+use std::time::Duration;
+
+/// How much the luminance of a tile may change on average, out of 255, before it counts as changed.
+/// Leaves room for the noise of scaling the screen down and for blinking cursors.
+const CHANGE_THRESHOLD: u32 = 3;
+
+/// A frame of the screen, scaled down, as one luminance byte per pixel, row by row
+pub struct Frame<'a> {
+    pub width: usize,
+    pub height: usize,
+    pub luma: &'a [u8],
+}
+
+/// How the static regions are dimmed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DimConfig {
+    /// How long a tile has to stay unchanged before it starts to dim
+    pub static_after: Duration,
+    /// How long it takes a static tile to go from not dimmed to fully dimmed
+    pub fade_in: Duration,
+    /// How dark a fully dimmed tile is, 0 is invisible and 255 is black
+    pub max_alpha: u8,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Tile {
+    /// How long the tile has been unchanged
+    static_for: Duration,
+    /// How far the tile is dimmed, from 0.0 to 1.0
+    dim: f64,
+}
+
+/// Splits the frames into a grid of tiles and tracks how long each tile has stayed unchanged
+pub struct StaticRegions {
+    columns: usize,
+    rows: usize,
+    tiles: Vec<Tile>,
+    /// The last frame, to compare the next one with
+    previous: Option<(usize, usize, Vec<u8>)>,
+}
+
+impl StaticRegions {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        let columns = columns.max(1);
+        let rows = rows.max(1);
+        return StaticRegions {
+            columns,
+            rows,
+            tiles: vec![Tile::default(); columns * rows],
+            previous: None,
+        };
+    }
+
+    pub fn columns(&self) -> usize {
+        return self.columns;
+    }
+
+    pub fn rows(&self) -> usize {
+        return self.rows;
+    }
+
+    /// Returns the pixels of the frame that belong to the tile, as (x range, y range)
+    fn tile_bounds(&self, column: usize, row: usize, width: usize, height: usize) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let x = column * width / self.columns..(column + 1) * width / self.columns;
+        let y = row * height / self.rows..(row + 1) * height / self.rows;
+        return (x, y);
+    }
+
+    /// Returns true if the tile differs between the frames by more than the threshold
+    fn has_changed(&self, column: usize, row: usize, frame: &Frame, previous: &[u8]) -> bool {
+        let (xs, ys) = self.tile_bounds(column, row, frame.width, frame.height);
+        let pixels = (xs.len() * ys.len()) as u32;
+        if pixels == 0 {
+            return false;
+        }
+
+        let mut difference: u32 = 0;
+        for y in ys {
+            let line = y * frame.width;
+            for x in xs.clone() {
+                difference += (frame.luma[line + x] as i32 - previous[line + x] as i32).unsigned_abs();
+            }
+        }
+        return difference > CHANGE_THRESHOLD * pixels;
+    }
+
+    /// Compares the frame with the previous one, the elapsed time is the time between the two.
+    /// A tile that changed is undimmed right away, one that stayed the same for long enough slowly dims.
+    /// The first frame, and a frame of another size, only becomes the reference for the next one.
+    pub fn update(&mut self, frame: &Frame, elapsed: Duration, config: &DimConfig) {
+        if frame.luma.len() < frame.width * frame.height {
+            return;
+        }
+
+        let previous = match self.previous.take() {
+            Some((width, height, luma)) if width == frame.width && height == frame.height => Some(luma),
+            _ => None,
+        };
+
+        if let Some(previous) = previous.as_ref() {
+            for row in 0..self.rows {
+                for column in 0..self.columns {
+                    let changed = self.has_changed(column, row, frame, previous);
+                    let tile = &mut self.tiles[row * self.columns + column];
+                    if changed {
+                        *tile = Tile::default();
+                        continue;
+                    }
+
+                    tile.static_for += elapsed;
+                    if tile.static_for >= config.static_after {
+                        // Only the time past the threshold counts towards the fade
+                        let fading = elapsed.min(tile.static_for - config.static_after);
+                        let step = if config.fade_in.is_zero() { 1.0 } else { fading.as_secs_f64() / config.fade_in.as_secs_f64() };
+                        tile.dim = (tile.dim + step).min(1.0);
+                    }
+                }
+            }
+        } else {
+            self.tiles.iter_mut().for_each(|tile| *tile = Tile::default());
+        }
+
+        self.previous = Some((frame.width, frame.height, frame.luma[..frame.width * frame.height].to_vec()));
+    }
+
+    /// Returns how dark each tile should be, row by row, 0 is not dimmed and max_alpha is fully dimmed
+    pub fn alphas(&self, config: &DimConfig) -> Vec<u8> {
+        return self.tiles.iter()
+            .map(|tile| (tile.dim * config.max_alpha as f64).round() as u8)
+            .collect();
+    }
+
+    /// Takes the dimming back out of a frame that was captured with the overlay showing, the alphas are the ones it showed.
+    /// Dimmed pixels lose some precision, but a tile that didn't change still comes out the same.
+    pub fn undim(&self, luma: &mut [u8], width: usize, height: usize, alphas: &[u8]) {
+        if luma.len() < width * height {
+            return;
+        }
+
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let alpha = alphas.get(row * self.columns + column).copied().unwrap_or(0) as u32;
+                if alpha == 0 || alpha == 255 {
+                    continue;
+                }
+
+                let (xs, ys) = self.tile_bounds(column, row, width, height);
+                for y in ys {
+                    for x in xs.clone() {
+                        let pixel = &mut luma[y * width + x];
+                        *pixel = (*pixel as u32 * 255 / (255 - alpha)).min(255) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forgets everything, the next frame starts over
+    pub fn reset(&mut self) {
+        self.tiles.iter_mut().for_each(|tile| *tile = Tile::default());
+        self.previous = None;
+    }
+}
+
+/// Returns the luminance of a pixel, Rec. 601 weights in integer math
+pub fn luminance(red: u8, green: u8, blue: u8) -> u8 {
+    return ((red as u32 * 299 + green as u32 * 587 + blue as u32 * 114) / 1000) as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: DimConfig = DimConfig {
+        static_after: Duration::from_secs(60),
+        fade_in: Duration::from_secs(20),
+        max_alpha: 200,
+    };
+
+    /// A 4x2 frame split into two tiles side by side, the left one at the left luminance and the right one at the right
+    fn frame(luma: &mut Vec<u8>, left: u8, right: u8) -> Frame<'_> {
+        *luma = [left, left, right, right, left, left, right, right].to_vec();
+        return Frame { width: 4, height: 2, luma };
+    }
+
+    fn update(regions: &mut StaticRegions, left: u8, right: u8, seconds: u64) {
+        let mut luma = Vec::new();
+        regions.update(&frame(&mut luma, left, right), Duration::from_secs(seconds), &CONFIG);
+    }
+
+    #[test]
+    fn first_frame_only_becomes_the_reference() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 600);
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+
+        update(&mut regions, 100, 100, 60);
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+    }
+
+    #[test]
+    fn static_tile_dims_only_after_static_after_and_fades_in() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 0);
+
+        update(&mut regions, 100, 100, 50);
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+
+        // 60 seconds static, the fade starts now
+        update(&mut regions, 100, 100, 10);
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+
+        // A quarter of the fade in
+        update(&mut regions, 100, 100, 5);
+        assert_eq!(regions.alphas(&CONFIG), vec![50, 50]);
+
+        // The fade in is done and stays at max_alpha
+        update(&mut regions, 100, 100, 15);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 200]);
+        update(&mut regions, 100, 100, 30);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 200]);
+    }
+
+    #[test]
+    fn only_the_time_past_static_after_counts_towards_the_fade() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 0);
+        // 70 seconds static in one go, 10 of them past static_after
+        update(&mut regions, 100, 100, 70);
+        assert_eq!(regions.alphas(&CONFIG), vec![100, 100]);
+    }
+
+    #[test]
+    fn changed_tile_is_reset_and_undimmed() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 0);
+        update(&mut regions, 100, 100, 80);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 200]);
+
+        update(&mut regions, 100, 200, 1);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 0]);
+
+        // The changed tile counts its static time from the start again
+        update(&mut regions, 100, 200, 60);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 0]);
+        update(&mut regions, 100, 200, 10);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 100]);
+    }
+
+    #[test]
+    fn change_within_the_threshold_keeps_the_tile_static() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 0);
+        update(&mut regions, 100 + CHANGE_THRESHOLD as u8, 100 + CHANGE_THRESHOLD as u8 + 1, 80);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 0]);
+    }
+
+    #[test]
+    fn frame_size_change_resets_the_state() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 0);
+        update(&mut regions, 100, 100, 80);
+        assert_eq!(regions.alphas(&CONFIG), vec![200, 200]);
+
+        let luma = [100u8; 12];
+        regions.update(&Frame { width: 6, height: 2, luma: &luma }, Duration::from_secs(10), &CONFIG);
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+
+        // The new frame became the reference, the time counts from it
+        regions.update(&Frame { width: 6, height: 2, luma: &luma }, Duration::from_secs(65), &CONFIG);
+        assert_eq!(regions.alphas(&CONFIG), vec![50, 50]);
+    }
+
+    #[test]
+    fn reset_forgets_the_reference() {
+        let mut regions = StaticRegions::new(2, 1);
+        update(&mut regions, 100, 100, 0);
+        update(&mut regions, 100, 100, 80);
+        regions.reset();
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+
+        update(&mut regions, 100, 100, 80);
+        assert_eq!(regions.alphas(&CONFIG), vec![0, 0]);
+    }
+
+    #[test]
+    fn undim_inverts_the_overlay_alpha() {
+        let regions = StaticRegions::new(2, 1);
+        let original: Vec<u8> = vec![200, 100, 200, 100, 40, 255, 40, 255];
+        let alphas = [0u8, 128];
+
+        // What the capture sees through the overlay: black at the alpha of the tile over the pixel
+        let mut luma: Vec<u8> = original.iter().enumerate()
+            .map(|(index, &pixel)| {
+                let alpha = alphas[(index % 4) / 2] as u32;
+                (pixel as u32 * (255 - alpha) / 255) as u8
+            })
+            .collect();
+        regions.undim(&mut luma, 4, 2, &alphas);
+
+        for (undimmed, pixel) in luma.iter().zip(original.iter()) {
+            assert!((*undimmed as i32 - *pixel as i32).abs() <= 2, "{} should be close to {}", undimmed, pixel);
+        }
+        // The tile without an overlay is left exactly as it was
+        assert_eq!(&luma[..2], &original[..2]);
+    }
+
+    #[test]
+    fn luminance_uses_rec_601_weights() {
+        assert_eq!(luminance(0, 0, 0), 0);
+        assert_eq!(luminance(255, 255, 255), 255);
+        assert_eq!(luminance(255, 0, 0), 76);
+        assert_eq!(luminance(0, 255, 0), 149);
+        assert_eq!(luminance(0, 0, 255), 29);
+    }
+}
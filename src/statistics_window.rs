@@ -0,0 +1,242 @@
+use crate::dialog;
+use crate::history::{self, Statistics};
+use crate::i18n::{tr, Text};
+use std::collections::HashMap;
+use std::{thread, cell::RefCell};
+use nwg::NativeUi;
+
+#[derive(Default)]
+pub struct StatisticsWindow {
+    window: nwg::Window,
+    icon: nwg::Icon,
+    big_icon: nwg::Icon,
+    days_label: nwg::Label,
+    days_list: nwg::ListView,
+    skipped_label: nwg::Label,
+    skipped_list: nwg::ListView,
+    displacement_label: nwg::Label,
+    displacement_list: nwg::ListView,
+    close_button: nwg::Button,
+    /// Monitor device id => friendly name, the log only has the ids
+    monitor_names: RefCell<HashMap<String, String>>,
+}
+
+impl StatisticsWindow {
+
+    /// Create the window on a new thread, the log is read there too.
+    /// To alert the main GUI that the window closed, this function takes a notice sender object.
+    pub(crate) fn popup(sender: nwg::NoticeSender, monitor_names: HashMap<String, String>) -> thread::JoinHandle<()> {
+        return thread::spawn(move || {
+            // Create the UI just like in the main function
+            let app = StatisticsWindow::build_ui(Default::default()).expect("Failed to build UI");
+            *app.monitor_names.borrow_mut() = monitor_names;
+
+            app.show(&history::statistics());
+            if let Some(hwnd) = app.close_button.handle.hwnd() {
+                dialog::focus_on_open(&app.window, hwnd);
+            }
+
+            nwg::dispatch_thread_events();
+
+            // Notice the main thread that the window closed
+            sender.notice();
+        })
+    }
+
+    /// Fills the lists, the most recent days first
+    fn show(&self, statistics: &Statistics) {
+        for (date, (passes, moved)) in statistics.days.iter().rev() {
+            self.days_list.insert_items_row(None, &[date.clone(), passes.to_string(), moved.to_string()]);
+        }
+
+        for (reason, count) in statistics.skipped.iter() {
+            self.skipped_list.insert_items_row(None, &[reason.label().to_string(), count.to_string()]);
+        }
+
+        let monitor_names = self.monitor_names.borrow();
+        for (device_id, pixels) in statistics.displacement.iter() {
+            let name = monitor_names.get(device_id).cloned().unwrap_or_else(|| device_id.clone());
+            self.displacement_list.insert_items_row(None, &[name, format!("{:.0}", pixels)]);
+        }
+    }
+
+    fn close(&self) {
+        self.window.close();
+    }
+
+    fn exit(&self) {
+        nwg::stop_thread_dispatch();
+    }
+}
+
+mod statistics_window_ui {
+    use native_windows_gui as nwg;
+    use super::*;
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use std::ops::Deref;
+    use crate::dialog::{self, DialogKey};
+
+    pub struct StatisticsWindowUI {
+        inner: Rc<StatisticsWindow>,
+        default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
+    }
+
+    /// Builds a read-only report list with the given column headers
+    fn build_list(list: &mut nwg::ListView, parent: &nwg::Window, columns: &[(&str, i32)]) -> Result<(), nwg::NwgError> {
+        nwg::ListView::builder()
+            .list_style(nwg::ListViewStyle::Detailed)
+            .flags(nwg::ListViewFlags::VISIBLE | nwg::ListViewFlags::TAB_STOP | nwg::ListViewFlags::SINGLE_SELECTION)
+            .ex_flags(nwg::ListViewExFlags::GRID | nwg::ListViewExFlags::FULL_ROW_SELECT)
+            .parent(parent)
+            .build(list)?;
+
+        list.set_headers_enabled(true);
+        for (text, width) in columns.iter() {
+            list.insert_column(nwg::InsertListViewColumn {
+                index: None,
+                fmt: None,
+                width: Some(*width),
+                text: Some(text.to_string()),
+            });
+        }
+
+        return Ok(());
+    }
+
+    impl NativeUi<StatisticsWindowUI> for StatisticsWindow {
+        fn build_ui(mut data: StatisticsWindow) -> Result<StatisticsWindowUI, nwg::NwgError> {
+            // Controls
+            nwg::Window::builder()
+                .size((440, 480))
+                .center(true)
+                .title(tr(Text::StatisticsTitle))
+                .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE | nwg::WindowFlags::POPUP)
+                .build(&mut data.window)?;
+
+            dialog::set_window_icons(&data.window, &mut data.icon, &mut data.big_icon)?;
+
+            nwg::Label::builder()
+                .text(tr(Text::StatisticsDaysLabel))
+                .parent(&data.window)
+                .build(&mut data.days_label)?;
+
+            build_list(&mut data.days_list, &data.window, &[
+                (tr(Text::ColumnDate), 140),
+                (tr(Text::ColumnPasses), 110),
+                (tr(Text::ColumnWindowsMoved), 140),
+            ])?;
+
+            nwg::Label::builder()
+                .text(tr(Text::StatisticsSkippedLabel))
+                .parent(&data.window)
+                .build(&mut data.skipped_label)?;
+
+            build_list(&mut data.skipped_list, &data.window, &[
+                (tr(Text::ColumnReason), 280),
+                (tr(Text::ColumnWindows), 110),
+            ])?;
+
+            nwg::Label::builder()
+                .text(tr(Text::StatisticsDisplacementLabel))
+                .parent(&data.window)
+                .build(&mut data.displacement_label)?;
+
+            build_list(&mut data.displacement_list, &data.window, &[
+                (tr(Text::ColumnMonitor), 280),
+                (tr(Text::ColumnPixels), 110),
+            ])?;
+
+            nwg::Button::builder()
+                .text(tr(Text::ButtonClose))
+                .parent(&data.window)
+                .build(&mut data.close_button)?;
+
+            let grid = nwg::GridLayout::default();
+            nwg::GridLayout::builder()
+                .parent(&data.window)
+                .spacing(2)
+                .max_row(Some(12))
+                .max_column(Some(3))
+                .child_item(nwg::GridLayoutItem::new(&data.days_label, 0, 0, 3, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.days_list, 0, 1, 3, 3))
+                .child_item(nwg::GridLayoutItem::new(&data.skipped_label, 0, 4, 3, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.skipped_list, 0, 5, 3, 3))
+                .child_item(nwg::GridLayoutItem::new(&data.displacement_label, 0, 8, 3, 1))
+                .child_item(nwg::GridLayoutItem::new(&data.displacement_list, 0, 9, 3, 2))
+                .child(2, 11, &data.close_button)
+                .build(&grid)?;
+
+            // Wrap-up
+            let ui = StatisticsWindowUI {
+                inner: Rc::new(data),
+                default_handler: Default::default(),
+                raw_handler: Default::default(),
+            };
+
+            use nwg::Event as E;
+
+            // Events
+            let evt_ui = Rc::downgrade(&ui.inner);
+            let handle_events = move |evt, _evt_data, handle| {
+                if let Some(ui) = evt_ui.upgrade() {
+                    match evt {
+                        E::OnButtonClick => {
+                            if &handle == &ui.close_button {
+                                StatisticsWindow::close(&ui);
+                            }
+                        }
+                        E::OnWindowClose => {
+                            if &handle == &ui.window {
+                                StatisticsWindow::exit(&ui);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            };
+
+            ui.default_handler.borrow_mut().push(
+                nwg::full_bind_event_handler(&ui.window.handle, handle_events)
+            );
+
+            // Enter and Escape both close the window, there is nothing to confirm
+            let key_ui = Rc::downgrade(&ui.inner);
+            let handle_keys = move |_key: DialogKey| {
+                if let Some(ui) = key_ui.upgrade() {
+                    StatisticsWindow::close(&ui);
+                }
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                dialog::bind_dialog_keys(&ui.window, handle_keys)?
+            );
+
+            return Ok(ui);
+        }
+    }
+
+    impl Drop for StatisticsWindowUI {
+        /// To make sure that everything is freed without issues, the default handler must be unbound.
+        fn drop(&mut self) {
+            let mut handlers = self.default_handler.borrow_mut();
+            for handler in handlers.drain(0..) {
+                nwg::unbind_event_handler(&handler);
+            }
+
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
+        }
+    }
+
+    impl Deref for StatisticsWindowUI {
+        type Target = StatisticsWindow;
+
+        fn deref(&self) -> &StatisticsWindow {
+            &self.inner
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::{mem, ptr};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use winapi::shared::minwindef::LPARAM;
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::shellapi::{ABM_GETSTATE, ABM_SETSTATE, ABS_AUTOHIDE, APPBARDATA, SHAppBarMessage};
+use winapi::um::sysinfoapi::GetTickCount;
+use winapi::um::winuser::{
+    FindWindowExW,
+    GetLastInputInfo,
+    GetWindowRect,
+    IsWindow,
+    ShowWindow,
+    LASTINPUTINFO,
+    SW_HIDE,
+    SW_SHOWNA,
+};
+
+use crate::i18n::{tr, Text};
+use crate::overlay::{self, Overlay};
+
+/// How the taskbar is protected once the user has been idle long enough
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskbarMode {
+    #[default]
+    Off,
+    /// Turns on auto-hide, so the taskbar slides away
+    AutoHide,
+    /// Hides the taskbar windows altogether
+    Hide,
+    /// Darkens the taskbar with an overlay
+    Dim,
+}
+
+impl TaskbarMode {
+    pub const ALL: [TaskbarMode; 4] = [TaskbarMode::Off, TaskbarMode::AutoHide, TaskbarMode::Hide, TaskbarMode::Dim];
+
+    /// The mode as shown to the user
+    pub fn label(&self) -> &'static str {
+        return match self {
+            TaskbarMode::Off => tr(Text::TaskbarModeOff),
+            TaskbarMode::AutoHide => tr(Text::TaskbarModeAutoHide),
+            TaskbarMode::Hide => tr(Text::TaskbarModeHide),
+            TaskbarMode::Dim => tr(Text::TaskbarModeDim),
+        };
+    }
+}
+
+/// Lowest idle time allowed before the taskbar is protected, in minutes
+pub const LOWEST_IDLE_MINUTES: u32 = 1;
+
+/// Highest idle time allowed before the taskbar is protected, in minutes (4 hours)
+pub const MAX_IDLE_MINUTES: u32 = 4 * 60;
+
+/// How often the idle time is checked, also how quickly the taskbar comes back once the user is
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How dark the dimming overlay is, out of 255
+const DIM_ALPHA: u8 = 180;
+
+/// What was done to the taskbar, to be undone once the user is back
+enum Applied {
+    /// The AppBar state from before auto-hide was turned on
+    AutoHide(u32),
+    /// The taskbar windows that were hidden
+    Hidden(Vec<isize>),
+    Dimmed(Vec<Overlay>),
+}
+
+lazy_static! {
+    /// The mode and how long the user has to be idle, synced from the settings by the controller
+    static ref CONFIG: Mutex<(TaskbarMode, Duration)> = Mutex::new((TaskbarMode::Off, Duration::from_secs(5 * 60)));
+
+    static ref THREAD: Mutex<Option<thread::JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Set when the app exits, the taskbar thread restores the taskbar and stops
+static CLOSED: AtomicBool = AtomicBool::new(false);
+
+fn wide(text: &str) -> Vec<u16> {
+    return OsStr::new(text).encode_wide().chain(std::iter::once(0)).collect();
+}
+
+/// Sets the mode and how long the user has to be idle before the taskbar is protected
+pub fn configure(mode: TaskbarMode, idle: Duration) {
+    *CONFIG.lock().unwrap() = (mode, idle);
+}
+
+/// Starts watching the idle time on a thread of its own, the overlays live on that thread too
+pub fn start() {
+    let spawned = thread::Builder::new().name("taskbar_thread".to_string()).spawn(run);
+    match spawned {
+        Ok(handle) => *THREAD.lock().unwrap() = Some(handle),
+        Err(err) => log::error!("Failed to start the taskbar thread: {}", err),
+    }
+}
+
+/// Restores the taskbar and stops watching, for when the app exits
+pub fn close() {
+    CLOSED.store(true, Ordering::Relaxed);
+    if let Some(handle) = THREAD.lock().unwrap().take() {
+        let _ = handle.join();
+    }
+}
+
+/// Returns how long it has been since the last keyboard or mouse input
+pub fn idle_time() -> Duration {
+    let mut info: LASTINPUTINFO = unsafe { mem::zeroed() };
+    info.cbSize = mem::size_of::<LASTINPUTINFO>() as u32;
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return Duration::ZERO;
+    }
+    // Both wrap around after 49.7 days, the difference is still right
+    let idle_milliseconds = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+    return Duration::from_millis(idle_milliseconds as u64);
+}
+
+/// Returns the main taskbar and the ones on the other monitors
+fn taskbar_windows() -> Vec<HWND> {
+    let mut windows = Vec::new();
+    for class in ["Shell_TrayWnd", "Shell_SecondaryTrayWnd"].iter() {
+        let class = wide(class);
+        let mut hwnd: HWND = ptr::null_mut();
+        loop {
+            hwnd = unsafe { FindWindowExW(ptr::null_mut(), hwnd, class.as_ptr(), ptr::null()) };
+            if hwnd.is_null() {
+                break;
+            }
+            windows.push(hwnd);
+        }
+    }
+    return windows;
+}
+
+fn app_bar_data() -> APPBARDATA {
+    let mut data: APPBARDATA = unsafe { mem::zeroed() };
+    data.cbSize = mem::size_of::<APPBARDATA>() as u32;
+    data.hWnd = taskbar_windows().first().copied().unwrap_or(ptr::null_mut());
+    return data;
+}
+
+fn set_app_bar_state(state: u32) {
+    let mut data = app_bar_data();
+    data.lParam = state as LPARAM;
+    unsafe { SHAppBarMessage(ABM_SETSTATE, &mut data) };
+}
+
+fn apply(mode: TaskbarMode) -> Option<Applied> {
+    log::info!("The user is idle, protecting the taskbar: {:?}", mode);
+
+    return match mode {
+        TaskbarMode::Off => None,
+        TaskbarMode::AutoHide => {
+            let original = unsafe { SHAppBarMessage(ABM_GETSTATE, &mut app_bar_data()) } as u32;
+            set_app_bar_state(original | ABS_AUTOHIDE);
+            Some(Applied::AutoHide(original))
+        }
+        TaskbarMode::Hide => {
+            let hidden = taskbar_windows().into_iter()
+                .filter(|&hwnd| unsafe { ShowWindow(hwnd, SW_HIDE) } != 0) // Non-zero if it was visible
+                .map(|hwnd| hwnd as isize)
+                .collect();
+            Some(Applied::Hidden(hidden))
+        }
+        TaskbarMode::Dim => {
+            let overlays = taskbar_windows().into_iter()
+                .filter_map(|hwnd| {
+                    let mut rect: RECT = unsafe { mem::zeroed() };
+                    if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+                        return None;
+                    }
+                    Overlay::new(rect, DIM_ALPHA)
+                })
+                .collect();
+            Some(Applied::Dimmed(overlays))
+        }
+    };
+}
+
+fn undo(applied: Applied) {
+    log::info!("Restoring the taskbar");
+
+    match applied {
+        Applied::AutoHide(original) => set_app_bar_state(original),
+        Applied::Hidden(hidden) => {
+            for hwnd in hidden {
+                // Explorer may have been restarted in the meantime, the new taskbar is visible anyway
+                if unsafe { IsWindow(hwnd as HWND) } != 0 {
+                    unsafe { ShowWindow(hwnd as HWND, SW_SHOWNA) };
+                }
+            }
+        }
+        Applied::Dimmed(overlays) => drop(overlays),
+    }
+}
+
+/// Protects the taskbar while the user is idle and restores it as soon as they are back or the mode changes
+fn run() {
+    let mut applied: Option<(TaskbarMode, Applied)> = None;
+
+    loop {
+        overlay::pump_messages();
+
+        if CLOSED.load(Ordering::Relaxed) {
+            if let Some((_, applied)) = applied.take() {
+                undo(applied);
+            }
+            return;
+        }
+
+        let (mode, idle_after) = *CONFIG.lock().unwrap();
+        let idle = mode != TaskbarMode::Off && idle_time() >= idle_after;
+
+        match applied.as_ref() {
+            Some((applied_mode, _)) if !idle || *applied_mode != mode => {
+                if let Some((_, applied)) = applied.take() {
+                    undo(applied);
+                }
+            }
+            Some((_, Applied::Dimmed(overlays))) => {
+                for overlay in overlays.iter() {
+                    overlay.raise();
+                }
+            }
+            Some(_) => {}
+            None if idle => applied = apply(mode).map(|done| (mode, done)),
+            None => {}
+        }
+
+        thread::sleep(CHECK_INTERVAL);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(windows)]
+use std::ffi::OsStr;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::{mem, ptr};
+
+#[cfg(windows)]
+use winapi::shared::minwindef::DWORD;
+#[cfg(windows)]
+use winapi::shared::winerror::ERROR_SUCCESS;
+#[cfg(windows)]
+use winapi::um::winreg::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+static RUNNING_DARK_ICON: &[u8] = include_bytes!("../icons/running-dark.ico");
+static RUNNING_LIGHT_ICON: &[u8] = include_bytes!("../icons/running-light.ico");
+static PAUSED_DARK_ICON: &[u8] = include_bytes!("../icons/paused-dark.ico");
+static PAUSED_LIGHT_ICON: &[u8] = include_bytes!("../icons/paused-light.ico");
+static DISABLED_DARK_ICON: &[u8] = include_bytes!("../icons/disabled-dark.ico");
+static DISABLED_LIGHT_ICON: &[u8] = include_bytes!("../icons/disabled-light.ico");
+static ERROR_DARK_ICON: &[u8] = include_bytes!("../icons/error-dark.ico");
+static ERROR_LIGHT_ICON: &[u8] = include_bytes!("../icons/error-light.ico");
+
+/// What the tray icon shows at a glance
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayState {
+    /// The windows are being moved
+    Running,
+    /// Disabled by the user, or paused for a fixed duration
+    Paused,
+    /// Enabled, but there is nothing to move, e.g. every connected screen is turned off in the Screens menu
+    Disabled,
+    /// The settings file couldn't be read or written
+    Error,
+}
+
+impl TrayState {
+    pub const ALL: [TrayState; 4] = [TrayState::Running, TrayState::Paused, TrayState::Disabled, TrayState::Error];
+}
+
+/// The color of the taskbar the icon is shown on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskbarTheme {
+    Dark,
+    Light,
+}
+
+impl TaskbarTheme {
+    pub const ALL: [TaskbarTheme; 2] = [TaskbarTheme::Dark, TaskbarTheme::Light];
+}
+
+/// Returns the icon for the state, drawn to stand out on the taskbar: the swirl gets a light rim on a dark taskbar
+/// and a dark one on a light taskbar, and so do the badges.
+pub fn icon_for(state: TrayState, theme: TaskbarTheme) -> &'static [u8] {
+    return match (state, theme) {
+        (TrayState::Running, TaskbarTheme::Dark) => RUNNING_DARK_ICON,
+        (TrayState::Running, TaskbarTheme::Light) => RUNNING_LIGHT_ICON,
+        (TrayState::Paused, TaskbarTheme::Dark) => PAUSED_DARK_ICON,
+        (TrayState::Paused, TaskbarTheme::Light) => PAUSED_LIGHT_ICON,
+        (TrayState::Disabled, TaskbarTheme::Dark) => DISABLED_DARK_ICON,
+        (TrayState::Disabled, TaskbarTheme::Light) => DISABLED_LIGHT_ICON,
+        (TrayState::Error, TaskbarTheme::Dark) => ERROR_DARK_ICON,
+        (TrayState::Error, TaskbarTheme::Light) => ERROR_LIGHT_ICON,
+    };
+}
+
+/// Reads the taskbar theme from the personalization settings, the taskbar is dark unless the system theme is light
+#[cfg(windows)]
+pub fn taskbar_theme() -> TaskbarTheme {
+    let sub_key: Vec<u16> = OsStr::new(r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize")
+        .encode_wide().chain(std::iter::once(0)).collect();
+    let value: Vec<u16> = OsStr::new("SystemUsesLightTheme").encode_wide().chain(std::iter::once(0)).collect();
+
+    let mut data: DWORD = 0;
+    let mut data_size = mem::size_of::<DWORD>() as DWORD;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            sub_key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            ptr::null_mut(),
+            &mut data as *mut _ as *mut _,
+            &mut data_size,
+        )
+    };
+
+    // Windows 10 before 1903 doesn't have the value, its taskbar is always dark
+    if status != ERROR_SUCCESS as i32 || data == 0 {
+        return TaskbarTheme::Dark;
+    }
+    return TaskbarTheme::Light;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first bytes of every .ico file: reserved, then type 1 for icons
+    const ICO_HEADER: [u8; 4] = [0, 0, 1, 0];
+
+    #[test]
+    fn every_state_has_an_icon_for_every_theme() {
+        for state in TrayState::ALL {
+            for theme in TaskbarTheme::ALL {
+                let icon = icon_for(state, theme);
+                assert_eq!(&icon[..4], &ICO_HEADER, "{:?} on {:?} isn't an icon", state, theme);
+            }
+        }
+    }
+
+    #[test]
+    fn every_pair_has_an_icon_of_its_own() {
+        let icons: Vec<&[u8]> = TrayState::ALL.iter()
+            .flat_map(|state| TaskbarTheme::ALL.iter().map(move |theme| icon_for(*state, *theme)))
+            .collect();
+        for (index, icon) in icons.iter().enumerate() {
+            assert!(icons[index + 1..].iter().all(|other| other != icon));
+        }
+    }
+}
@@ -1,10 +1,31 @@
-use crate::controller::{Controller, Delays, Distances};
+use crate::controller::{Controller, Delays, Distances, Pauses};
 use crate::delay_dialog::{DelayDialog, DelayDialogData};
+use crate::burn_in;
+use crate::desktop;
+use crate::dimmer;
+use crate::diagnostics_window::DiagnosticsWindow;
 use crate::distance_dialog::{DistanceDialog, DistanceDialogData};
+use crate::heatmap_window::HeatmapWindow;
+use crate::hotkeys::{self, Hotkey, HotkeyAction};
+use crate::i18n::{self, tr, tr_args, Text, Unit};
+use crate::ipc;
+use crate::logging;
+use crate::maximized;
+use crate::orbit;
+use crate::settings_window::{SettingsWindow, SettingsWindowData};
+use crate::statistics_window::StatisticsWindow;
+use crate::taskbar;
+use crate::tray_icons::{self, TaskbarTheme, TrayState};
 use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::process::Command;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use std::{cell::RefCell, thread};
+use std::cell::{Cell, RefCell};
+use std::{mem, thread};
+use winapi::shared::minwindef::{FALSE, UINT};
+use winapi::um::winuser::{SetMenuItemInfoW, MENUITEMINFOW, MIIM_STRING};
 
 pub static ICON: &[u8] = include_bytes!("../icon.ico");
 
@@ -15,6 +36,17 @@ pub struct SystemTray {
     tray: nwg::TrayNotification,
     tray_menu: nwg::Menu,
     enabled_toggle: nwg::MenuItem,
+    shift_now_menu: nwg::MenuItem,
+    undo_menu: nwg::MenuItem,
+    restore_menu: nwg::MenuItem,
+    next_profile_menu: nwg::MenuItem,
+    pause_menu: nwg::Menu,
+    pause_15_menu: nwg::MenuItem,
+    pause_60_menu: nwg::MenuItem,
+    pause_tomorrow_menu: nwg::MenuItem,
+    status_timer: nwg::AnimationTimer,
+    state_icon: RefCell<nwg::Icon>,
+    state_icon_key: Cell<Option<(TrayState, TaskbarTheme)>>,
     delay_menu: nwg::Menu,
     delay_30_menu: nwg::MenuItem,
     delay_1_menu: nwg::MenuItem,
@@ -28,6 +60,11 @@ pub struct SystemTray {
     distance_custom_menu: nwg::MenuItem,
     screen_menu: nwg::Menu,
     screens_map: RefCell<HashMap<String, nwg::MenuItem>>,
+    settings_menu: nwg::MenuItem,
+    statistics_menu: nwg::MenuItem,
+    diagnostics_menu: nwg::MenuItem,
+    heatmap_menu: nwg::MenuItem,
+    log_folder_menu: nwg::MenuItem,
     exit_menu: nwg::MenuItem,
     separator_delay: nwg::MenuSeparator,
     separator_distance: nwg::MenuSeparator,
@@ -36,6 +73,15 @@ pub struct SystemTray {
     delay_dialog_notice: nwg::Notice,
     distance_dialog_data: RefCell<Option<thread::JoinHandle<DistanceDialogData>>>,
     distance_dialog_notice: nwg::Notice,
+    settings_window_data: RefCell<Option<thread::JoinHandle<SettingsWindowData>>>,
+    settings_window_notice: nwg::Notice,
+    statistics_window_data: RefCell<Option<thread::JoinHandle<()>>>,
+    statistics_window_notice: nwg::Notice,
+    diagnostics_window_data: RefCell<Option<thread::JoinHandle<()>>>,
+    diagnostics_window_notice: nwg::Notice,
+    heatmap_window_data: RefCell<Option<thread::JoinHandle<()>>>,
+    heatmap_window_notice: nwg::Notice,
+    ipc_notice: nwg::Notice,
 }
 
 impl SystemTray {
@@ -45,25 +91,155 @@ impl SystemTray {
     }
 
     fn toggle_enabled(&self) {
-        println!("Toggling enabled called");
+        log::debug!("Toggling enabled");
         self.controller.lock().unwrap().toggle_running();
         self.update_toggle();
         self.update_tooltip();
     }
 
+    fn do_pause(&self, pause: Pauses) {
+        self.controller.lock().unwrap().pause_for(pause.as_duration());
+        self.update_toggle();
+        self.update_tooltip();
+    }
+
+    /// Callback for the status timer, keeps the remaining pause time up to date and notices when a pause has ended.
+    /// The tray icon also follows the taskbar theme from here, the message window doesn't get the theme change broadcasts.
+    fn refresh_status(&self) {
+        self.controller.lock().unwrap().resume_if_pause_ended();
+        self.update_toggle();
+        self.update_tooltip();
+    }
+
+    /// Runs a pass right away and restarts the countdown
+    fn shift_now(&self) {
+        self.controller.lock().unwrap().shift_now();
+    }
+
+    /// Moves the windows of the last pass back
+    fn undo(&self) {
+        self.controller.lock().unwrap().undo();
+    }
+
+    /// Moves all the windows back to where the user left them
+    fn restore_windows(&self) {
+        self.controller.lock().unwrap().restore_windows();
+    }
+
+    /// Switches to the next profile and tells the user which one it is
+    fn next_profile(&self) {
+        let profile = self.controller.lock().unwrap().next_profile();
+        self.update_delay_menu();
+        self.update_distance_menu();
+        self.update_tooltip();
+
+        let message = match profile {
+            Some(name) => tr_args(Text::MessageProfile, &[&name]),
+            None => tr(Text::MessageNoProfiles).to_string(),
+        };
+        let flags = nwg::TrayNotificationFlags::USER_ICON | nwg::TrayNotificationFlags::LARGE_ICON;
+        self.tray.show(&message, Some("OLEDShift"), Some(flags), Some(&self.icon));
+    }
+
+    /// Runs the action of a global hotkey
+    fn handle_hotkey(&self, action: HotkeyAction) {
+        match action {
+            HotkeyAction::Toggle => self.toggle_enabled(),
+            HotkeyAction::ShiftNow => self.shift_now(),
+            HotkeyAction::Undo => self.undo(),
+            HotkeyAction::PauseFor => {
+                // Pressing it again during the pause ends the pause
+                let paused = self.controller.lock().unwrap().get_pause_remaining().is_some();
+                if paused {
+                    self.toggle_enabled();
+                } else {
+                    self.do_pause(Pauses::FifteenMinutes);
+                }
+            }
+            HotkeyAction::RestoreWindows => self.restore_windows(),
+            HotkeyAction::NextProfile => self.next_profile(),
+        }
+    }
+
+    /// Returns the menu item the action is shown as, along with its text
+    fn hotkey_menu_item(&self, action: HotkeyAction) -> (&nwg::MenuItem, &'static str) {
+        match action {
+            HotkeyAction::Toggle => (&self.enabled_toggle, tr(Text::MenuEnabled)),
+            HotkeyAction::ShiftNow => (&self.shift_now_menu, tr(Text::MenuShiftNow)),
+            HotkeyAction::Undo => (&self.undo_menu, tr(Text::MenuUndo)),
+            HotkeyAction::PauseFor => (&self.pause_15_menu, tr(Text::MenuPauseFifteenMinutes)),
+            HotkeyAction::RestoreWindows => (&self.restore_menu, tr(Text::MenuRestoreWindows)),
+            HotkeyAction::NextProfile => (&self.next_profile_menu, tr(Text::MenuNextProfile)),
+        }
+    }
+
+    /// Registers the hotkeys from the settings and shows them next to their menu items.
+    /// Bindings used twice or already taken by another application are reported to the user.
+    /// The previous bindings are released first, so this can be called again after the settings changed.
+    fn register_hotkeys(&self) {
+        let hwnd = self.window.handle.hwnd().expect("The message window has no handle");
+        for action in HotkeyAction::ALL.iter() {
+            hotkeys::unregister(hwnd, action.id());
+            let (item, text) = self.hotkey_menu_item(*action);
+            set_menu_item_text(item, text);
+        }
+
+        let bindings: Vec<(HotkeyAction, Hotkey)> = {
+            let controller = self.controller.lock().unwrap();
+            HotkeyAction::ALL.iter()
+                .filter_map(|action| {
+                    // Invalid bindings are disabled when the settings are loaded
+                    let hotkey = controller.get_hotkey(*action).and_then(|binding| Hotkey::parse(&binding).ok())?;
+                    return Some((*action, hotkey));
+                })
+                .collect()
+        };
+
+        let mut problems: Vec<String> = Vec::new();
+        let conflicts = hotkeys::find_conflicts(&bindings);
+        for (action, conflict) in conflicts.iter() {
+            problems.push(format!("{}: {}", action.label(), conflict));
+        }
+
+        for (action, hotkey) in bindings.iter() {
+            if conflicts.iter().any(|(conflicting, _)| conflicting == action) {
+                continue;
+            }
+
+            match hotkeys::register(hwnd, action.id(), hotkey) {
+                Ok(()) => {
+                    let (item, text) = self.hotkey_menu_item(*action);
+                    set_menu_item_text(item, &format!("{}\t{}", text, hotkey));
+                }
+                Err(err) => problems.push(format!("{}: {}", action.label(), err)),
+            }
+        }
+
+        if !problems.is_empty() {
+            self.show_hotkey_problems_message(&problems);
+        }
+    }
+
+    /// Lets the user know which hotkeys couldn't be registered
+    fn show_hotkey_problems_message(&self, problems: &[String]) {
+        let flags = nwg::TrayNotificationFlags::WARNING_ICON;
+        let message = problems.join("\n");
+        self.tray.show(&message, Some(tr(Text::HotkeysUnavailableTitle)), Some(flags), None);
+    }
+
     fn hello1(&self) {
         nwg::modal_info_message(&self.window, "Hello", "Hello World!");
     }
 
     fn show_start_message(&self) {
         let flags = nwg::TrayNotificationFlags::USER_ICON | nwg::TrayNotificationFlags::LARGE_ICON;
-        self.tray.show("OLEDShift", Some("OLEDShift is running in the system tray"), Some(flags), Some(&self.icon));
+        self.tray.show("OLEDShift", Some(tr(Text::MessageRunning)), Some(flags), Some(&self.icon));
     }
 
     /// Shows the failed to parse the config file error message
     fn show_config_parse_failed_message(&self, error_message: &str) {
-        let message = format!("Failed to parse the config file!\nThe default settings will be used instead.\n\nError: {}", error_message);
-        nwg::modal_error_message(&self.window, "Config parsing failed", &message);
+        let message = tr_args(Text::ConfigParseFailedMessage, &[&error_message]);
+        nwg::modal_error_message(&self.window, tr(Text::ConfigParseFailedTitle), &message);
     }
 
     fn do_delay(&self, delay: Delays) {
@@ -117,6 +293,42 @@ impl SystemTray {
     /// Updates the toggle menu item to reflect the current state of the controller
     fn update_toggle(&self) {
         self.enabled_toggle.set_checked(self.controller.lock().unwrap().is_running());
+        self.update_icon();
+    }
+
+    /// Switches the tray icon to the variant for the current state and taskbar theme, if it changed
+    fn update_icon(&self) {
+        let state = {
+            let controller = self.controller.lock().unwrap();
+            if controller.has_settings_error() {
+                TrayState::Error
+            } else if !controller.is_running() {
+                TrayState::Paused
+            } else if controller.is_every_monitor_disabled() {
+                TrayState::Disabled
+            } else {
+                TrayState::Running
+            }
+        };
+        let theme = tray_icons::taskbar_theme();
+
+        if self.state_icon_key.get() == Some((state, theme)) {
+            return;
+        }
+
+        let mut icon = nwg::Icon::default();
+        let built = nwg::Icon::builder()
+            .source_bin(Some(tray_icons::icon_for(state, theme)))
+            .build(&mut icon);
+        if let Err(err) = built {
+            log::error!("Failed to build the tray icon: {}", err);
+            return;
+        }
+
+        self.tray.set_icon(&icon);
+        // The tray only references the icon, so it must outlive its use
+        *self.state_icon.borrow_mut() = icon;
+        self.state_icon_key.set(Some((state, theme)));
     }
 
     /// Updates the delay menu item to reflect the current state of the controller
@@ -153,7 +365,11 @@ impl SystemTray {
     fn update_tooltip(&self) {
         let controller = self.controller.lock().unwrap();
 
-        let pause = if controller.is_running() { "running" } else { "paused" };
+        let pause = match controller.get_pause_remaining() {
+            Some(remaining) => tr_args(Text::StatusPausedFor, &[&self.format_remaining(remaining)]),
+            None if controller.is_running() => tr(Text::StatusRunning).to_string(),
+            None => tr(Text::StatusPaused).to_string(),
+        };
         let interval = controller.get_interval();
         let distance = controller.get_max_move();
 
@@ -161,9 +377,10 @@ impl SystemTray {
 
         let delay = self.format_interval(interval);
         let format_distance = self.format_distance(distance.0, distance.1);
-        let tooltip = format!("OLEDShift\nStatus: {}\nDelay: {}\nMax distance: {}", pause, delay, format_distance);
+        let tooltip = tr_args(Text::Tooltip, &[&pause, &delay, &format_distance]);
 
         self.tray.set_tip(&tooltip);
+        self.update_icon();
     }
 
     /// Formats an interval in milliseconds into a human readable string
@@ -171,37 +388,57 @@ impl SystemTray {
         let interval = duration.as_millis() as i32;
 
         match Delays::from_millis(interval) {
-            Delays::ThirtySeconds => return "30 seconds".to_string(),
-            Delays::OneMinute => return "1 minute".to_string(),
-            Delays::TwoMinutes => return "2 minutes".to_string(),
-            Delays::FiveMinutes => return "5 minutes".to_string(),
+            Delays::ThirtySeconds => return i18n::count(Unit::Second, 30),
+            Delays::OneMinute => return i18n::count(Unit::Minute, 1),
+            Delays::TwoMinutes => return i18n::count(Unit::Minute, 2),
+            Delays::FiveMinutes => return i18n::count(Unit::Minute, 5),
             _ => {}
         }
 
-        let seconds = interval / 1000;
+        let seconds = interval as u64 / 1000;
         if seconds < 60 {
-            return format!("{} second{} (Custom)", seconds, if seconds == 1 { "" } else { "s" });
+            return tr_args(Text::Custom, &[&i18n::count(Unit::Second, seconds)]);
         }
 
         let minutes = seconds / 60;
         let seconds = seconds % 60;
         if seconds == 0 {
-            return format!("{} minutes (Custom)", minutes);
+            return tr_args(Text::Custom, &[&i18n::count(Unit::Minute, minutes)]);
+        }
+
+        let both = tr_args(Text::And, &[&i18n::count(Unit::Minute, minutes), &i18n::count(Unit::Second, seconds)]);
+        return tr_args(Text::Custom, &[&both]);
+    }
+
+    /// Formats the time left of a timed pause into a human readable string, rounded up to the minute
+    fn format_remaining(&self, remaining: Duration) -> String {
+        let minutes = (remaining.as_secs() + 59) / 60;
+        if minutes < 60 {
+            return i18n::count(Unit::Minute, minutes);
+        }
+
+        let hours = minutes / 60;
+        let minutes = minutes % 60;
+        if minutes == 0 {
+            return i18n::count(Unit::Hour, hours);
         }
 
-        return format!("{} minute{} and {} second{} (Custom)", minutes, if minutes == 1 { "" } else { "s" }, seconds, if seconds == 1 { "" } else { "s" });
+        return tr_args(Text::And, &[&i18n::count(Unit::Hour, hours), &i18n::count(Unit::Minute, minutes)]);
     }
 
     /// Formats the distance into a human readable string
     fn format_distance(&self, max_x: i32, max_y: i32) -> String {
-        match Distances::from_distance(max_x, max_y) {
-            Distances::Small => return "25 px (Small)".to_string(),
-            Distances::Medium => return "50 px (Medium)".to_string(),
-            Distances::Large => return "100 px (Large)".to_string(),
-            _ => {}
-        }
+        let preset = match Distances::from_distance(max_x, max_y) {
+            Distances::Small => Text::DistanceSmall,
+            Distances::Medium => Text::DistanceMedium,
+            Distances::Large => Text::DistanceLarge,
+            Distances::Custom => {
+                let distance = tr_args(Text::DistanceCustom, &[&max_x, &max_y]);
+                return tr_args(Text::Custom, &[&distance]);
+            }
+        };
 
-        return format!("{} px (x) {} px (y) (Custom)", max_x, max_y);
+        return tr_args(Text::DistancePreset, &[&max_x, &tr(preset)]);
     }
 
     /// Callback for the dialog notice
@@ -244,6 +481,143 @@ impl SystemTray {
         }
     }
 
+    /// Opens the settings window, or does nothing if it's already open
+    fn open_settings(&self) {
+        if self.settings_window_data.borrow().is_some() {
+            return;
+        }
+
+        let (draft, monitors) = {
+            let controller = self.controller.lock().unwrap();
+            let monitors = controller.get_monitors_merged().into_iter()
+                .map(|(device_id, (friendly_name, is_enabled, is_connected))| (device_id, friendly_name, is_enabled, is_connected))
+                .collect();
+            (controller.get_settings(), monitors)
+        };
+
+        *self.settings_window_data.borrow_mut() = Some(SettingsWindow::popup(
+            self.settings_window_notice.sender(),
+            draft,
+            monitors
+        ));
+    }
+
+    /// Callback for the settings window notice
+    fn read_settings_window_output(&self) {
+        let data = self.settings_window_data.borrow_mut().take();
+        match data {
+            Some(handle) => {
+                let window_result = handle.join().unwrap();
+
+                match window_result {
+                    SettingsWindowData::Apply { original, edited } => {
+                        self.controller.lock().unwrap().apply_settings(original, edited);
+                        self.update_delay_menu();
+                        self.update_distance_menu();
+                        self.update_toggle();
+                        self.update_tooltip();
+                        system_tray_ui::update_screens_submenu(self);
+                        self.register_hotkeys();
+                    },
+                    SettingsWindowData::Cancel => {}
+                }
+            },
+            None => {}
+        }
+    }
+
+    /// Opens the statistics window, or does nothing if it's already open
+    fn open_statistics(&self) {
+        if self.statistics_window_data.borrow().is_some() {
+            return;
+        }
+
+        let monitor_names = self.controller.lock().unwrap().get_connected_monitors();
+
+        *self.statistics_window_data.borrow_mut() = Some(StatisticsWindow::popup(
+            self.statistics_window_notice.sender(),
+            monitor_names
+        ));
+    }
+
+    /// Callback for the statistics window notice
+    fn read_statistics_window_output(&self) {
+        if let Some(handle) = self.statistics_window_data.borrow_mut().take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Opens the diagnostics window, or does nothing if it's already open
+    fn open_diagnostics(&self) {
+        if self.diagnostics_window_data.borrow().is_some() {
+            return;
+        }
+
+        let monitor_names = self.controller.lock().unwrap().get_connected_monitors();
+
+        *self.diagnostics_window_data.borrow_mut() = Some(DiagnosticsWindow::popup(
+            self.diagnostics_window_notice.sender(),
+            monitor_names
+        ));
+    }
+
+    /// Callback for the diagnostics window notice
+    fn read_diagnostics_window_output(&self) {
+        if let Some(handle) = self.diagnostics_window_data.borrow_mut().take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Opens the burn-in heatmap window, or does nothing if it's already open
+    fn open_heatmap(&self) {
+        if self.heatmap_window_data.borrow().is_some() {
+            return;
+        }
+
+        let monitor_names = self.controller.lock().unwrap().get_connected_monitors();
+
+        *self.heatmap_window_data.borrow_mut() = Some(HeatmapWindow::popup(
+            self.heatmap_window_notice.sender(),
+            monitor_names
+        ));
+    }
+
+    /// Callback for the heatmap window notice
+    fn read_heatmap_window_output(&self) {
+        if let Some(handle) = self.heatmap_window_data.borrow_mut().take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Opens the folder with the log files in Explorer
+    fn open_log_folder(&self) {
+        let folder = logging::log_folder();
+        if let Err(err) = Command::new("explorer").arg(&folder).spawn() {
+            log::error!("Failed to open the log folder {:?}: {}", folder, err);
+            let flags = nwg::TrayNotificationFlags::ERROR_ICON;
+            self.tray.show(&err.to_string(), Some(tr(Text::OpenFolderFailedTitle)), Some(flags), None);
+        }
+    }
+
+    /// Lets the user know that a second launch was redirected to this instance
+    fn show_already_running_message(&self) {
+        let flags = nwg::TrayNotificationFlags::USER_ICON | nwg::TrayNotificationFlags::LARGE_ICON;
+        self.tray.show("OLEDShift", Some(tr(Text::MessageAlreadyRunning)), Some(flags), Some(&self.icon));
+    }
+
+    /// Callback for the IPC notice, a client changed the controller state behind the UI's back
+    fn read_ipc_changes(&self) {
+        if !ipc::take_activations().is_empty() {
+            self.show_already_running_message();
+        }
+
+        self.update_delay_menu();
+        self.update_distance_menu();
+        self.update_toggle();
+        self.update_tooltip();
+        system_tray_ui::update_screens_submenu(self);
+    }
+
     pub fn handle_monitor_selected(&self, device_id: &str) {
         let mut controller = self.controller.lock().unwrap();
 
@@ -255,31 +629,67 @@ impl SystemTray {
         });
 
         controller.set_monitor_state(device_id, !enabled);
+        drop(controller);
+
+        self.update_icon();
     }
 
     fn exit(&self) {
+        // The desktop isn't a window the user could move back, so it's put back before leaving
+        desktop::close();
+        taskbar::close();
+        dimmer::close();
+        burn_in::close();
+        orbit::close();
+        maximized::close();
         nwg::stop_thread_dispatch();
     }
 }
 
+/// Changes the text of a menu item after it was built, NWG doesn't support that
+fn set_menu_item_text(item: &nwg::MenuItem, text: &str) {
+    let (hmenu, id) = match item.handle.hmenu_item() {
+        Some(handle) => handle,
+        None => return,
+    };
+
+    let mut wide_text: Vec<u16> = OsStr::new(text).encode_wide().chain(std::iter::once(0)).collect();
+
+    let mut info: MENUITEMINFOW = unsafe { mem::zeroed() };
+    info.cbSize = mem::size_of::<MENUITEMINFOW>() as UINT;
+    info.fMask = MIIM_STRING;
+    info.dwTypeData = wide_text.as_mut_ptr();
+
+    unsafe { SetMenuItemInfoW(hmenu, id, FALSE, &info) };
+}
+
 
 //
 // ALL of this stuff is handled by native-windows-derive
 //
 mod system_tray_ui {
-    use crate::controller::{Controller, Delays, Distances};
+    use crate::controller::{Controller, Delays, Distances, Pauses};
+    use crate::hotkeys::{self, HotkeyAction};
+    use crate::i18n::{self, tr, tr_args, Text, Unit};
+    use crate::ipc;
     use crate::settings::SettingsManager;
     use crate::view::{SystemTray, ICON};
     use native_windows_gui as nwg;
     use std::cell::RefCell;
     use std::ops::Deref;
     use std::rc::Rc;
+    use std::time::Duration;
+    use winapi::um::winuser::WM_HOTKEY;
 
     pub struct SystemTrayUi {
         inner: Rc<SystemTray>,
         default_handler: RefCell<Vec<nwg::EventHandler>>,
+        raw_handler: RefCell<Vec<nwg::RawEventHandler>>,
     }
 
+    /// The id of the raw handler that listens for the global hotkeys, ids up to 0xFFFF are reserved by NWG
+    const HOTKEY_HANDLER_ID: usize = 0x10000;
+
     /// Refresh the "Screens" submenu based on the merged monitor info.
     pub fn update_screens_submenu(system_tray: &SystemTray) {
         let merged = {
@@ -292,7 +702,7 @@ mod system_tray_ui {
         for (device_id, (friendly_name, is_enabled, is_connected)) in merged.iter() {
             let menu_item = screens_map.entry(device_id.clone())
                 .or_insert_with(|| {
-                    let text = format!("Monitor - {} ({})", friendly_name, device_id);
+                    let text = tr_args(Text::MenuMonitor, &[friendly_name, device_id]);
                     let mut item = nwg::MenuItem::default();
                     nwg::MenuItem::builder()
                         .text(&text)
@@ -354,34 +764,74 @@ mod system_tray_ui {
                 .build(&mut data.tray_menu)?;
 
             nwg::MenuItem::builder()
-                .text("Enabled")
+                .text(tr(Text::MenuEnabled))
                 .check(true)
                 .parent(&data.tray_menu)
                 .build(&mut data.enabled_toggle)?;
 
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuShiftNow))
+                .parent(&data.tray_menu)
+                .build(&mut data.shift_now_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuUndo))
+                .parent(&data.tray_menu)
+                .build(&mut data.undo_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuRestoreWindows))
+                .parent(&data.tray_menu)
+                .build(&mut data.restore_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuNextProfile))
+                .parent(&data.tray_menu)
+                .build(&mut data.next_profile_menu)?;
+
             nwg::Menu::builder()
-                .text("Delay")
+                .text(tr(Text::MenuPauseFor))
+                .parent(&data.tray_menu)
+                .build(&mut data.pause_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuPauseFifteenMinutes))
+                .parent(&data.pause_menu)
+                .build(&mut data.pause_15_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuPauseOneHour))
+                .parent(&data.pause_menu)
+                .build(&mut data.pause_60_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuPauseUntilTomorrow))
+                .parent(&data.pause_menu)
+                .build(&mut data.pause_tomorrow_menu)?;
+
+            nwg::Menu::builder()
+                .text(tr(Text::MenuDelay))
                 .parent(&data.tray_menu)
                 .build(&mut data.delay_menu)?;
 
             nwg::MenuItem::builder()
-                .text("30 seconds")
+                .text(&i18n::count(Unit::Second, 30))
                 .check(true)
                 .parent(&data.delay_menu)
                 .build(&mut data.delay_30_menu)?;
 
             nwg::MenuItem::builder()
-                .text("1 minute")
+                .text(&i18n::count(Unit::Minute, 1))
                 .parent(&data.delay_menu)
                 .build(&mut data.delay_1_menu)?;
 
             nwg::MenuItem::builder()
-                .text("2 minutes")
+                .text(&i18n::count(Unit::Minute, 2))
                 .parent(&data.delay_menu)
                 .build(&mut data.delay_2_menu)?;
 
             nwg::MenuItem::builder()
-                .text("5 minutes")
+                .text(&i18n::count(Unit::Minute, 5))
                 .parent(&data.delay_menu)
                 .build(&mut data.delay_5_menu)?;
 
@@ -390,27 +840,27 @@ mod system_tray_ui {
                 .build(&mut data.separator_delay)?;
 
             nwg::MenuItem::builder()
-                .text("Custom delay")
+                .text(tr(Text::MenuCustomDelay))
                 .parent(&data.delay_menu)
                 .build(&mut data.delay_custom_menu)?;
 
             nwg::Menu::builder()
-                .text("Max distance")
+                .text(tr(Text::MenuMaxDistance))
                 .parent(&data.tray_menu)
                 .build(&mut data.distance_menu)?;
 
             nwg::MenuItem::builder()
-                .text("Small, 25 px")
+                .text(&tr_args(Text::MenuDistancePreset, &[&tr(Text::DistanceSmall), &(Distances::Small as i32)]))
                 .parent(&data.distance_menu)
                 .build(&mut data.distance_small_menu)?;
 
             nwg::MenuItem::builder()
-                .text("Medium, 50 px")
+                .text(&tr_args(Text::MenuDistancePreset, &[&tr(Text::DistanceMedium), &(Distances::Medium as i32)]))
                 .parent(&data.distance_menu)
                 .build(&mut data.distance_medium_menu)?;
 
             nwg::MenuItem::builder()
-                .text("Large, 100 px")
+                .text(&tr_args(Text::MenuDistancePreset, &[&tr(Text::DistanceLarge), &(Distances::Large as i32)]))
                 .parent(&data.distance_menu)
                 .build(&mut data.distance_large_menu)?;
 
@@ -419,21 +869,46 @@ mod system_tray_ui {
                 .build(&mut data.separator_distance)?;
 
             nwg::MenuItem::builder()
-                .text("Custom distance")
+                .text(tr(Text::MenuCustomDistance))
                 .parent(&data.distance_menu)
                 .build(&mut data.distance_custom_menu)?;
 
             nwg::Menu::builder()
-                .text("Screens")
+                .text(tr(Text::MenuScreens))
                 .parent(&data.tray_menu)
                 .build(&mut data.screen_menu)?;
 
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuSettings))
+                .parent(&data.tray_menu)
+                .build(&mut data.settings_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuStatistics))
+                .parent(&data.tray_menu)
+                .build(&mut data.statistics_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuDiagnostics))
+                .parent(&data.tray_menu)
+                .build(&mut data.diagnostics_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuHeatmap))
+                .parent(&data.tray_menu)
+                .build(&mut data.heatmap_menu)?;
+
+            nwg::MenuItem::builder()
+                .text(tr(Text::MenuOpenLogFolder))
+                .parent(&data.tray_menu)
+                .build(&mut data.log_folder_menu)?;
+
             nwg::MenuSeparator::builder()
                 .parent(&data.tray_menu)
                 .build(&mut data.separator_delay)?;
 
             nwg::MenuItem::builder()
-                .text("Exit")
+                .text(tr(Text::MenuExit))
                 .parent(&data.tray_menu)
                 .build(&mut data.exit_menu)?;
 
@@ -446,10 +921,38 @@ mod system_tray_ui {
                 .parent(&data.window)
                 .build(&mut data.distance_dialog_notice)?;
 
+            nwg::Notice::builder()
+                .parent(&data.window)
+                .build(&mut data.settings_window_notice)?;
+
+            nwg::Notice::builder()
+                .parent(&data.window)
+                .build(&mut data.statistics_window_notice)?;
+
+            nwg::Notice::builder()
+                .parent(&data.window)
+                .build(&mut data.diagnostics_window_notice)?;
+
+            nwg::Notice::builder()
+                .parent(&data.window)
+                .build(&mut data.heatmap_window_notice)?;
+
+            nwg::Notice::builder()
+                .parent(&data.window)
+                .build(&mut data.ipc_notice)?;
+
+            // Refreshes the remaining pause time in the tooltip
+            nwg::AnimationTimer::builder()
+                .parent(&data.window)
+                .interval(Duration::from_secs(30))
+                .active(true)
+                .build(&mut data.status_timer)?;
+
             // Wrap-up
             let ui = SystemTrayUi {
                 inner: Rc::new(data),
                 default_handler: Default::default(),
+                raw_handler: Default::default(),
             };
 
             // Setup the controller
@@ -460,6 +963,18 @@ mod system_tray_ui {
             Controller::set_settings(ui.inner.controller.clone(), settings_manager);
             // Start the controller
             Controller::run(ui.inner.controller.clone());
+            // Start the local control server
+            ipc::start(ui.inner.controller.clone(), ui.inner.ipc_notice.sender());
+            // Start watching for idle periods to protect the taskbar
+            taskbar::start();
+            // Start watching the screen for static regions to dim
+            dimmer::start();
+            // Start recording the burn-in heatmaps
+            burn_in::start();
+            // Start the screen orbit, it only shifts the image once it's turned on
+            orbit::start();
+            // Start watching the nudged maximized windows, to maximize them again once they are used
+            maximized::start();
 
             // Update the UI to reflect the controller state at startup
             ui.inner.update_delay_menu();
@@ -467,6 +982,7 @@ mod system_tray_ui {
             ui.inner.update_toggle();
             ui.inner.update_tooltip();
             update_screens_submenu(&ui.inner);
+            ui.inner.register_hotkeys();
 
             SystemTray::show_start_message(&ui.inner);
 
@@ -482,6 +998,25 @@ mod system_tray_ui {
                             else if &handle == &evt_ui.distance_dialog_notice {
                                 SystemTray::read_distance_dialog_output(&evt_ui);
                             }
+                            else if &handle == &evt_ui.settings_window_notice {
+                                SystemTray::read_settings_window_output(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.statistics_window_notice {
+                                SystemTray::read_statistics_window_output(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.diagnostics_window_notice {
+                                SystemTray::read_diagnostics_window_output(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.heatmap_window_notice {
+                                SystemTray::read_heatmap_window_output(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.ipc_notice {
+                                SystemTray::read_ipc_changes(&evt_ui);
+                            }
+                        E::OnTimerTick =>
+                            if &handle == &evt_ui.status_timer {
+                                SystemTray::refresh_status(&evt_ui);
+                            }
                         E::OnContextMenu =>
                             if &handle == &evt_ui.tray {
                                 SystemTray::show_menu(&evt_ui);
@@ -496,6 +1031,27 @@ mod system_tray_ui {
                             if &handle == &evt_ui.enabled_toggle {
                                 SystemTray::toggle_enabled(&evt_ui);
                             }
+                            else if &handle == &evt_ui.shift_now_menu {
+                                SystemTray::shift_now(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.undo_menu {
+                                SystemTray::undo(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.restore_menu {
+                                SystemTray::restore_windows(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.next_profile_menu {
+                                SystemTray::next_profile(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.pause_15_menu {
+                                SystemTray::do_pause(&evt_ui, Pauses::FifteenMinutes);
+                            }
+                            else if &handle == &evt_ui.pause_60_menu {
+                                SystemTray::do_pause(&evt_ui, Pauses::OneHour);
+                            }
+                            else if &handle == &evt_ui.pause_tomorrow_menu {
+                                SystemTray::do_pause(&evt_ui, Pauses::UntilTomorrow);
+                            }
                             else if &handle == &evt_ui.delay_30_menu {
                                 SystemTray::do_delay(&evt_ui, Delays::ThirtySeconds)
                             }
@@ -523,6 +1079,21 @@ mod system_tray_ui {
                             else if &handle == &evt_ui.distance_custom_menu {
                                 SystemTray::do_distance(&evt_ui, Distances::Custom);
                             }
+                            else if &handle == &evt_ui.settings_menu {
+                                SystemTray::open_settings(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.statistics_menu {
+                                SystemTray::open_statistics(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.diagnostics_menu {
+                                SystemTray::open_diagnostics(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.heatmap_menu {
+                                SystemTray::open_heatmap(&evt_ui);
+                            }
+                            else if &handle == &evt_ui.log_folder_menu {
+                                SystemTray::open_log_folder(&evt_ui);
+                            }
                             else if &handle == &evt_ui.exit_menu {
                                 SystemTray::exit(&evt_ui);
                             }
@@ -547,6 +1118,22 @@ mod system_tray_ui {
                 nwg::full_bind_event_handler(&ui.window.handle, handle_events)
             );
 
+            // Global hotkeys, NWG doesn't translate WM_HOTKEY into an event
+            let hotkey_ui = Rc::downgrade(&ui.inner);
+            let handle_hotkeys = move |_hwnd, msg, w, _l| {
+                if msg == WM_HOTKEY {
+                    if let (Some(hotkey_ui), Some(action)) = (hotkey_ui.upgrade(), HotkeyAction::from_id(w as i32)) {
+                        SystemTray::handle_hotkey(&hotkey_ui, action);
+                    }
+                    return Some(0);
+                }
+                return None;
+            };
+
+            ui.raw_handler.borrow_mut().push(
+                nwg::bind_raw_event_handler(&ui.window.handle, HOTKEY_HANDLER_ID, handle_hotkeys)?
+            );
+
             return Ok(ui);
         }
     }
@@ -558,6 +1145,16 @@ mod system_tray_ui {
             for handler in handlers.drain(0..) {
                 nwg::unbind_event_handler(&handler);
             }
+
+            if let Some(hwnd) = self.window.handle.hwnd() {
+                for action in HotkeyAction::ALL.iter() {
+                    hotkeys::unregister(hwnd, action.id());
+                }
+            }
+            let mut raw_handlers = self.raw_handler.borrow_mut();
+            for handler in raw_handlers.drain(0..) {
+                let _ = nwg::unbind_raw_event_handler(&handler);
+            }
         }
     }
 
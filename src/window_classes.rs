@@ -0,0 +1,43 @@
+// The window classes a pass never moves
+
+/// The class of the overlay windows: the dimmed taskbar, the dimmed static regions and the screen orbit
+pub const OVERLAY_CLASS: &str = "OLEDShiftOverlay";
+
+/// The window classes that should be excluded from being moved
+const CLASS_EXCLUSIONS: [&str; 6] = [
+    "#32768", // OLEDShift right click menu
+    "NarratorHelperWindow", // A small circle/line, more info here: https://github.com/Marko19907/OLEDShift/issues/12
+    "TopLevelWindowForOverflowXamlIsland", // "Hidden Icon Menu", the flyout menu that appears when you click the arrow on the taskbar
+    "Shell_TrayWnd", // Taskbar
+    "Shell_SecondaryTrayWnd", // Taskbar on secondary monitors
+    OVERLAY_CLASS, // Our own overlays, they have to stay exactly where they were put
+];
+
+/// Returns true if windows of the class are never moved
+pub fn is_excluded(class: &str) -> bool {
+    return CLASS_EXCLUSIONS.contains(&class);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlays_are_excluded() {
+        assert!(is_excluded(OVERLAY_CLASS));
+        assert!(is_excluded("OLEDShiftOverlay"));
+    }
+
+    #[test]
+    fn taskbars_are_excluded() {
+        assert!(is_excluded("Shell_TrayWnd"));
+        assert!(is_excluded("Shell_SecondaryTrayWnd"));
+    }
+
+    #[test]
+    fn ordinary_windows_are_not_excluded() {
+        assert!(!is_excluded("Notepad"));
+        assert!(!is_excluded("Chrome_WidgetWin_1"));
+        assert!(!is_excluded("oledshiftoverlay"));
+    }
+}
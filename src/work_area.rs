@@ -0,0 +1,142 @@
+// The part of a monitor the windows are kept within: its work area, clear of an auto-hidden taskbar's edge
+
+use crate::easing::{Position, Rect};
+
+/// The edge of the screen the taskbar is docked to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+/// Where the taskbar is, as reported by the shell
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Taskbar {
+    pub edge: Edge,
+    /// The rect of the taskbar when it's shown, in screen coordinates
+    pub rect: Rect,
+    /// An auto-hidden taskbar isn't left out of the work area, it slides in over the windows on its edge
+    pub auto_hide: bool,
+}
+
+impl Taskbar {
+    /// Returns true if the taskbar is on the monitor
+    fn is_on(&self, monitor: Rect) -> bool {
+        let rect = self.rect;
+        return rect.0 < monitor.2 && monitor.0 < rect.2 && rect.1 < monitor.3 && monitor.1 < rect.3;
+    }
+
+    /// Returns how far the taskbar reaches into the screen from its edge
+    fn thickness(&self) -> i32 {
+        return match self.edge {
+            Edge::Left | Edge::Right => self.rect.2 - self.rect.0,
+            Edge::Top | Edge::Bottom => self.rect.3 - self.rect.1,
+        };
+    }
+}
+
+/// Returns the part of the monitor windows may be moved within: its work area, clear of the taskbar if it's on that monitor.
+/// The work area already leaves out a taskbar that is always shown, an auto-hidden one is cut away from its edge here.
+pub fn usable_area(monitor: Rect, work: Rect, taskbar: Option<&Taskbar>) -> Rect {
+    let mut area = work;
+
+    if let Some(taskbar) = taskbar.filter(|taskbar| taskbar.auto_hide && taskbar.is_on(monitor)) {
+        let thickness = taskbar.thickness();
+        match taskbar.edge {
+            Edge::Left => area.0 = area.0.max(monitor.0 + thickness),
+            Edge::Top => area.1 = area.1.max(monitor.1 + thickness),
+            Edge::Right => area.2 = area.2.min(monitor.2 - thickness),
+            Edge::Bottom => area.3 = area.3.min(monitor.3 - thickness),
+        }
+    }
+
+    return area;
+}
+
+/// Moves the position so that a window of the size stays within the area.
+/// A window larger than the area is lined up with its top left corner, so its title bar stays reachable.
+pub fn clamp((x, y): Position, (width, height): (i32, i32), area: Rect) -> Position {
+    return (
+        x.min(area.2 - width).max(area.0),
+        y.min(area.3 - height).max(area.1),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITOR: Rect = (0, 0, 1920, 1080);
+
+    fn taskbar(edge: Edge, rect: Rect, auto_hide: bool) -> Taskbar {
+        return Taskbar { edge, rect, auto_hide };
+    }
+
+    #[test]
+    fn shown_taskbar_is_already_left_out_of_the_work_area() {
+        let cases = [
+            (taskbar(Edge::Left, (0, 0, 60, 1080), false), (60, 0, 1920, 1080)),
+            (taskbar(Edge::Top, (0, 0, 1920, 48), false), (0, 48, 1920, 1080)),
+            (taskbar(Edge::Right, (1860, 0, 1920, 1080), false), (0, 0, 1860, 1080)),
+            (taskbar(Edge::Bottom, (0, 1032, 1920, 1080), false), (0, 0, 1920, 1032)),
+        ];
+        for (taskbar, work) in cases {
+            assert_eq!(usable_area(MONITOR, work, Some(&taskbar)), work);
+        }
+    }
+
+    #[test]
+    fn auto_hidden_taskbar_is_cut_from_its_edge() {
+        // An auto-hidden taskbar leaves a sliver of 2 pixels out of the work area at most
+        let cases = [
+            (taskbar(Edge::Left, (0, 0, 60, 1080), true), (2, 0, 1920, 1080), (60, 0, 1920, 1080)),
+            (taskbar(Edge::Top, (0, 0, 1920, 48), true), (0, 2, 1920, 1080), (0, 48, 1920, 1080)),
+            (taskbar(Edge::Right, (1860, 0, 1920, 1080), true), (0, 0, 1918, 1080), (0, 0, 1860, 1080)),
+            (taskbar(Edge::Bottom, (0, 1032, 1920, 1080), true), (0, 0, 1920, 1078), (0, 0, 1920, 1032)),
+        ];
+        for (taskbar, work, usable) in cases {
+            assert_eq!(usable_area(MONITOR, work, Some(&taskbar)), usable);
+        }
+    }
+
+    #[test]
+    fn no_taskbar_leaves_the_work_area() {
+        assert_eq!(usable_area(MONITOR, (0, 0, 1920, 1040), None), (0, 0, 1920, 1040));
+    }
+
+    #[test]
+    fn taskbar_on_another_monitor_is_ignored() {
+        let secondary = (1920, 0, 4480, 1440);
+        let bar = taskbar(Edge::Bottom, (0, 1032, 1920, 1080), true);
+        assert_eq!(usable_area(secondary, secondary, Some(&bar)), secondary);
+    }
+
+    #[test]
+    fn secondary_monitor_at_negative_coordinates() {
+        let secondary = (-2560, -360, 0, 1080);
+        let bar = taskbar(Edge::Top, (-2560, -360, 0, -312), true);
+        assert_eq!(usable_area(secondary, secondary, Some(&bar)), (-2560, -312, 0, 1080));
+
+        let area = (-2560, -312, 0, 1080);
+        assert_eq!(clamp((-3000, -400), (800, 600), area), (-2560, -312));
+        assert_eq!(clamp((-500, 700), (800, 600), area), (-800, 480));
+        assert_eq!(clamp((-1200, 100), (800, 600), area), (-1200, 100));
+    }
+
+    #[test]
+    fn clamp_keeps_the_window_within_the_area() {
+        let area = (60, 0, 1920, 1078);
+        assert_eq!(clamp((100, 100), (400, 300), area), (100, 100));
+        assert_eq!(clamp((1900, 1000), (400, 300), area), (1520, 778));
+        assert_eq!(clamp((-50, -50), (400, 300), area), (60, 0));
+    }
+
+    #[test]
+    fn window_larger_than_the_area_keeps_its_top_left_corner_inside() {
+        let area = (60, 48, 1920, 1080);
+        assert_eq!(clamp((500, 500), (3000, 2000), area), (60, 48));
+        assert_eq!(clamp((-500, 100), (3000, 300), area), (60, 100));
+    }
+}